@@ -0,0 +1,278 @@
+#[path = "./tests/attitude.rs"]
+mod tests;
+
+use crate::cubesat;
+use crate::orbit;
+use crate::vector;
+
+use serde::Deserialize;
+
+pub static MAGNETIC_FIELD_EARTH: f64 = 3.12e-5; // [T] at the equator, on the surface
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Face {
+    pub normal: vector::Vector3,
+    pub area: f64, // [m^2]
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Geometry {
+    pub faces: Vec<Face>,
+    #[serde(default = "Geometry::default_vector")]
+    pub center_of_pressure: vector::Vector3, // [m] offset from the center of mass
+    #[serde(default = "Geometry::default_vector")]
+    pub residual_dipole: vector::Vector3, // [A*m^2]
+    #[serde(default = "Geometry::default_drag_coefficient")]
+    pub drag_coefficient: f64,
+    #[serde(default = "Geometry::default_reflectivity")]
+    pub reflectivity: f64, // Specular fraction
+}
+
+impl Geometry {
+    #[allow(unused)]
+    pub fn new(faces: Vec<((f64, f64, f64), f64)>) -> Self {
+        Geometry {
+            faces: faces
+                .iter()
+                .map(|(n, area)| Face {
+                    normal: vector::Vector3::new(n.0, n.1, n.2),
+                    area: *area,
+                })
+                .collect(),
+            center_of_pressure: vector::Vector3::origin(),
+            residual_dipole: vector::Vector3::origin(),
+            drag_coefficient: Geometry::default_drag_coefficient(),
+            reflectivity: Geometry::default_reflectivity(),
+        }
+    }
+
+    pub fn aerodynamic_force(&self, velocity: &vector::Vector3, density: f64) -> vector::Vector3 {
+        // F = -1/2 * rho * Cd * A * v^2 * cos(angle) * v_unit, for faces facing the flow
+        let flow = velocity.unit();
+        let dynamic_pressure = 0.5 * density * velocity.dot(velocity);
+        self.faces
+            .iter()
+            .map(|f| (f.area, f.normal.unit().dot(&flow)))
+            .filter(|(_, cos)| *cos > 0.0)
+            .fold(vector::Vector3::origin(), |force, (area, cos)| {
+                force.sub(&flow.scale(dynamic_pressure * self.drag_coefficient * area * cos))
+            })
+    }
+
+    pub fn solar_radiation_force(&self, sun: &vector::Vector3, pressure: f64) -> vector::Vector3 {
+        // F = -P * A * cos(angle) * ((1 - r) * s + 2 * r * cos(angle) * n), for sunlit faces
+        // The sun vector is the direction of the sunlight, s points towards the sun
+        let s = sun.unit().negative();
+        let r = self.reflectivity;
+        self.faces
+            .iter()
+            .map(|f| (f.area, f.normal.unit()))
+            .filter(|(_, n)| n.dot(&s) > 0.0)
+            .fold(vector::Vector3::origin(), |force, (area, n)| {
+                let cos = n.dot(&s);
+                let direction = s.scale(1.0 - r).add(&n.scale(2.0 * r * cos));
+                force.sub(&direction.scale(pressure * area * cos))
+            })
+    }
+
+    // Default values for deserialization
+    fn default_vector() -> vector::Vector3 {
+        vector::Vector3::origin()
+    }
+    fn default_drag_coefficient() -> f64 {
+        2.2
+    }
+    fn default_reflectivity() -> f64 {
+        0.3
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Torques {
+    pub gravity_gradient: vector::Vector3, // [Nm]
+    pub aerodynamic: vector::Vector3,      // [Nm]
+    pub solar_radiation: vector::Vector3,  // [Nm]
+    pub magnetic: vector::Vector3,         // [Nm]
+}
+
+impl Torques {
+    pub fn total(&self) -> vector::Vector3 {
+        self.gravity_gradient
+            .add(&self.aerodynamic)
+            .add(&self.solar_radiation)
+            .add(&self.magnetic)
+    }
+}
+
+pub fn magnetic_field(pos: &vector::Vector3) -> vector::Vector3 {
+    // Non-tilted dipole, magnetic moment pointing towards the geographic south pole
+    // B = B0 * (R/r)^3 * (3 * (m . r) * r - m)
+    let moment = vector::Vector3::new(0.0, 0.0, -1.0);
+    let r = pos.unit();
+    let factor = MAGNETIC_FIELD_EARTH * (orbit::RADIUS_EARTH / pos.abs()).powi(3);
+    r.scale(3.0 * moment.dot(&r)).sub(&moment).scale(factor)
+}
+
+pub fn gravity_gradient_torque(
+    pos: &vector::Vector3,
+    inertia: &vector::Vector3,
+) -> vector::Vector3 {
+    // T = 3 * mu / r^5 * (r x (I * r)), r in the body frame
+    let r = pos.abs();
+    let i_r = vector::Vector3::new(inertia.x * pos.x, inertia.y * pos.y, inertia.z * pos.z);
+    pos.cross(&i_r).scale(3.0 * orbit::CONST_MU / r.powi(5))
+}
+
+pub fn disturbance_torques(cubesat: &cubesat::CubeSat) -> Torques {
+    // Guards
+    let geometry = cubesat.geometry.as_ref().expect("No geometry is set!");
+    let pos = &cubesat.pos.expect("No position vector is set!");
+    let vel = &cubesat.vel.expect("No velocity vector is set!");
+    let rot = &cubesat.rot.expect("No rotation is set!");
+    let sun = &cubesat.sun.expect("No sun is set!");
+    let arm = &geometry.center_of_pressure;
+
+    // Gravity gradient, only with a known inertia
+    let gravity_gradient = match &cubesat.inertia {
+        Some(inertia) => gravity_gradient_torque(&pos.to_body(rot), inertia),
+        None => vector::Vector3::origin(),
+    };
+
+    // Aerodynamic, non-rotating atmosphere
+    let density = orbit::atmospheric_density(pos.abs() - orbit::RADIUS_EARTH);
+    let aerodynamic = arm.cross(&geometry.aerodynamic_force(&vel.to_body(rot), density));
+
+    // Solar radiation pressure, none in eclipse
+    let solar_radiation = match cubesat.in_eclipse() {
+        true => vector::Vector3::origin(),
+        false => {
+            arm.cross(&geometry.solar_radiation_force(&sun.to_body(rot), orbit::SOLAR_PRESSURE))
+        }
+    };
+
+    // Residual magnetic dipole
+    let magnetic = geometry
+        .residual_dipole
+        .cross(&magnetic_field(pos).to_body(rot));
+
+    Torques {
+        gravity_gradient,
+        aerodynamic,
+        solar_radiation,
+        magnetic,
+    }
+}
+
+pub fn update_momentum_dumping(cubesat: &mut cubesat::CubeSat) -> vector::Vector3 {
+    // Guards
+    let wheels = match &cubesat.reaction_wheels {
+        Some(w) => w,
+        None => return vector::Vector3::origin(),
+    };
+    let magnetorquer = match cubesat.magnetorquer.as_mut() {
+        Some(m) => m,
+        None => return vector::Vector3::origin(),
+    };
+    let now = cubesat.time.as_ref().expect("No time is set!").now;
+    let pos = &cubesat.pos.expect("No position vector is set!");
+    let rot = &cubesat.rot.expect("No rotation is set!");
+
+    // Start and stop dumping based on the most loaded wheel
+    let fraction = wheels
+        .iter()
+        .map(|w| w.momentum().abs() / w.max_momentum)
+        .fold(0.0, f64::max);
+    if !magnetorquer.active && fraction >= magnetorquer.dump_start {
+        magnetorquer.active = true;
+        cubesat
+            .history
+            .log_event(now, "Momentum dumping started".to_string());
+    } else if magnetorquer.active && fraction <= magnetorquer.dump_stop {
+        magnetorquer.active = false;
+        cubesat
+            .history
+            .log_event(now, "Momentum dumping stopped".to_string());
+    }
+    if !magnetorquer.active {
+        return vector::Vector3::origin();
+    }
+
+    // Dipole: m = k * (h x B) / |B|^2, limited per axis
+    let field = magnetic_field(pos).to_body(rot);
+    let momentum = wheels.iter().fold(vector::Vector3::origin(), |h, w| {
+        h.add(&w.axis.unit().scale(w.momentum()))
+    });
+    let max = magnetorquer.max_dipole;
+    let dipole = momentum
+        .cross(&field)
+        .scale(magnetorquer.gain / field.dot(&field));
+    let dipole = vector::Vector3::new(
+        dipole.x.clamp(-max, max),
+        dipole.y.clamp(-max, max),
+        dipole.z.clamp(-max, max),
+    );
+
+    // Torque: T = m x B
+    dipole.cross(&field)
+}
+
+pub fn update_attitude(cubesat: &mut cubesat::CubeSat) {
+    // External torques
+    let mut external = update_momentum_dumping(cubesat);
+    if cubesat.geometry.is_some() {
+        external = external.add(&disturbance_torques(cubesat).total());
+    }
+
+    // Guards
+    let inertia = cubesat.inertia.expect("No inertia is set!");
+    let step = cubesat.time.as_ref().expect("No time is set!").step;
+    let now = cubesat.time.as_ref().expect("No time is set!").now;
+    let rot_vel = cubesat
+        .rot_vel
+        .as_mut()
+        .expect("No rotational velocity is set!");
+    let ang_to_rad = std::f64::consts::PI / 180.0;
+    let omega = rot_vel.scale(ang_to_rad);
+
+    // Rate damping: T = -k * I * w
+    let gain = cubesat.control_gain.unwrap_or(0.0);
+    let command = vector::Vector3::new(
+        -gain * inertia.x * omega.x,
+        -gain * inertia.y * omega.y,
+        -gain * inertia.z * omega.z,
+    );
+
+    // Reaction wheels absorb the commanded torque
+    let mut wheel_torque = vector::Vector3::origin();
+    let mut wheel_momentum = vector::Vector3::origin();
+    if let Some(wheels) = cubesat.reaction_wheels.as_mut() {
+        for wheel in wheels {
+            let axis = wheel.axis.unit();
+            let was_saturated = wheel.saturated;
+            let change = wheel.apply_torque(-command.dot(&axis), step);
+            wheel_torque = wheel_torque.sub(&axis.scale(change));
+            wheel_momentum = wheel_momentum.add(&axis.scale(wheel.momentum()));
+            if wheel.saturated && !was_saturated {
+                let event = format!("Reaction wheel '{}' saturated", wheel.name);
+                cubesat.history.log_event(now, event);
+            }
+        }
+    }
+
+    // Euler's equations: I * w' = T - w x (I * w + h)
+    let body_momentum = vector::Vector3::new(
+        inertia.x * omega.x,
+        inertia.y * omega.y,
+        inertia.z * omega.z,
+    );
+    let gyroscopic = omega.cross(&body_momentum.add(&wheel_momentum));
+    let torque = external.add(&wheel_torque).sub(&gyroscopic);
+    let omega_dot = vector::Vector3::new(
+        torque.x / inertia.x,
+        torque.y / inertia.y,
+        torque.z / inertia.z,
+    );
+
+    // Update rotational velocity
+    *rot_vel = omega.add(&omega_dot.scale(step)).scale(1.0 / ang_to_rad);
+}
@@ -1,43 +1,422 @@
 #[path = "./tests/component.rs"]
 mod tests;
 
+use crate::maneuver;
+use crate::schedule;
 use crate::time;
 use crate::vector;
 
 use serde::Deserialize;
+use serde_repr::Deserialize_repr;
+
+#[derive(Debug, PartialEq, Clone, Copy, Deserialize)]
+pub struct Rectangle {
+    pub corner: vector::Vector3,                   // [m] body frame
+    pub edges: (vector::Vector3, vector::Vector3), // [m] from the corner
+}
+
+impl Rectangle {
+    #[allow(unused)]
+    pub fn new(corner: (f64, f64, f64), a: (f64, f64, f64), b: (f64, f64, f64)) -> Self {
+        Rectangle {
+            corner: vector::Vector3::new(corner.0, corner.1, corner.2),
+            edges: (
+                vector::Vector3::new(a.0, a.1, a.2),
+                vector::Vector3::new(b.0, b.1, b.2),
+            ),
+        }
+    }
+
+    pub fn center(&self) -> vector::Vector3 {
+        self.corner
+            .add(&self.edges.0.scale(0.5))
+            .add(&self.edges.1.scale(0.5))
+    }
+
+    pub fn rotate(&self, pivot: &vector::Vector3, axis: &vector::Vector3, radians: f64) -> Self {
+        Rectangle {
+            corner: pivot.add(&self.corner.sub(pivot).rot_axis(axis, radians)),
+            edges: (
+                self.edges.0.rot_axis(axis, radians),
+                self.edges.1.rot_axis(axis, radians),
+            ),
+        }
+    }
+
+    pub fn intersects(&self, origin: &vector::Vector3, direction: &vector::Vector3) -> bool {
+        // Ray against the plane, then the position within the edges
+        let (a, b) = &self.edges;
+        let normal = a.cross(b);
+        let denominator = direction.dot(&normal);
+        if denominator.abs() < 1e-12 {
+            return false;
+        }
+        let t = self.corner.sub(origin).dot(&normal) / denominator;
+        if t <= 1e-9 {
+            return false;
+        }
+        let hit = origin.add(&direction.scale(t)).sub(&self.corner);
+        let alpha = hit.dot(a) / a.dot(a);
+        let beta = hit.dot(b) / b.dot(b);
+        (0.0..=1.0).contains(&alpha) && (0.0..=1.0).contains(&beta)
+    }
+
+    pub fn shadow_fraction(
+        &self,
+        occluders: &[Rectangle],
+        to_sun: &vector::Vector3,
+        samples: usize,
+    ) -> f64 {
+        // Share of sample points whose ray towards the sun hits another rectangle
+        let (a, b) = &self.edges;
+        let mut shaded = 0;
+        for i in 0..samples {
+            for j in 0..samples {
+                let u = (i as f64 + 0.5) / samples as f64;
+                let v = (j as f64 + 0.5) / samples as f64;
+                let point = self.corner.add(&a.scale(u)).add(&b.scale(v));
+                if occluders.iter().any(|o| o.intersects(&point, to_sun)) {
+                    shaded += 1;
+                }
+            }
+        }
+        shaded as f64 / (samples * samples) as f64
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Shadowing {
+    #[serde(default = "Shadowing::default_bus")]
+    pub bus: Vec<Rectangle>,
+    #[serde(default = "Shadowing::default_samples")]
+    pub samples: usize, // Per panel edge
+}
+
+impl Shadowing {
+    #[allow(unused)]
+    pub fn new(bus: Vec<Rectangle>) -> Self {
+        Shadowing {
+            bus,
+            samples: Shadowing::default_samples(),
+        }
+    }
+
+    // Default values for deserialization
+    fn default_bus() -> Vec<Rectangle> {
+        Vec::new()
+    }
+    fn default_samples() -> usize {
+        8
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Deployment {
+    #[serde(default = "Deployment::default_zero")]
+    pub time: f64, // [s] Deployment time, or earliest time for an event
+    pub event: Option<maneuver::OrbitEvent>,
+    pub hinge: vector::Vector3, // Body frame
+    #[serde(default = "Deployment::default_pivot")]
+    pub pivot: vector::Vector3, // [m] point on the hinge line
+    pub angle: f64,             // [deg] from stowed to deployed
+    #[serde(default = "Deployment::default_zero")]
+    pub duration: f64, // [s]
+
+    // State
+    #[serde(skip)]
+    pub start: Option<f64>,
+    #[serde(skip)]
+    pub previous: Option<(f64, f64)>,
+    #[serde(skip)]
+    pub progress: f64, // [fraction] deployed
+}
+
+impl Deployment {
+    #[allow(unused)]
+    pub fn new(time: f64, hinge: (f64, f64, f64), angle: f64, duration: f64) -> Self {
+        Deployment {
+            time,
+            event: None,
+            hinge: vector::Vector3::new(hinge.0, hinge.1, hinge.2),
+            pivot: vector::Vector3::origin(),
+            angle,
+            duration,
+            start: None,
+            previous: None,
+            progress: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, now: f64, step: f64, current: (f64, f64)) -> Option<&'static str> {
+        // Trigger on time and optionally on the next orbital event
+        if self.start.is_none() {
+            let mut ready = now + 0.5 * step >= self.time;
+            if let Some(event) = self.event {
+                let crossed = match self.previous {
+                    Some(previous) => event.crossed(previous, current),
+                    None => false,
+                };
+                self.previous = Some(current);
+                ready = ready && crossed;
+            }
+            if !ready {
+                return None;
+            }
+            self.start = Some(now);
+        }
+        if self.progress >= 1.0 {
+            return None;
+        }
+
+        // Hinge rotates at a constant rate
+        let start = self.start.expect("No deployment start is set!");
+        let progress = match self.duration > 0.0 {
+            true => ((now - start) / self.duration).min(1.0),
+            false => 1.0,
+        };
+        let started = self.progress == 0.0 && now == start;
+        self.progress = progress;
+        match (started, progress >= 1.0) {
+            (_, true) => Some("deployed"),
+            (true, false) => Some("deployment started"),
+            _ => None,
+        }
+    }
+
+    // Default values for deserialization
+    fn default_zero() -> f64 {
+        0.0
+    }
+    fn default_pivot() -> vector::Vector3 {
+        vector::Vector3::origin()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Tracking {
+    pub axis: vector::Vector3, // Body frame
+    pub max_rate: f64,         // [deg/s]
+    #[serde(default = "Tracking::default_zero")]
+    pub consumption: f64, // [W] while driving
+
+    // State
+    #[serde(default = "Tracking::default_zero")]
+    pub angle: f64, // [deg]
+    #[serde(skip)]
+    pub moving: bool,
+}
+
+impl Tracking {
+    #[allow(unused)]
+    pub fn new(axis: (f64, f64, f64), max_rate: f64, consumption: f64) -> Self {
+        Tracking {
+            axis: vector::Vector3::new(axis.0, axis.1, axis.2),
+            max_rate,
+            consumption,
+            angle: 0.0,
+            moving: false,
+        }
+    }
+
+    pub fn update(&mut self, normal: &vector::Vector3, to_sun: &vector::Vector3, step: f64) {
+        // Best angle about the drive axis, both vectors in the body frame
+        let a = self.axis.unit();
+        let n = normal.sub(&a.scale(a.dot(normal)));
+        let b = a.cross(&n);
+        let target = to_sun.dot(&b).atan2(to_sun.dot(&n)).to_degrees();
+
+        // Shortest way there, limited by the drive rate
+        let difference = (target - self.angle + 540.0).rem_euclid(360.0) - 180.0;
+        let max = self.max_rate * step;
+        let change = difference.clamp(-max, max);
+        self.angle = (self.angle + change).rem_euclid(360.0);
+        self.moving = change.abs() > 1e-9;
+    }
+
+    pub fn power_consumption(&self) -> f64 {
+        match self.moving {
+            true => self.consumption,
+            false => 0.0,
+        }
+    }
+
+    // Default values for deserialization
+    fn default_zero() -> f64 {
+        0.0
+    }
+}
 
 #[derive(Debug, PartialEq, Deserialize)]
 pub struct SolarPanel {
-    pub orientation: vector::Vector3,
-    pub power_generation: f64, // [W]
+    #[serde(default = "SolarPanel::default_name")]
+    pub name: String,
+    pub orientation: vector::Vector3,  // Stowed, body frame
+    pub power_generation: Option<f64>, // [W] Peak, when no cell model is set
+    pub deployment: Option<Deployment>,
+    pub tracking: Option<Tracking>,
+    pub shape: Option<Rectangle>, // Stowed, for self-shadowing
+    #[serde(skip)]
+    pub shadow: f64, // [fraction]
+    #[serde(skip)]
+    pub albedo: (f64, f64), // [W/m^2] Reflected sunlight and Earth infrared
+
+    // Cell model
+    pub area: Option<f64>,       // [m^2]
+    pub efficiency: Option<f64>, // Cell efficiency at 28 °C
+    #[serde(default = "SolarPanel::default_one")]
+    pub packing_factor: f64,
+    #[serde(default = "SolarPanel::default_zero")]
+    pub temperature_coefficient: f64, // [1/K] relative to 28 °C
+    #[serde(default = "SolarPanel::default_temperature")]
+    pub temperature: f64, // [°C]
+    #[serde(default = "SolarPanel::default_zero")]
+    pub degradation: f64, // [fraction] per year
+    #[serde(skip)]
+    pub failed: bool,
 }
 
 impl SolarPanel {
     #[allow(unused)]
     pub fn new(power_generation: f64, x: f64, y: f64, z: f64) -> Self {
         SolarPanel {
+            name: SolarPanel::default_name(),
             orientation: vector::Vector3::new(x, y, z),
-            power_generation,
+            power_generation: Some(power_generation),
+            deployment: None,
+            tracking: None,
+            shape: None,
+            shadow: 0.0,
+            albedo: (0.0, 0.0),
+            area: None,
+            efficiency: None,
+            packing_factor: 1.0,
+            temperature_coefficient: 0.0,
+            temperature: SolarPanel::default_temperature(),
+            degradation: 0.0,
+            failed: false,
+        }
+    }
+
+    #[allow(unused)]
+    pub fn with_cells(x: f64, y: f64, z: f64, area: f64, efficiency: f64) -> Self {
+        let mut panel = SolarPanel::new(0.0, x, y, z);
+        panel.power_generation = None;
+        panel.area = Some(area);
+        panel.efficiency = Some(efficiency);
+        panel
+    }
+
+    pub fn number(panels: &mut [SolarPanel]) {
+        // Unnamed panels get their position as suffix, History columns and faults need unique names
+        if panels.len() < 2 {
+            return;
+        }
+        for (i, panel) in panels.iter_mut().enumerate() {
+            if panel.name == SolarPanel::default_name() {
+                panel.name = format!("{} {}", panel.name, i + 1);
+            }
+        }
+    }
+
+    pub fn deployed(&self) -> bool {
+        match &self.deployment {
+            Some(d) => d.progress >= 1.0,
+            None => true,
+        }
+    }
+
+    pub fn deployed_normal(&self) -> vector::Vector3 {
+        // Stowed orientation turned about the hinge
+        match &self.deployment {
+            Some(d) => self
+                .orientation
+                .rot_axis(&d.hinge, (d.angle * d.progress).to_radians()),
+            None => self.orientation,
+        }
+    }
+
+    pub fn normal(&self) -> vector::Vector3 {
+        // Actual orientation in the body frame, after deployment and tracking
+        let normal = self.deployed_normal();
+        match &self.tracking {
+            Some(t) => normal.rot_axis(&t.axis, t.angle.to_radians()),
+            None => normal,
         }
     }
 
-    pub fn power_generation(&self, rotation: &vector::Vector3, sun: &vector::Vector3) -> f64 {
+    pub fn current_shape(&self) -> Option<Rectangle> {
+        // Turned about the hinge, then about the drive axis through its center
+        let mut shape = self.shape?;
+        if let Some(d) = &self.deployment {
+            shape = shape.rotate(&d.pivot, &d.hinge, (d.angle * d.progress).to_radians());
+        }
+        if let Some(t) = &self.tracking {
+            shape = shape.rotate(&shape.center(), &t.axis, t.angle.to_radians());
+        }
+        Some(shape)
+    }
+
+    pub fn power_consumption(&self) -> f64 {
+        match &self.tracking {
+            Some(t) => t.power_consumption(),
+            None => 0.0,
+        }
+    }
+
+    pub fn peak_power(&self, flux: f64, years: f64) -> f64 {
+        // P = S * A * eff * packing * (1 + k * (T - 28)) * (1 - d)^years
+        if self.failed {
+            return 0.0;
+        }
+        match (self.area, self.efficiency) {
+            (Some(area), Some(efficiency)) => {
+                let temperature = 1.0 + self.temperature_coefficient * (self.temperature - 28.0);
+                let degradation = (1.0 - self.degradation).powf(years);
+                flux * area * efficiency * self.packing_factor * temperature * degradation
+            }
+            _ => self.power_generation.expect("No power generation is set!"),
+        }
+    }
+
+    pub fn has_power_model(&self) -> bool {
+        self.power_generation.is_some() || (self.area.is_some() && self.efficiency.is_some())
+    }
+
+    pub fn power(
+        &self,
+        rotation: &vector::Vector3,
+        sun: &vector::Vector3,
+        flux: f64,
+        years: f64,
+    ) -> f64 {
+        // Cells are covered until the panel is fully deployed
+        if !self.deployed() {
+            return 0.0;
+        }
+
         // Angle
-        let ang_to_rad = std::f64::consts::PI / 180.0;
-        let angle = self
-            .orientation
-            .rot_x(rotation.x * ang_to_rad)
-            .rot_y(rotation.y * ang_to_rad)
-            .rot_z(rotation.z * ang_to_rad)
-            .negative()
-            .angle_to(sun);
+        let angle = self.normal().to_inertial(rotation).negative().angle_to(sun);
 
         if angle >= std::f64::consts::FRAC_PI_2 {
             0.0
         } else {
-            self.power_generation * angle.cos()
+            self.peak_power(flux, years) * angle.cos() * (1.0 - self.shadow)
         }
     }
+
+    // Default values for deserialization
+    fn default_name() -> String {
+        "Solar panel".to_string()
+    }
+    fn default_zero() -> f64 {
+        0.0
+    }
+    fn default_one() -> f64 {
+        1.0
+    }
+    fn default_temperature() -> f64 {
+        28.0
+    }
 }
 
 #[derive(Debug, PartialEq, Copy, Clone, Deserialize)]
@@ -82,6 +461,469 @@ impl Eps {
     }
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[repr(u8)]
+pub enum BrownoutState {
+    Powered,
+    Dead,
+    Booting,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Brownout {
+    #[serde(default = "Brownout::default_zero")]
+    pub threshold: f64, // [%] Loads lose power at or below
+    pub recovery: f64, // [%] Reboot at or above, above the threshold
+    #[serde(default = "Brownout::default_zero")]
+    pub boot_time: f64, // [s]
+    #[serde(default = "Brownout::default_zero")]
+    pub boot_consumption: f64, // [W] While booting
+    pub essential: Option<Vec<String>>, // Loads that stay powered, none if not set
+
+    // State
+    #[serde(skip)]
+    pub dead: Option<f64>, // [s] Since
+    #[serde(skip)]
+    pub booting: Option<f64>, // [s] Since
+    #[serde(skip)]
+    pub dead_time: f64, // [s]
+    #[serde(skip)]
+    pub brownouts: usize,
+}
+
+impl Brownout {
+    #[allow(unused)]
+    pub fn new(threshold: f64, recovery: f64, boot_time: f64, boot_consumption: f64) -> Self {
+        Brownout {
+            threshold,
+            recovery,
+            boot_time,
+            boot_consumption,
+            essential: None,
+            dead: None,
+            booting: None,
+            dead_time: 0.0,
+            brownouts: 0,
+        }
+    }
+
+    pub fn update(&mut self, battery: f64, now: f64, step: f64) -> Vec<String> {
+        // Loads drop out at the threshold and boot again once the battery has recovered
+        let mut events = Vec::new();
+        if self.dead.is_none() && battery <= self.threshold {
+            self.dead = Some(now);
+            self.booting = None;
+            self.brownouts += 1;
+            events.push(format!("Brownout, battery at {battery:.2}%"));
+        } else if self.dead.is_some() && battery >= self.recovery {
+            self.dead = None;
+            self.booting = Some(now);
+            events.push(format!("Reboot started, battery at {battery:.2}%"));
+        }
+        if let Some(start) = self.booting {
+            if now - start + 0.5 * step >= self.boot_time {
+                self.booting = None;
+                events.push("Reboot complete".to_string());
+            }
+        }
+
+        // Time without power for this step
+        if self.dead.is_some() {
+            self.dead_time += step;
+        }
+        events
+    }
+
+    pub fn state(&self) -> BrownoutState {
+        match (self.dead, self.booting) {
+            (Some(_), _) => BrownoutState::Dead,
+            (None, Some(_)) => BrownoutState::Booting,
+            (None, None) => BrownoutState::Powered,
+        }
+    }
+
+    pub fn powers(&self, load: &str) -> bool {
+        // Everything runs when powered, otherwise only the essential loads
+        match self.state() {
+            BrownoutState::Powered => true,
+            _ => self
+                .essential
+                .as_ref()
+                .is_some_and(|e| e.iter().any(|n| n == load)),
+        }
+    }
+
+    // Default values for deserialization
+    fn default_zero() -> f64 {
+        0.0
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Battery {
+    #[serde(default = "Battery::default_one")]
+    pub charge_efficiency: f64,
+    #[serde(default = "Battery::default_one")]
+    pub discharge_efficiency: f64,
+    pub max_charge_rate: Option<f64>,    // [C]
+    pub max_discharge_rate: Option<f64>, // [C]
+    #[serde(default = "Battery::default_curve")]
+    pub voltage_curve: Vec<(f64, f64)>, // ([%] state of charge, [V])
+    #[serde(default = "Battery::default_temperature")]
+    pub temperature: f64, // [°C]
+    #[serde(default = "Battery::default_zero")]
+    pub temperature_coefficient: f64, // [1/K] capacity loss below 20 °C
+    #[serde(default = "Battery::default_zero")]
+    pub fade_per_cycle: f64, // [fraction of capacity] per full equivalent cycle
+
+    // State
+    #[serde(skip)]
+    pub nominal_capacity: Option<f64>, // [Wh]
+    #[serde(skip)]
+    pub throughput: f64, // [Wh]
+    #[serde(skip)]
+    pub power: f64, // [W] at the terminals, last step
+    #[serde(skip)]
+    pub orbit: Option<(f64, f64, f64)>, // (start, max, min state of charge)
+    #[serde(skip)]
+    pub depth_of_discharge: Vec<f64>, // [%] per orbit
+}
+
+impl Battery {
+    #[allow(unused)]
+    pub fn new(charge_efficiency: f64, discharge_efficiency: f64) -> Self {
+        Battery {
+            charge_efficiency,
+            discharge_efficiency,
+            max_charge_rate: None,
+            max_discharge_rate: None,
+            voltage_curve: Vec::new(),
+            temperature: Battery::default_temperature(),
+            temperature_coefficient: 0.0,
+            fade_per_cycle: 0.0,
+            nominal_capacity: None,
+            throughput: 0.0,
+            power: 0.0,
+            orbit: None,
+            depth_of_discharge: Vec::new(),
+        }
+    }
+
+    pub fn cycles(&self) -> f64 {
+        // Full equivalent cycles: one charge and one discharge of the nominal capacity
+        match self.nominal_capacity {
+            Some(c) if c > 0.0 => self.throughput / (2.0 * c),
+            _ => 0.0,
+        }
+    }
+
+    pub fn voltage(&self, state_of_charge: f64) -> Option<f64> {
+        // Linear interpolation, constant outside the curve
+        let curve = &self.voltage_curve;
+        let first = curve.first()?;
+        let last = curve.last()?;
+        if state_of_charge <= first.0 {
+            return Some(first.1);
+        }
+        if state_of_charge >= last.0 {
+            return Some(last.1);
+        }
+        curve.windows(2).find_map(|w| {
+            let ((x0, y0), (x1, y1)) = (w[0], w[1]);
+            match state_of_charge <= x1 {
+                true => Some(y0 + (y1 - y0) * (state_of_charge - x0) / (x1 - x0)),
+                false => None,
+            }
+        })
+    }
+
+    pub fn update(&mut self, eps: &mut Eps, power: f64, timestep: f64) {
+        let nominal = *self.nominal_capacity.get_or_insert(eps.max_charge);
+
+        // C-rate limits: 1 C charges the nominal capacity in one hour
+        let mut power = power;
+        if let Some(rate) = self.max_charge_rate {
+            power = power.min(rate * nominal);
+        }
+        if let Some(rate) = self.max_discharge_rate {
+            power = power.max(-rate * nominal);
+        }
+
+        // Losses on the way in and out
+        let stored = match power >= 0.0 {
+            true => power * self.charge_efficiency,
+            false => power / self.discharge_efficiency,
+        };
+
+        // A cold battery accepts charge up to its reduced capacity
+        let cold = (20.0 - self.temperature).max(0.0);
+        let capacity = eps.max_charge * (1.0 - self.temperature_coefficient * cold).max(0.0);
+        let old = eps.charge;
+        let new = (old + stored * timestep / time::HOUR).clamp(0.0, capacity.max(old));
+        eps.charge = new;
+
+        // Power at the terminals
+        let change = (new - old) * time::HOUR / timestep;
+        self.power = match change >= 0.0 {
+            true => change / self.charge_efficiency,
+            false => change * self.discharge_efficiency,
+        };
+
+        // Capacity fade
+        self.throughput += (new - old).abs();
+        eps.max_charge = nominal * (1.0 - self.fade_per_cycle * self.cycles()).max(0.0);
+        eps.charge = eps.charge.min(eps.max_charge);
+    }
+
+    pub fn update_depth_of_discharge(
+        &mut self,
+        now: f64,
+        step: f64,
+        period: f64,
+        state_of_charge: f64,
+    ) -> Option<f64> {
+        let orbit = self
+            .orbit
+            .get_or_insert((now, state_of_charge, state_of_charge));
+        orbit.1 = orbit.1.max(state_of_charge);
+        orbit.2 = orbit.2.min(state_of_charge);
+
+        // A full orbit has passed
+        if now - orbit.0 + 0.5 * step < period {
+            return None;
+        }
+        let depth = orbit.1 - orbit.2;
+        self.depth_of_discharge.push(depth);
+        self.orbit = Some((now, state_of_charge, state_of_charge));
+        Some(depth)
+    }
+
+    #[allow(unused)]
+    pub fn depth_of_discharge_statistics(&self) -> Option<(f64, f64)> {
+        // (mean, max) over all completed orbits
+        let n = self.depth_of_discharge.len();
+        if n == 0 {
+            return None;
+        }
+        let mean = self.depth_of_discharge.iter().sum::<f64>() / n as f64;
+        let max = self.depth_of_discharge.iter().cloned().fold(0.0, f64::max);
+        Some((mean, max))
+    }
+
+    // Default values for deserialization
+    fn default_one() -> f64 {
+        1.0
+    }
+    fn default_zero() -> f64 {
+        0.0
+    }
+    fn default_temperature() -> f64 {
+        20.0
+    }
+    fn default_curve() -> Vec<(f64, f64)> {
+        Vec::new()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Deserialize_repr)]
+#[repr(u8)]
+pub enum Regulator {
+    Mppt, // Maximum power point tracking
+    Det,  // Direct energy transfer
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Rail {
+    pub name: String,
+    #[serde(default = "Rail::default_efficiency")]
+    pub efficiency: f64, // Converter
+    #[serde(default = "Rail::default_loads")]
+    pub loads: Vec<String>, // Names of the consumers on this rail
+}
+
+impl Rail {
+    #[allow(unused)]
+    pub fn new(name: &str, efficiency: f64, loads: Vec<&str>) -> Self {
+        Rail {
+            name: name.to_string(),
+            efficiency,
+            loads: loads.iter().map(|l| l.to_string()).collect(),
+        }
+    }
+
+    // Default values for deserialization
+    fn default_efficiency() -> f64 {
+        1.0
+    }
+    fn default_loads() -> Vec<String> {
+        Vec::new()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct PowerBus {
+    #[serde(default = "PowerBus::default_regulator")]
+    pub regulator: Regulator,
+    #[serde(default = "PowerBus::default_one")]
+    pub regulator_efficiency: f64,
+    #[serde(default = "PowerBus::default_zero")]
+    pub mismatch: f64, // [fraction] DET array power lost off the maximum power point
+    #[serde(default = "PowerBus::default_rails")]
+    pub rails: Vec<Rail>,
+}
+
+impl PowerBus {
+    #[allow(unused)]
+    pub fn new(regulator: Regulator, regulator_efficiency: f64) -> Self {
+        PowerBus {
+            regulator,
+            regulator_efficiency,
+            mismatch: 0.0,
+            rails: Vec::new(),
+        }
+    }
+
+    pub fn array_power(&self, generation: f64) -> f64 {
+        // Power delivered to the bus by the solar array regulator
+        match self.regulator {
+            Regulator::Mppt => generation * self.regulator_efficiency,
+            Regulator::Det => generation * (1.0 - self.mismatch) * self.regulator_efficiency,
+        }
+    }
+
+    pub fn rail(&self, load: &str) -> Option<&Rail> {
+        self.rails
+            .iter()
+            .find(|r| r.loads.iter().any(|l| l == load))
+    }
+
+    pub fn load_power(&self, load: &str, consumption: f64) -> f64 {
+        // Power drawn from the bus, loads without a rail are fed directly
+        match self.rail(load) {
+            Some(rail) => consumption / rail.efficiency,
+            None => consumption,
+        }
+    }
+
+    // Default values for deserialization
+    fn default_regulator() -> Regulator {
+        Regulator::Mppt
+    }
+    fn default_one() -> f64 {
+        1.0
+    }
+    fn default_zero() -> f64 {
+        0.0
+    }
+    fn default_rails() -> Vec<Rail> {
+        Vec::new()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Energy {
+    pub generated: f64, // [Wh] by the solar panels
+    pub consumed: f64,  // [Wh] by the loads
+    pub stored: f64,    // [Wh] net into the battery
+    pub wasted: f64,    // [Wh] surplus that could not be stored
+    pub unmet: f64,     // [Wh] load the empty battery could not supply
+    pub losses: f64,    // [Wh] regulator and converters
+}
+
+impl Energy {
+    pub fn add(
+        &mut self,
+        generated: f64,
+        consumed: f64,
+        stored: f64,
+        wasted: f64,
+        unmet: f64,
+        losses: f64,
+    ) {
+        self.generated += generated;
+        self.consumed += consumed;
+        self.stored += stored;
+        self.wasted += wasted;
+        self.unmet += unmet;
+        self.losses += losses;
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Deserialize_repr)]
+#[repr(u8)]
+pub enum Mode {
+    Off,
+    Standby,
+    Idle,
+    Active,
+    Transmit,
+}
+
+impl Mode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Off => "OFF",
+            Mode::Standby => "STANDBY",
+            Mode::Idle => "IDLE",
+            Mode::Active => "ACTIVE",
+            Mode::Transmit => "TRANSMIT",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        [
+            Mode::Off,
+            Mode::Standby,
+            Mode::Idle,
+            Mode::Active,
+            Mode::Transmit,
+        ]
+        .into_iter()
+        .find(|m| m.name().eq_ignore_ascii_case(name.trim()))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct OperatingMode {
+    pub mode: Mode,
+    pub consumption: f64, // [W]
+    #[serde(default = "OperatingMode::default_zero")]
+    pub warm_up: f64, // [s] Before the mode is ready and operating
+    pub warm_up_consumption: Option<f64>, // [W] Until ready, the mode consumption if not set
+    #[serde(default = "OperatingMode::default_zero")]
+    pub min_dwell: f64, // [s] Before the mode may be left
+    #[serde(default = "OperatingMode::default_transitions")]
+    pub transitions: Vec<Mode>, // Allowed next modes, any if empty
+    #[serde(skip)]
+    pub time: f64, // [s] Spent in this mode
+    #[serde(skip)]
+    pub energy: f64, // [Wh] Consumed in this mode
+}
+
+impl OperatingMode {
+    #[allow(unused)]
+    pub fn new(mode: Mode, consumption: f64) -> Self {
+        OperatingMode {
+            mode,
+            consumption,
+            warm_up: 0.0,
+            warm_up_consumption: None,
+            min_dwell: 0.0,
+            transitions: Vec::new(),
+            time: 0.0,
+            energy: 0.0,
+        }
+    }
+
+    // Default values for deserialization
+    fn default_zero() -> f64 {
+        0.0
+    }
+    fn default_transitions() -> Vec<Mode> {
+        Vec::new()
+    }
+}
+
 #[derive(Debug, PartialEq, Deserialize)]
 pub struct Component {
     #[serde(default = "Component::default_name")]
@@ -92,6 +934,29 @@ pub struct Component {
     pub consumption_active: Option<f64>,
     pub activation_interval: Option<f64>,
     pub activation_duration: Option<f64>,
+    pub activation_offset: Option<f64>, // [s] From the simulation start
+    pub activation_jitter: Option<f64>, // [s] Largest delay of a cycle
+    pub trigger: Option<schedule::Trigger>,
+    #[serde(skip)]
+    pub commanded: Option<bool>, // Timeline override of the schedule
+    #[serde(skip)]
+    pub forced: bool, // On by the safe-mode policy
+    #[serde(skip)]
+    pub resume: Option<bool>, // Activation before safe mode shed it
+    #[serde(skip)]
+    pub locked: bool, // Stuck in its mode by a fault
+
+    // Mode table, the first mode is the initial one
+    #[serde(default = "Component::default_modes")]
+    pub modes: Vec<OperatingMode>,
+    #[serde(skip)]
+    pub mode: Option<Mode>,
+    #[serde(skip)]
+    pub mode_since: f64, // [s]
+    #[serde(skip)]
+    pub warming: bool, // Current mode not ready yet
+    #[serde(skip)]
+    pub refused: Option<Mode>,
 }
 
 impl Component {
@@ -110,6 +975,204 @@ impl Component {
             consumption_active,
             activation_interval,
             activation_duration,
+            activation_offset: None,
+            activation_jitter: None,
+            trigger: None,
+            commanded: None,
+            forced: false,
+            resume: None,
+            locked: false,
+            modes: Vec::new(),
+            mode: None,
+            mode_since: 0.0,
+            warming: false,
+            refused: None,
+        }
+    }
+
+    #[allow(unused)]
+    pub fn with_mode(mut self, mode: OperatingMode) -> Self {
+        self.modes.push(mode);
+        self
+    }
+
+    #[allow(unused)]
+    pub fn with_offset(mut self, offset: f64, jitter: f64) -> Self {
+        self.activation_offset = Some(offset);
+        self.activation_jitter = Some(jitter);
+        self
+    }
+
+    pub fn jitter(&self, cycle: i64) -> f64 {
+        // Reproducible delay per cycle and component, splitmix64
+        let jitter = match self.activation_jitter {
+            Some(j) if j > 0.0 => j,
+            _ => return 0.0,
+        };
+        let seed = self.name.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |h, b| {
+            (h ^ b as u64).wrapping_mul(0x100_0000_01b3)
+        });
+        let mut x = seed.wrapping_add((cycle as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        x ^= x >> 31;
+        jitter * (x >> 11) as f64 / (1_u64 << 53) as f64
+    }
+
+    pub fn periodic_active(&self, elapsed: f64, step: f64) -> Option<bool> {
+        // Guards
+        let (interval, duration) = match (self.activation_interval, self.activation_duration) {
+            (_, None) => return None,
+            (Some(interval), Some(duration)) => (interval, duration),
+            _ => panic!("Component {self:?} is incorrectly set!"),
+        };
+
+        // Each time step stands for the half step around it, so float residue never misses a cycle
+        let elapsed = elapsed - self.activation_offset.unwrap_or(0.0);
+        let half = 0.5 * step;
+        if elapsed + half < 0.0 {
+            return Some(false);
+        }
+        let cycle = ((elapsed + half) / interval).floor() as i64;
+        let phase = elapsed - (cycle as f64 * interval + self.jitter(cycle));
+        Some(phase >= -half && phase < duration - half)
+    }
+
+    #[allow(unused)]
+    pub fn with_trigger(mut self, trigger: schedule::Trigger) -> Self {
+        self.trigger = Some(trigger);
+        self
+    }
+
+    pub fn has_active_consumption(&self) -> bool {
+        self.consumption_active.is_some() || !self.modes.is_empty()
+    }
+
+    pub fn current_mode(&self) -> Option<&OperatingMode> {
+        let mode = self.mode.or(self.modes.first().map(|m| m.mode))?;
+        self.modes.iter().find(|m| m.mode == mode)
+    }
+
+    pub fn ready(&self, now: f64) -> bool {
+        // Warm-up of the current mode has passed
+        match self.current_mode() {
+            Some(m) => now - self.mode_since >= m.warm_up,
+            None => true,
+        }
+    }
+
+    pub fn set_mode(&mut self, mode: Mode, now: f64) -> Result<(), String> {
+        // Guards
+        let current = self.current_mode().expect("No modes are set!");
+        if current.mode == mode {
+            return Ok(());
+        }
+        if self.locked {
+            return Err(format!("locked in {}", current.mode.name()));
+        }
+        if !self.modes.iter().any(|m| m.mode == mode) {
+            return Err(format!("{} is not in the mode table", mode.name()));
+        }
+        if !current.transitions.is_empty() && !current.transitions.contains(&mode) {
+            return Err(format!(
+                "{} to {} is not allowed",
+                current.mode.name(),
+                mode.name()
+            ));
+        }
+        if now - self.mode_since < current.min_dwell {
+            return Err(format!(
+                "minimum dwell in {} not reached",
+                current.mode.name()
+            ));
+        }
+
+        self.mode = Some(mode);
+        self.mode_since = now;
+        self.refused = None;
+        Ok(())
+    }
+
+    pub fn update_mode(&mut self, now: f64) -> Option<String> {
+        // The mode follows activation between ACTIVE and the initial mode
+        let previous = self.current_mode()?.mode;
+        let mut event = None;
+        if self.active != (previous == Mode::Active) {
+            let target = match self.active {
+                true => Mode::Active,
+                false => self.modes[0].mode,
+            };
+            match self.set_mode(target, now) {
+                Ok(()) if previous != target => {
+                    event = Some(format!(
+                        "Component '{}' {} to {}",
+                        self.name,
+                        previous.name(),
+                        target.name()
+                    ));
+                }
+                Err(reason) if self.refused != Some(target) => {
+                    self.refused = Some(target);
+                    event = Some(format!(
+                        "Component '{}' mode change refused, {reason}",
+                        self.name
+                    ));
+                }
+                _ => (),
+            }
+        }
+
+        // The mode draws its warm-up power until the warm-up has passed
+        let warming = !self.ready(now);
+        if self.warming && !warming {
+            let mode = self.current_mode()?.mode;
+            event = event.or(Some(format!(
+                "Component '{}' ready in {}",
+                self.name,
+                mode.name()
+            )));
+        }
+        self.warming = warming;
+        event
+    }
+
+    pub fn power_off(&mut self, now: f64) {
+        // Unpowered, back in the initial mode with commands forgotten, a locked mode stays
+        if !self.locked && self.mode.is_some() {
+            self.mode = None;
+            self.mode_since = now;
+        }
+        self.active = false;
+        self.commanded = None;
+        self.forced = false;
+        self.resume = None;
+        self.refused = None;
+    }
+
+    pub fn account_mode(&mut self, step: f64) {
+        // Time and energy of the current mode
+        let mode = match self.current_mode() {
+            Some(m) => m.mode,
+            None => return,
+        };
+        let consumption = self.power_consumption();
+        let table = self.modes.iter_mut().find(|m| m.mode == mode).unwrap();
+        table.time += step;
+        table.energy -= consumption * step / time::HOUR;
+    }
+
+    pub fn power_consumption(&self) -> f64 {
+        if let Some(mode) = self.current_mode() {
+            return match self.warming {
+                true => mode.warm_up_consumption.unwrap_or(mode.consumption),
+                false => mode.consumption,
+            };
+        }
+        match self.active {
+            true => self
+                .consumption_active
+                .expect("No active consumption is set!"),
+            false => self.consumption_passive,
         }
     }
 
@@ -117,13 +1180,14 @@ impl Component {
     pub fn print(&self) {
         let name = &self.name;
         let active = self.active;
-        let consumption = if self.active {
-            self.consumption_active
-                .expect("Cno active consumption is set!")
-        } else {
-            self.consumption_passive
-        };
-        println!("\t\tName: {name}, active: {active}, consumption: {consumption} W");
+        let consumption = self.power_consumption();
+        match self.current_mode() {
+            Some(m) => println!(
+                "\t\tName: {name}, mode: {}, consumption: {consumption} W",
+                m.mode.name()
+            ),
+            None => println!("\t\tName: {name}, active: {active}, consumption: {consumption} W"),
+        }
     }
 
     // Default values for deserialization
@@ -133,4 +1197,282 @@ impl Component {
     fn default_active() -> bool {
         false
     }
+    fn default_modes() -> Vec<OperatingMode> {
+        Vec::new()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct ReactionWheel {
+    #[serde(default = "ReactionWheel::default_name")]
+    pub name: String,
+    pub axis: vector::Vector3,
+    pub max_torque: f64,   // [Nm]
+    pub max_momentum: f64, // [Nms]
+    pub inertia: f64,      // [kg*m^2]
+    #[serde(default = "ReactionWheel::default_zero")]
+    pub friction: f64, // [Nm*s/rad]
+    pub consumption_idle: f64, // [W]
+    #[serde(default = "ReactionWheel::default_zero")]
+    pub consumption_speed: f64, // [W*s/rad] Negative like all consumption, scales with |speed|
+    #[serde(default = "ReactionWheel::default_zero")]
+    pub speed: f64, // [rad/s]
+    #[serde(default = "ReactionWheel::default_saturated")]
+    pub saturated: bool,
+}
+
+impl ReactionWheel {
+    #[allow(unused)]
+    pub fn new(
+        name: &str,
+        axis: (f64, f64, f64),
+        max_torque: f64,
+        max_momentum: f64,
+        inertia: f64,
+        consumption_idle: f64,
+    ) -> Self {
+        ReactionWheel {
+            name: name.to_string(),
+            axis: vector::Vector3::new(axis.0, axis.1, axis.2),
+            max_torque,
+            max_momentum,
+            inertia,
+            friction: 0.0,
+            consumption_idle,
+            consumption_speed: 0.0,
+            speed: 0.0,
+            saturated: false,
+        }
+    }
+
+    pub fn momentum(&self) -> f64 {
+        self.inertia * self.speed
+    }
+
+    pub fn apply_torque(&mut self, torque: f64, timestep: f64) -> f64 {
+        // Motor torque is limited, friction always opposes the wheel speed
+        let motor = torque.clamp(-self.max_torque, self.max_torque);
+        let friction = -self.friction * self.speed;
+
+        // New momentum, limited by the maximum wheel speed
+        let old_momentum = self.momentum();
+        let new_momentum = old_momentum + (motor + friction) * timestep;
+        self.saturated = new_momentum.abs() >= self.max_momentum;
+        let new_momentum = new_momentum.clamp(-self.max_momentum, self.max_momentum);
+        self.speed = new_momentum / self.inertia;
+
+        // Actual change of momentum [Nm], the body receives the opposite
+        (new_momentum - old_momentum) / timestep
+    }
+
+    pub fn power_consumption(&self) -> f64 {
+        // Both terms are negative, a faster wheel draws more in either direction
+        self.consumption_idle + self.consumption_speed * self.speed.abs()
+    }
+
+    // Default values for deserialization
+    fn default_name() -> String {
+        "Reaction wheel".to_string()
+    }
+    fn default_zero() -> f64 {
+        0.0
+    }
+    fn default_saturated() -> bool {
+        false
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Magnetorquer {
+    pub max_dipole: f64,         // [A*m^2]
+    pub consumption_active: f64, // [W]
+    pub dump_start: f64,         // [fraction of max wheel momentum]
+    pub dump_stop: f64,          // [fraction of max wheel momentum]
+    #[serde(default = "Magnetorquer::default_gain")]
+    pub gain: f64, // [1/s]
+    #[serde(default = "Magnetorquer::default_active")]
+    pub active: bool,
+}
+
+impl Magnetorquer {
+    #[allow(unused)]
+    pub fn new(max_dipole: f64, consumption_active: f64, dump_start: f64, dump_stop: f64) -> Self {
+        Magnetorquer {
+            max_dipole,
+            consumption_active,
+            dump_start,
+            dump_stop,
+            gain: Magnetorquer::default_gain(),
+            active: false,
+        }
+    }
+
+    pub fn power_consumption(&self) -> f64 {
+        match self.active {
+            true => self.consumption_active,
+            false => 0.0,
+        }
+    }
+
+    // Default values for deserialization
+    fn default_gain() -> f64 {
+        0.01
+    }
+    fn default_active() -> bool {
+        false
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Propulsion {
+    #[serde(default = "Propulsion::default_name")]
+    pub name: String,
+    pub tank_mass: f64,  // [kg] Empty tank
+    pub dry_mass: f64,   // [kg] Thrusters, valves and lines
+    pub propellant: f64, // [kg]
+    pub thrust: f64,     // [N]
+    pub isp: f64,        // [s]
+    #[serde(default = "Propulsion::default_zero")]
+    pub warm_up: f64, // [s]
+    #[serde(default = "Propulsion::default_zero")]
+    pub consumption_heater: f64, // [W]
+    #[serde(default = "Propulsion::default_zero")]
+    pub consumption_valve: f64, // [W]
+    #[serde(skip)]
+    pub heater_start: Option<f64>,
+    #[serde(skip)]
+    pub valve_open: bool,
+}
+
+impl Propulsion {
+    #[allow(unused)]
+    pub fn new(
+        name: &str,
+        tank_mass: f64,
+        dry_mass: f64,
+        propellant: f64,
+        thrust: f64,
+        isp: f64,
+    ) -> Self {
+        Propulsion {
+            name: name.to_string(),
+            tank_mass,
+            dry_mass,
+            propellant,
+            thrust,
+            isp,
+            warm_up: 0.0,
+            consumption_heater: 0.0,
+            consumption_valve: 0.0,
+            heater_start: None,
+            valve_open: false,
+        }
+    }
+
+    #[allow(unused)]
+    pub fn wet_mass(&self) -> f64 {
+        self.tank_mass + self.dry_mass + self.propellant
+    }
+
+    pub fn delta_v(&self, mass: f64) -> f64 {
+        // Rocket equation: dv = Isp * g0 * ln(m0 / (m0 - mp))
+        self.isp * maneuver::G0 * (mass / (mass - self.propellant)).ln()
+    }
+
+    pub fn update_heater(&mut self, now: f64, step: f64, next_burn: Option<f64>) -> bool {
+        // The heater runs from warm-up before the next burn until no burn is pending
+        match next_burn {
+            Some(t) if now + 0.5 * step >= t - self.warm_up => {
+                let start = *self.heater_start.get_or_insert(now);
+                now - start + 0.5 * step >= self.warm_up
+            }
+            _ => {
+                self.heater_start = None;
+                false
+            }
+        }
+    }
+
+    pub fn power_consumption(&self) -> f64 {
+        let mut consumption = 0.0;
+        if self.heater_start.is_some() {
+            consumption += self.consumption_heater;
+        }
+        if self.valve_open {
+            consumption += self.consumption_valve;
+        }
+        consumption
+    }
+
+    // Default values for deserialization
+    fn default_name() -> String {
+        "Propulsion".to_string()
+    }
+    fn default_zero() -> f64 {
+        0.0
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Heater {
+    pub name: String,
+    pub node: String,      // Thermal node that is heated and sensed
+    pub consumption: f64,  // [W]
+    pub setpoint_on: f64,  // [°C] Switch on below
+    pub setpoint_off: f64, // [°C] Switch off above
+    #[serde(skip)]
+    pub on: bool,
+    #[serde(skip)]
+    pub time_on: f64, // [s]
+    #[serde(skip)]
+    pub time_total: f64, // [s]
+}
+
+impl Heater {
+    #[allow(unused)]
+    pub fn new(
+        name: &str,
+        node: &str,
+        consumption: f64,
+        setpoint_on: f64,
+        setpoint_off: f64,
+    ) -> Self {
+        Heater {
+            name: name.to_string(),
+            node: node.to_string(),
+            consumption,
+            setpoint_on,
+            setpoint_off,
+            on: false,
+            time_on: 0.0,
+            time_total: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, temperature: f64, step: f64) {
+        // Thermostat with hysteresis between the two setpoints
+        if self.on && temperature >= self.setpoint_off {
+            self.on = false;
+        } else if !self.on && temperature <= self.setpoint_on {
+            self.on = true;
+        }
+        if self.on {
+            self.time_on += step;
+        }
+        self.time_total += step;
+    }
+
+    pub fn duty_cycle(&self) -> f64 {
+        match self.time_total > 0.0 {
+            true => self.time_on / self.time_total,
+            false => 0.0,
+        }
+    }
+
+    pub fn power_consumption(&self) -> f64 {
+        match self.on {
+            true => self.consumption,
+            false => 0.0,
+        }
+    }
 }
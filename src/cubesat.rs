@@ -1,12 +1,19 @@
 #[path = "./tests/cubesat.rs"]
 mod tests;
 
+use crate::attitude;
 use crate::component;
+use crate::error::CubeSatError;
+use crate::fdir;
+use crate::maneuver;
 use crate::orbit;
+use crate::schedule;
+use crate::thermal;
 use crate::time;
 use crate::vector;
 use std::fs::File;
 use std::io::Write;
+use std::sync::Arc;
 
 use serde::Deserialize;
 
@@ -24,11 +31,28 @@ pub struct CubeSat {
     pub safe_mode: bool,
     #[serde(default = "CubeSat::default_safe_limit")]
     pub safe_limit: Option<f64>,
+    pub safe_mode_policy: Option<schedule::SafeModePolicy>,
+    pub fdir_rules: Option<Vec<fdir::Rule>>,
+    pub faults: Option<Vec<fdir::Fault>>,
+    #[serde(skip)]
+    pub sensor_biases: Vec<(String, f64)>,
 
     // Orbit
     pub orbit_type: Option<orbit::OrbitType>,
     pub orbit_parameters: Option<orbit::OrbitParameters>,
     pub time: Option<time::Time>,
+    pub mass: Option<f64>,       // [kg]
+    pub propellant: Option<f64>, // [kg]
+    pub maneuvers: Option<Vec<maneuver::Maneuver>>,
+    pub transfers: Option<Vec<orbit::TransferPlan>>,
+    pub propulsion: Option<component::Propulsion>,
+    pub solar_radiation_pressure: Option<orbit::SolarRadiationPressure>,
+    pub third_body: Option<orbit::ThirdBody>,
+    pub gravity_field: Option<orbit::GravityFieldParameters>,
+    #[serde(skip)]
+    pub gravity_model: Option<Arc<orbit::GravityField>>,
+    pub albedo: Option<orbit::Albedo>,
+    pub ground_stations: Option<Vec<orbit::GroundStation>>,
 
     // Vectors
     #[serde(default = "CubeSat::default_vector")]
@@ -44,16 +68,40 @@ pub struct CubeSat {
     #[serde(default = "CubeSat::default_sun")]
     pub sun: Option<vector::Vector3>,
 
+    // Attitude
+    pub inertia: Option<vector::Vector3>, // [kg*m^2]
+    pub control_gain: Option<f64>,        // [1/s]
+    pub geometry: Option<attitude::Geometry>,
+
     // Components
     pub solar_panels: Option<Vec<component::SolarPanel>>,
+    pub lifetime: Option<f64>, // [years]
+    pub shadowing: Option<component::Shadowing>,
     pub eps: Option<component::Eps>,
+    pub battery: Option<component::Battery>,
+    pub brownout: Option<component::Brownout>,
+    pub power_bus: Option<component::PowerBus>,
+    #[serde(skip)]
+    pub energy: component::Energy,
     pub components: Option<Vec<component::Component>>,
+    pub reaction_wheels: Option<Vec<component::ReactionWheel>>,
+    pub magnetorquer: Option<component::Magnetorquer>,
+    pub timeline: Option<schedule::Timeline>,
+
+    // Thermal
+    pub thermal: Option<thermal::Thermal>,
+    pub heaters: Option<Vec<component::Heater>>,
 }
 
 impl CubeSat {
-    pub fn from_toml(path: &str) -> Self {
-        let file = std::fs::read_to_string(path).unwrap();
-        toml::from_str(&file).unwrap()
+    pub fn from_toml(path: &str) -> Result<Self, CubeSatError> {
+        let file = std::fs::read_to_string(path).map_err(|e| CubeSatError::io(path, &e))?;
+        let mut cubesat: CubeSat =
+            toml::from_str(&file).map_err(|e| CubeSatError::toml(path, &file, &e))?;
+        if let Some(panels) = cubesat.solar_panels.as_mut() {
+            component::SolarPanel::number(panels);
+        }
+        Ok(cubesat)
     }
 
     #[allow(unused)]
@@ -64,35 +112,68 @@ impl CubeSat {
             history: History::new(),
             safe_mode: false,
             safe_limit: None,
+            safe_mode_policy: None,
+            fdir_rules: None,
+            faults: None,
+            sensor_biases: Vec::new(),
             orbit_type: None,
             orbit_parameters: None,
             time: None,
+            mass: None,
+            propellant: None,
+            maneuvers: None,
+            transfers: None,
+            propulsion: None,
+            solar_radiation_pressure: None,
+            third_body: None,
+            gravity_field: None,
+            gravity_model: None,
+            albedo: None,
+            ground_stations: None,
             pos: None,
             vel: None,
             acc: None,
             rot: None,
             rot_vel: None,
             sun: None,
+            inertia: None,
+            control_gain: None,
+            geometry: None,
             solar_panels: None,
+            lifetime: None,
+            shadowing: None,
             eps: None,
+            battery: None,
+            brownout: None,
+            power_bus: None,
+            energy: component::Energy::default(),
             components: None,
+            reaction_wheels: None,
+            magnetorquer: None,
+            timeline: None,
+            thermal: None,
+            heaters: None,
         }
     }
 
+    #[allow(unused)]
     pub fn with_name(mut self, name: &str) -> Self {
         self.name = Some(name.to_string());
         self
     }
 
+    #[allow(unused)]
     pub fn with_time(mut self, start: f64, end: f64, step: f64) -> Self {
         self.time = Some(time::Time::new(start, end, step));
         self
     }
 
+    #[allow(unused)]
     pub fn with_orbit_type(mut self, orbit_type: &str) -> Self {
         match orbit_type {
             "circular cosine" => self.orbit_type = Some(orbit::OrbitType::CircularCosine),
             "parametric" => self.orbit_type = Some(orbit::OrbitType::Parametric),
+            "numerical" => self.orbit_type = Some(orbit::OrbitType::Numerical),
             t => {
                 self.orbit_type = None;
                 println!("{t} is not a valid orbit type!");
@@ -101,6 +182,7 @@ impl CubeSat {
         self
     }
 
+    #[allow(unused)]
     pub fn with_orbit_parameters(mut self, orbit_parameters: Vec<(&str, f64)>) -> Self {
         let mut parameters = orbit::OrbitParameters::new();
         for p in orbit_parameters {
@@ -120,36 +202,128 @@ impl CubeSat {
         self
     }
 
+    #[allow(unused)]
+    pub fn with_mass(mut self, mass: f64) -> Self {
+        self.mass = Some(mass);
+        self
+    }
+
+    #[allow(unused)]
+    pub fn with_propellant(mut self, propellant: f64) -> Self {
+        self.propellant = Some(propellant);
+        self
+    }
+
+    #[allow(unused)]
+    pub fn with_maneuver(mut self, maneuver: maneuver::Maneuver) -> Self {
+        match self.maneuvers {
+            Some(ref mut m) => m.push(maneuver),
+            None => self.maneuvers = Some(vec![maneuver]),
+        }
+        self
+    }
+
+    #[allow(unused)]
+    pub fn with_propulsion(
+        mut self,
+        tank_mass: f64,
+        dry_mass: f64,
+        propellant: f64,
+        thrust: f64,
+        isp: f64,
+    ) -> Self {
+        self.propulsion = Some(component::Propulsion::new(
+            "Propulsion",
+            tank_mass,
+            dry_mass,
+            propellant,
+            thrust,
+            isp,
+        ));
+        self
+    }
+
+    #[allow(unused)]
+    pub fn with_transfer(mut self, transfer: &orbit::Transfer, name: &str, start: f64) -> Self {
+        for maneuver in transfer.to_maneuvers(name, start) {
+            self = self.with_maneuver(maneuver);
+        }
+        self
+    }
+
+    #[allow(unused)]
+    pub fn with_transfer_plan(mut self, plan: orbit::TransferPlan) -> Self {
+        match self.transfers {
+            Some(ref mut t) => t.push(plan),
+            None => self.transfers = Some(vec![plan]),
+        }
+        self
+    }
+
+    #[allow(unused)]
+    pub fn with_solar_radiation_pressure(
+        mut self,
+        model: orbit::SrpModel,
+        area: f64,
+        reflectivity: f64,
+    ) -> Self {
+        self.solar_radiation_pressure = Some(orbit::SolarRadiationPressure {
+            model,
+            area,
+            reflectivity,
+        });
+        self
+    }
+
+    #[allow(unused)]
+    pub fn with_third_body(mut self, sun: bool, moon: bool) -> Self {
+        self.third_body = Some(orbit::ThirdBody { sun, moon });
+        self
+    }
+
+    #[allow(unused)]
+    pub fn with_gravity_field(mut self, field: orbit::GravityField) -> Self {
+        self.gravity_model = Some(Arc::new(field));
+        self
+    }
+
+    #[allow(unused)]
     pub fn with_position(mut self, x: f64, y: f64, z: f64) -> Self {
         self.pos = Some(vector::Vector3::new(x, y, z));
         self
     }
 
+    #[allow(unused)]
     pub fn with_velocity(mut self, x: f64, y: f64, z: f64) -> Self {
         self.vel = Some(vector::Vector3::new(x, y, z));
         self
     }
 
+    #[allow(unused)]
     pub fn with_acceleration(mut self, x: f64, y: f64, z: f64) -> Self {
         self.acc = Some(vector::Vector3::new(x, y, z));
         self
     }
 
+    #[allow(unused)]
     pub fn with_rotation(mut self, x: f64, y: f64, z: f64) -> Self {
         self.rot = Some(vector::Vector3::new(x, y, z));
         self
     }
 
+    #[allow(unused)]
     pub fn with_rotation_velocity(mut self, x: f64, y: f64, z: f64) -> Self {
         self.rot_vel = Some(vector::Vector3::new(x, y, z));
         self
     }
 
+    #[allow(unused)]
     pub fn with_sun(mut self, x: f64, y: f64, z: f64) -> Self {
         self.sun = Some(vector::Vector3::new(x, y, z));
         self
     }
 
+    #[allow(unused)]
     pub fn with_solar_panels(
         mut self,
         orientations: Vec<(f64, f64, f64)>,
@@ -165,15 +339,33 @@ impl CubeSat {
             );
             solar_panels.push(panel);
         }
+        component::SolarPanel::number(&mut solar_panels);
         self.solar_panels = Some(solar_panels);
         self
     }
 
+    #[allow(unused)]
     pub fn with_eps(mut self, power_consumption: f64, max_charge: f64) -> Self {
         self.eps = Some(component::Eps::new(power_consumption, max_charge));
         self
     }
 
+    #[allow(unused)]
+    pub fn with_battery(mut self, charge_efficiency: f64, discharge_efficiency: f64) -> Self {
+        self.battery = Some(component::Battery::new(
+            charge_efficiency,
+            discharge_efficiency,
+        ));
+        self
+    }
+
+    #[allow(unused)]
+    pub fn with_power_bus(mut self, power_bus: component::PowerBus) -> Self {
+        self.power_bus = Some(power_bus);
+        self
+    }
+
+    #[allow(unused)]
     pub fn with_component(
         mut self,
         name: &str,
@@ -203,55 +395,285 @@ impl CubeSat {
         self
     }
 
+    #[allow(unused)]
     pub fn with_safety_limit(mut self, limit: f64) -> Self {
         self.safe_limit = Some(limit);
         self
     }
 
+    #[allow(unused)]
+    pub fn with_inertia(mut self, x: f64, y: f64, z: f64) -> Self {
+        self.inertia = Some(vector::Vector3::new(x, y, z));
+        self
+    }
+
+    #[allow(unused)]
+    pub fn with_control_gain(mut self, gain: f64) -> Self {
+        self.control_gain = Some(gain);
+        self
+    }
+
+    #[allow(unused)]
+    pub fn with_geometry(mut self, faces: Vec<((f64, f64, f64), f64)>) -> Self {
+        self.geometry = Some(attitude::Geometry::new(faces));
+        self
+    }
+
+    #[allow(unused)]
+    pub fn with_reaction_wheel(
+        mut self,
+        name: &str,
+        axis: (f64, f64, f64),
+        max_torque: f64,
+        max_momentum: f64,
+        inertia: f64,
+        consumption_idle: f64,
+    ) -> Self {
+        let wheel = component::ReactionWheel::new(
+            name,
+            axis,
+            max_torque,
+            max_momentum,
+            inertia,
+            consumption_idle,
+        );
+        match self.reaction_wheels {
+            Some(ref mut w) => w.push(wheel),
+            None => self.reaction_wheels = Some(vec![wheel]),
+        }
+        self
+    }
+
+    #[allow(unused)]
+    pub fn with_magnetorquer(
+        mut self,
+        max_dipole: f64,
+        consumption_active: f64,
+        dump_start: f64,
+        dump_stop: f64,
+    ) -> Self {
+        self.magnetorquer = Some(component::Magnetorquer::new(
+            max_dipole,
+            consumption_active,
+            dump_start,
+            dump_stop,
+        ));
+        self
+    }
+
+    #[allow(unused)]
+    pub fn with_thermal(mut self, thermal: thermal::Thermal) -> Self {
+        self.thermal = Some(thermal);
+        self
+    }
+
+    #[allow(unused)]
+    pub fn with_heater(
+        mut self,
+        name: &str,
+        node: &str,
+        consumption: f64,
+        setpoint_on: f64,
+        setpoint_off: f64,
+    ) -> Self {
+        let heater = component::Heater::new(name, node, consumption, setpoint_on, setpoint_off);
+        match self.heaters {
+            Some(ref mut h) => h.push(heater),
+            None => self.heaters = Some(vec![heater]),
+        }
+        self
+    }
+
     pub fn update_active_components(&mut self, time: f64, safe_mode: bool) {
+        // Guards
+        let clock = self.time.expect("No time is set!");
+
+        // Triggers are evaluated on the current state before any component changes,
+        // orbital conditions need a position and the default origin is none yet
+        let located = self.pos.is_some_and(|p| p != vector::Vector3::origin());
+        let states: Vec<Option<bool>> = self
+            .components
+            .as_ref()
+            .expect("No components are set!")
+            .iter()
+            .map(|c| {
+                c.trigger
+                    .as_ref()
+                    .filter(|_| located)
+                    .map(|t| t.evaluate(self))
+            })
+            .collect();
+
+        let policy = self.safe_mode_policy.clone();
+        let brownout = self.brownout.as_ref();
         let components = self.components.as_mut().expect("No components are set!");
-        for component in components {
-            match (component.activation_interval, component.activation_duration) {
-                // Component cannot be active
-                (_, None) => continue,
-                // Component can be active
-                (Some(interval), Some(duration)) => {
-                    // Activate component
-                    if time % interval == 0.0 && !safe_mode {
-                        component.active = true;
-                    }
-                    // Deactivate component
-                    if time % interval >= duration || safe_mode {
-                        component.active = false;
-                    }
+        for (component, state) in components.iter_mut().zip(states) {
+            let periodic = component.periodic_active(time - clock.start, clock.step);
+            let triggered = match (component.trigger.as_mut(), state) {
+                (Some(trigger), Some(state)) => Some(trigger.update(time, state)),
+                _ => None,
+            };
+
+            // Safe mode sheds or forces components on, all are shed without a policy
+            let (shed, forced) = match &policy {
+                Some(p) => (
+                    safe_mode && p.sheds(&component.name),
+                    safe_mode && p.forces(&component.name),
+                ),
+                None => (safe_mode, false),
+            };
+
+            // Either schedule activates the component
+            if periodic.is_some() || triggered.is_some() {
+                component.active = periodic == Some(true) || triggered == Some(true);
+            }
+
+            // Timeline commands override the schedule
+            if let Some(commanded) = component.commanded {
+                component.active = commanded;
+            }
+            // Forced components return to off unless something else drives them
+            let driven = periodic.is_some() || triggered.is_some() || component.commanded.is_some();
+            if forced {
+                component.active = true;
+            } else if component.forced && !driven {
+                component.active = false;
+            }
+            component.forced = forced;
+
+            // Shed components are off however they are scheduled, undriven ones come back after
+            if shed && !forced {
+                component.resume.get_or_insert(component.active);
+                component.active = false;
+            } else if let Some(active) = component.resume.take() {
+                if !driven && !forced {
+                    component.active = active;
                 }
-                // Component is incorrectly set
-                _ => {
-                    panic!("Component {component:?} is incorrectly set!");
+            }
+
+            // Nothing switches on without power
+            if brownout.is_some_and(|b| !b.powers(&component.name)) {
+                component.active = false;
+            }
+        }
+    }
+
+    pub fn apply_commands(&mut self) {
+        // Guards
+        let time = self.time.expect("No time is set!");
+        let commands = match self.timeline.as_mut() {
+            Some(t) => t.due(time.now, time.step),
+            None => return,
+        };
+
+        for command in commands {
+            let event = match self.apply_command(&command, time.now) {
+                Ok(()) => format!(
+                    "Command '{}' to '{}'",
+                    command.describe(),
+                    command.component
+                ),
+                Err(reason) => format!(
+                    "Command '{}' to '{}' refused, {reason}",
+                    command.describe(),
+                    command.component
+                ),
+            };
+            self.history.log_event(time.now, event);
+        }
+    }
+
+    pub fn apply_command(&mut self, command: &schedule::Command, now: f64) -> Result<(), String> {
+        let component = self
+            .components
+            .as_mut()
+            .and_then(|c| c.iter_mut().find(|c| c.name == command.component))
+            .ok_or("unknown component")?;
+        if self
+            .brownout
+            .as_ref()
+            .is_some_and(|b| !b.powers(&component.name))
+        {
+            return Err("no power".to_string());
+        }
+
+        match command.command {
+            schedule::CommandKind::On => {
+                // Commands loaded from a file are not seen by the validation
+                if !component.has_active_consumption() {
+                    return Err("no active consumption".to_string());
+                }
+                component.commanded = Some(true);
+            }
+            schedule::CommandKind::Off => component.commanded = Some(false),
+            schedule::CommandKind::Auto => component.commanded = None,
+            schedule::CommandKind::Mode => {
+                if component.modes.is_empty() {
+                    return Err("no mode table".to_string());
                 }
+                let mode = command
+                    .arguments
+                    .first()
+                    .and_then(|a| component::Mode::parse(a))
+                    .ok_or("unknown mode")?;
+                // Held like an on or off command until the next command or auto
+                component.set_mode(mode, now)?;
+                component.active = mode == component::Mode::Active;
+                component.commanded = Some(component.active);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn update_modes(&mut self) {
+        // Guards
+        let time = self.time.expect("No time is set!");
+        let components = match self.components.as_mut() {
+            Some(c) => c,
+            None => return,
+        };
+
+        for component in components {
+            if let Some(event) = component.update_mode(time.now) {
+                self.history.log_event(time.now, event);
+            }
+            // Unpowered components draw nothing in any mode
+            if self
+                .brownout
+                .as_ref()
+                .is_none_or(|b| b.powers(&component.name))
+            {
+                component.account_mode(time.step);
             }
         }
     }
 
+    pub fn quantity(&self, name: &str) -> Option<f64> {
+        // Built-in quantities, otherwise the latest value of a History column, as measured
+        let bias: f64 = self
+            .sensor_biases
+            .iter()
+            .filter(|(quantity, _)| quantity == name)
+            .map(|(_, b)| b)
+            .sum();
+        let value = match name {
+            "time" => self.time.map(|t| t.now),
+            "battery" => self.eps.map(|_| self.battery_percentage()),
+            "charge" => self.eps.map(|e| e.charge),
+            "altitude" => self.pos.map(|p| p.abs() - orbit::RADIUS_EARTH),
+            "rate" => self.rot_vel.map(|w| w.abs()),
+            "safe mode" => Some(self.safe_mode as u8 as f64),
+            _ => self.history.latest(name),
+        };
+        value.map(|v| v + bias)
+    }
+
     pub fn in_eclipse(&self) -> bool {
         // Guards
         let sun = &self.sun.expect("No sun is set!");
         let pos = &self.pos.expect("No position vector is set!");
 
-        // The conditions are:
-        // 1. 0 <= |pos| * sin(acos(( dot(pos,sun)) / (|pos|*|sun|) ))  <= RADIUS_EARTH
-        // 2. Angle between position and sun vectors is less than PI/2
-        let pos_dot_sun = pos.dot(sun);
-        let pos_times_sun = pos.abs() * sun.abs();
-        let inner = pos_dot_sun / pos_times_sun;
-
-        // Evaluate angle
-        let angle = inner.acos();
-        assert!(!angle.is_nan(), "angle is NaN!");
-
-        // Evaluate result
-        let result = pos.abs() * angle.sin();
-        (0.0 <= result && result <= orbit::RADIUS_EARTH) && (angle < std::f64::consts::FRAC_PI_2)
+        orbit::in_eclipse(pos, sun)
     }
 
     pub fn get_power_generation(&self) -> f64 {
@@ -263,33 +685,139 @@ impl CubeSat {
         let sun = &self.sun.expect("No sun is set!");
         let rotation = &self.rot.expect("No rotation is set!");
 
-        // In eclipse, no power generation
-        if self.in_eclipse() {
+        // Solar flux scales with the distance to the sun, cells degrade over the mission
+        let (flux, years) = match &self.time {
+            Some(t) => (self.solar_flux(), t.now / time::YEAR),
+            None => (orbit::SOLAR_CONSTANT, 0.0),
+        };
+
+        // Direct sunlight, none in eclipse
+        let direct: f64 = match self.in_eclipse() {
+            true => 0.0,
+            false => panels
+                .iter()
+                .map(|p| p.power(rotation, sun, flux, years))
+                .sum(),
+        };
+
+        // Sunlight reflected by the Earth
+        direct + self.get_albedo_power()
+    }
+
+    pub fn get_albedo_power(&self) -> f64 {
+        // Guards
+        if self.albedo.is_none() {
             return 0.0;
         }
+        let panels = self
+            .solar_panels
+            .as_ref()
+            .expect("No solar panels are set!");
+        let years = self.time.as_ref().expect("No time is set!").now / time::YEAR;
 
-        // In sun
+        // Cells convert reflected sunlight like direct sunlight, Earth infrared is out of band
         panels
             .iter()
-            .map(|p| p.power_generation(rotation, sun))
+            .filter(|p| p.deployed())
+            .map(|p| {
+                let peak = p.peak_power(orbit::SOLAR_CONSTANT, years);
+                peak * p.albedo.0 / orbit::SOLAR_CONSTANT * (1.0 - p.shadow)
+            })
             .sum()
     }
 
-    pub fn get_power_consumption(&self) -> f64 {
-        let mut consumption = 0.0;
+    pub fn update_albedo(&mut self) {
+        // Guards
+        let albedo = match &self.albedo {
+            Some(a) => a,
+            None => return,
+        };
+        let flux = self.solar_flux();
+        let panels = match self.solar_panels.as_mut() {
+            Some(p) => p,
+            None => return,
+        };
+        let time = self.time.as_ref().expect("No time is set!");
+        let pos = &self.pos.expect("No position vector is set!");
+        let rotation = &self.rot.expect("No rotation is set!");
+        let to_sun = self.sun.expect("No sun is set!").negative();
+        let julian_date = time.julian_date(time.now);
+
+        // Irradiance from the Earth on each panel, once per step
+        for panel in panels.iter_mut() {
+            let normal = panel.normal().to_inertial(rotation);
+            panel.albedo = albedo.irradiance(pos, &normal, &to_sun, flux, julian_date);
+        }
+    }
+
+    pub fn solar_flux(&self) -> f64 {
+        // S = S0 * (1 AU / d)^2
+        let time = self.time.as_ref().expect("No time is set!");
+        let distance = orbit::sun_position(time.julian_date(time.now)).abs();
+        orbit::SOLAR_CONSTANT * (orbit::ASTRONOMICAL_UNIT / distance).powi(2)
+    }
+
+    pub fn peak_power(&self, years: f64) -> f64 {
+        // All panels facing the sun at 1 AU
+        match &self.solar_panels {
+            Some(panels) => panels
+                .iter()
+                .map(|p| p.peak_power(orbit::SOLAR_CONSTANT, years))
+                .sum(),
+            None => 0.0,
+        }
+    }
+
+    pub fn get_power_consumers(&self) -> Vec<(String, f64)> {
+        // Name and power consumption of every load
+        let mut consumers = Vec::new();
         if let Some(eps) = &self.eps {
-            consumption += eps.consumption;
+            consumers.push(("EPS".to_string(), eps.consumption));
         }
         if let Some(components) = &self.components {
-            consumption += components
-                .iter()
-                .map(|c| match c.active {
-                    true => c.consumption_active.expect("No active consumption is set!"),
-                    false => c.consumption_passive,
-                })
-                .sum::<f64>();
+            for c in components {
+                consumers.push((c.name.clone(), c.power_consumption()));
+            }
+        }
+        if let Some(wheels) = &self.reaction_wheels {
+            for w in wheels {
+                consumers.push((w.name.clone(), w.power_consumption()));
+            }
+        }
+        if let Some(magnetorquer) = &self.magnetorquer {
+            consumers.push(("Magnetorquer".to_string(), magnetorquer.power_consumption()));
+        }
+        if let Some(maneuvers) = &self.maneuvers {
+            for m in maneuvers {
+                consumers.push((m.name.clone(), m.power_consumption()));
+            }
+        }
+        if let Some(propulsion) = &self.propulsion {
+            consumers.push((propulsion.name.clone(), propulsion.power_consumption()));
+        }
+        if let Some(panels) = &self.solar_panels {
+            for p in panels.iter().filter(|p| p.tracking.is_some()) {
+                consumers.push((p.name.clone(), p.power_consumption()));
+            }
         }
-        consumption
+        if let Some(heaters) = &self.heaters {
+            for h in heaters {
+                consumers.push((h.name.clone(), h.power_consumption()));
+            }
+        }
+
+        // Brownout leaves only the essential loads and the boot sequence
+        if let Some(brownout) = &self.brownout {
+            consumers.retain(|(name, _)| brownout.powers(name));
+            if brownout.state() == component::BrownoutState::Booting {
+                consumers.push(("Boot".to_string(), brownout.boot_consumption));
+            }
+        }
+        consumers
+    }
+
+    pub fn get_power_consumption(&self) -> f64 {
+        self.get_power_consumers().iter().map(|(_, c)| c).sum()
     }
 
     pub fn battery_percentage(&self) -> f64 {
@@ -297,14 +825,311 @@ impl CubeSat {
         100.0 * eps.charge / eps.max_charge
     }
 
-    pub fn update_orbit(&mut self) {
-        if let Some(orbit_type) = &self.orbit_type {
-            match orbit_type {
-                orbit::OrbitType::CircularCosine => orbit::orbit_circular_cosine(self),
-                orbit::OrbitType::Parametric => orbit::orbit_parametric(self),
+    pub fn plan_transfers(&mut self) -> Result<(), CubeSatError> {
+        // Guards
+        let plans = match self.transfers.as_ref() {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+        let current = self
+            .orbit_parameters
+            .as_ref()
+            .ok_or_else(|| CubeSatError::missing("orbit parameters"))?;
+
+        // Expand each transfer into its burns, planned from the initial orbit
+        let mut planned = Vec::new();
+        for plan in plans {
+            let transfer = plan.plan(current)?;
+            self.history.summarize(
+                &format!("{} delta-v", plan.name),
+                format!("{} m/s", transfer.delta_v),
+            );
+            self.history.summarize(
+                &format!("{} duration", plan.name),
+                format!("{} s", transfer.duration),
+            );
+            let mut maneuvers = transfer.to_maneuvers(&plan.name, plan.start);
+            for m in maneuvers.iter_mut() {
+                m.isp = plan.isp;
+            }
+            self.history.plan(&orbit::schedule_toml(&maneuvers));
+            planned.extend(maneuvers);
+        }
+        for maneuver in planned {
+            match self.maneuvers {
+                Some(ref mut m) => m.push(maneuver),
+                None => self.maneuvers = Some(vec![maneuver]),
             }
-        } else {
-            panic!("No orbit type is set!");
+        }
+        Ok(())
+    }
+
+    pub fn load_gravity_field(
+        &mut self,
+        cache: &mut Vec<Arc<orbit::GravityField>>,
+    ) -> Result<(), CubeSatError> {
+        // Guards
+        let parameters = match &self.gravity_field {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+
+        // Reuse a field with the same file, degree, order and constants
+        let cached = cache.iter().find(|f| {
+            f.path == parameters.path
+                && f.degree == parameters.degree
+                && f.order == parameters.order.min(parameters.degree)
+                && f.gm == parameters.gm
+                && f.radius == parameters.radius
+        });
+        let field = match cached {
+            Some(f) => f.clone(),
+            None => {
+                let f = Arc::new(orbit::GravityField::from_file(parameters)?);
+                cache.push(f.clone());
+                f
+            }
+        };
+        self.gravity_model = Some(field);
+        Ok(())
+    }
+
+    pub fn update_power(&mut self, generation: f64) {
+        // Guards
+        let step = self.time.as_ref().expect("No time is set!").step;
+        let consumers = self.get_power_consumers();
+        let consumption = self.get_power_consumption();
+
+        // Power bus: regulator and converter losses
+        let (delivered, drawn) = match &self.power_bus {
+            Some(bus) => {
+                for rail in &bus.rails {
+                    let load: f64 = consumers
+                        .iter()
+                        .filter(|(name, _)| rail.loads.contains(name))
+                        .map(|(_, c)| c)
+                        .sum();
+                    self.history.record(&format!("{} load", rail.name), load);
+                }
+                let drawn = consumers
+                    .iter()
+                    .map(|(name, c)| bus.load_power(name, *c))
+                    .sum();
+                (bus.array_power(generation), drawn)
+            }
+            None => (generation, consumption),
+        };
+
+        // Battery takes what it can
+        let power = delivered + drawn;
+        let before = self.eps.as_ref().expect("No EPS is set!").charge;
+        self.update_battery(power);
+        let stored = match &self.battery {
+            Some(battery) => battery.power,
+            None => {
+                let after = self.eps.as_ref().expect("No EPS is set!").charge;
+                (after - before) * time::HOUR / step
+            }
+        };
+
+        // Surplus that could not be stored is clipped, a deficit the battery could not cover is unmet
+        let wasted = (power - stored).max(0.0);
+        let unmet = (stored - power).max(0.0);
+        let losses = (generation - delivered) + (consumption - drawn).abs();
+        let history = &mut self.history;
+        history.record("generated power", generation);
+        history.record("consumed power", -consumption);
+        history.record("stored power", stored);
+        history.record("wasted power", wasted);
+        history.record("unmet load", unmet);
+        self.energy.add(
+            generation * step / time::HOUR,
+            -consumption * step / time::HOUR,
+            stored * step / time::HOUR,
+            wasted * step / time::HOUR,
+            unmet * step / time::HOUR,
+            losses * step / time::HOUR,
+        );
+    }
+
+    pub fn summarize(&mut self) {
+        let energy = self.energy;
+        let power = self
+            .lifetime
+            .map(|lifetime| (self.peak_power(0.0), self.peak_power(lifetime)));
+        let history = &mut self.history;
+        history.summarize("energy generated", format!("{:.4} Wh", energy.generated));
+        history.summarize("energy consumed", format!("{:.4} Wh", energy.consumed));
+        history.summarize("energy stored", format!("{:.4} Wh", energy.stored));
+        history.summarize("energy wasted", format!("{:.4} Wh", energy.wasted));
+        history.summarize("energy unmet", format!("{:.4} Wh", energy.unmet));
+        history.summarize("energy losses", format!("{:.4} Wh", energy.losses));
+        if let Some((beginning, end)) = power {
+            history.summarize("beginning-of-life power", format!("{beginning:.4} W"));
+            history.summarize("end-of-life power", format!("{end:.4} W"));
+        }
+        if let Some((mean, max)) = self
+            .battery
+            .as_ref()
+            .and_then(|b| b.depth_of_discharge_statistics())
+        {
+            history.summarize("mean depth of discharge", format!("{mean:.2}%"));
+            history.summarize("max depth of discharge", format!("{max:.2}%"));
+        }
+        if let Some(brownout) = &self.brownout {
+            history.summarize("brownouts", format!("{}", brownout.brownouts));
+            history.summarize("dead time", format!("{:.1} s", brownout.dead_time));
+        }
+        if let Some(components) = &self.components {
+            for c in components {
+                for m in &c.modes {
+                    let quantity = format!("{} {}", c.name, m.mode.name());
+                    history.summarize(&format!("{quantity} time"), format!("{:.1} s", m.time));
+                    history.summarize(&format!("{quantity} energy"), format!("{:.4} Wh", m.energy));
+                }
+            }
+        }
+        if let Some(faults) = &self.faults {
+            for f in faults {
+                let state = match (f.injected, &f.failure) {
+                    (Some(t), _) => format!("injected at {t:.1} s"),
+                    (None, Some(reason)) => format!("failed, {reason}"),
+                    (None, None) => "not injected".to_string(),
+                };
+                history.summarize(&format!("fault {}", f.name), state);
+            }
+        }
+        if let Some(rules) = &self.fdir_rules {
+            for r in rules {
+                history.summarize(&format!("{} firings", r.name), format!("{}", r.firings));
+            }
+        }
+        if let Some(heaters) = &self.heaters {
+            for h in heaters {
+                let duty_cycle = 100.0 * h.duty_cycle();
+                history.summarize(
+                    &format!("{} duty cycle", h.name),
+                    format!("{duty_cycle:.2}%"),
+                );
+            }
+        }
+    }
+
+    pub fn update_battery(&mut self, power: f64) {
+        // Guards
+        let time = self.time.expect("No time is set!");
+        let eps = self.eps.as_mut().expect("No EPS is set!");
+        let battery = match self.battery.as_mut() {
+            Some(b) => b,
+            None => {
+                // Ideal battery
+                eps.update_capacity(power, time.step);
+                return;
+            }
+        };
+        battery.update(eps, power, time.step);
+
+        // Depth of discharge per orbit
+        let period = match self.orbit_parameters.as_ref().and_then(|p| p.period()) {
+            Some(p) => p,
+            None => return,
+        };
+        let state_of_charge = 100.0 * eps.charge / eps.max_charge;
+        if let Some(depth) =
+            battery.update_depth_of_discharge(time.now, time.step, period, state_of_charge)
+        {
+            let orbit = battery.depth_of_discharge.len();
+            let event = format!("Orbit {orbit} depth of discharge: {depth:.2}%");
+            self.history.log_event(time.now, event);
+        }
+    }
+
+    pub fn update_orbit(&mut self) -> Result<(), CubeSatError> {
+        match &self.orbit_type {
+            Some(orbit::OrbitType::CircularCosine) => orbit::orbit_circular_cosine(self),
+            Some(orbit::OrbitType::Parametric) => orbit::orbit_parametric(self),
+            Some(orbit::OrbitType::Numerical) => orbit::orbit_numerical(self),
+            None => Err(CubeSatError::missing("orbit type")),
+        }
+    }
+
+    pub fn update_attitude(&mut self) {
+        // Attitude dynamics require an inertia, otherwise the rotational velocity is constant
+        if self.inertia.is_some() {
+            attitude::update_attitude(self);
+        }
+    }
+
+    pub fn update_thermal(&mut self) {
+        // Temperatures are only modelled when a thermal network is set
+        if self.thermal.is_some() {
+            thermal::update_thermal(self);
+        }
+    }
+
+    pub fn update_solar_panels(&mut self) {
+        // Guards
+        let panels = match self.solar_panels.as_mut() {
+            Some(p) => p,
+            None => return,
+        };
+        let time = self.time.expect("No time is set!");
+        let pos = &self.pos.expect("No position vector is set!");
+        let vel = &self.vel.expect("No velocity vector is set!");
+        let rot = &self.rot.expect("No rotation is set!");
+        let sun = &self.sun.expect("No sun is set!");
+        let current = (pos.dot(vel), pos.z);
+        let eclipse = orbit::in_eclipse(pos, sun);
+        let to_sun = sun.to_body(rot).negative();
+
+        for panel in panels.iter_mut() {
+            // Deployment
+            if let Some(deployment) = panel.deployment.as_mut() {
+                if let Some(event) = deployment.update(time.now, time.step, current) {
+                    let event = format!("Solar panel '{}' {event}", panel.name);
+                    self.history.log_event(time.now, event);
+                }
+            }
+
+            // Sun tracking once deployed, the drive holds still in eclipse
+            let deployed = panel.deployed();
+            let normal = panel.deployed_normal();
+            if let Some(tracking) = panel.tracking.as_mut() {
+                match deployed && !eclipse {
+                    true => tracking.update(&normal, &to_sun, time.step),
+                    false => tracking.moving = false,
+                }
+            }
+        }
+
+        // Self-shadowing by the bus and the other panels
+        let shadowing = match &self.shadowing {
+            Some(s) => s,
+            None => return,
+        };
+        let shapes: Vec<Option<component::Rectangle>> =
+            panels.iter().map(|p| p.current_shape()).collect();
+        for (i, panel) in panels.iter_mut().enumerate() {
+            let shape = match (&shapes[i], eclipse) {
+                (Some(shape), false) => shape,
+                _ => {
+                    panel.shadow = 0.0;
+                    continue;
+                }
+            };
+            let occluders: Vec<component::Rectangle> = shadowing
+                .bus
+                .iter()
+                .cloned()
+                .chain(
+                    shapes
+                        .iter()
+                        .enumerate()
+                        .filter(|(j, _)| *j != i)
+                        .filter_map(|(_, s)| *s),
+                )
+                .collect();
+            panel.shadow = shape.shadow_fraction(&occluders, &to_sun, shadowing.samples);
         }
     }
 
@@ -326,6 +1151,11 @@ impl CubeSat {
     }
 
     pub fn rotate_sun(&mut self) {
+        // Third-body perturbations need the date, the sun then follows the same ephemeris
+        if self.third_body.is_some() {
+            self.update_sun();
+            return;
+        }
         let sun = self.sun.as_mut().expect("No sun is set!");
         let step = self.time.as_ref().expect("No time is set!").step;
         let angle_per_day = 2.0 * std::f64::consts::PI / (365.25 * time::DAY) * step;
@@ -333,9 +1163,60 @@ impl CubeSat {
         *sun = sun.rot_z(angle_per_day);
     }
 
+    pub fn update_sun(&mut self) {
+        // Sunlight direction from the solar ephemeris at the current time
+        let time = self.time.expect("No time is set!");
+        self.sun = Some(orbit::sun_direction(time.julian_date(time.now)));
+    }
+
     pub fn check_safety_limit(&mut self) {
-        if let Some(limit) = self.safe_limit {
-            self.safe_mode = self.battery_percentage() <= limit;
+        // Guards
+        if self.safe_mode_policy.is_none() && self.safe_limit.is_none() {
+            return;
+        }
+        let previous = self.safe_mode;
+        let now = self.time.map(|t| t.now);
+        let battery = self.quantity("battery").expect("No EPS is set!");
+
+        // A policy has hysteresis and dwell, a plain limit switches on every crossing
+        self.safe_mode = match (self.safe_mode_policy.as_mut(), self.safe_limit) {
+            (Some(policy), _) => policy.update(previous, battery, now.unwrap_or(0.0)),
+            (None, Some(limit)) => battery <= limit,
+            (None, None) => previous,
+        };
+
+        // Log entry and exit
+        if let (Some(now), true) = (now, self.safe_mode != previous) {
+            let event = match self.safe_mode {
+                true => format!("Safe mode entered, battery at {battery:.2}%"),
+                false => format!("Safe mode exited, battery at {battery:.2}%"),
+            };
+            self.history.log_event(now, event);
+        }
+    }
+
+    pub fn check_brownout(&mut self) {
+        // Guards
+        if self.brownout.is_none() {
+            return;
+        }
+        let time = self.time.expect("No time is set!");
+        let battery = self.battery_percentage();
+        let brownout = self.brownout.as_mut().expect("No brownout is set!");
+        let powered = brownout.dead.is_none();
+
+        for event in brownout.update(battery, time.now, time.step) {
+            self.history.log_event(time.now, event);
+        }
+
+        // Loads that lose power switch off and restart from their initial mode
+        let brownout = self.brownout.as_ref().expect("No brownout is set!");
+        if powered && brownout.dead.is_some() {
+            for component in self.components.iter_mut().flatten() {
+                if !brownout.powers(&component.name) {
+                    component.power_off(time.now);
+                }
+            }
         }
     }
 
@@ -361,19 +1242,378 @@ impl CubeSat {
             self.sun,
             self.eps,
         );
+
+        // Reaction wheels
+        if let Some(wheels) = &self.reaction_wheels {
+            for wheel in wheels {
+                let column = format!("{} momentum", wheel.name);
+                self.history.record(&column, wheel.momentum());
+            }
+        }
+
+        // Component modes
+        if let Some(components) = &self.components {
+            for c in components {
+                if let Some(mode) = c.current_mode() {
+                    let column = format!("{} mode", c.name);
+                    self.history.record(&column, mode.mode as u8 as f64);
+                    let column = format!("{} ready", c.name);
+                    self.history.record(&column, !c.warming as u8 as f64);
+                }
+            }
+        }
+
+        // Solar panels
+        if let Some(panels) = &self.solar_panels {
+            for panel in panels {
+                if let Some(deployment) = &panel.deployment {
+                    let column = format!("{} deployment", panel.name);
+                    self.history.record(&column, deployment.progress);
+                }
+                if let Some(tracking) = &panel.tracking {
+                    let column = format!("{} tracking angle", panel.name);
+                    self.history.record(&column, tracking.angle);
+                }
+                if self.shadowing.is_some() && panel.shape.is_some() {
+                    let column = format!("{} shadow", panel.name);
+                    self.history.record(&column, panel.shadow);
+                }
+            }
+        }
+
+        // Albedo
+        if let (Some(_), Some(panels)) = (&self.albedo, &self.solar_panels) {
+            for panel in panels {
+                let column = format!("{} earth infrared", panel.name);
+                self.history.record(&column, panel.albedo.1);
+            }
+            let power = self.get_albedo_power();
+            self.history.record("albedo power", power);
+        }
+
+        // Battery
+        if let (Some(battery), Some(eps)) = (&self.battery, &self.eps) {
+            let state_of_charge = 100.0 * eps.charge / eps.max_charge;
+            self.history.record("battery capacity", eps.max_charge);
+            self.history.record("battery cycles", battery.cycles());
+            if let Some(voltage) = battery.voltage(state_of_charge) {
+                self.history.record("battery voltage", voltage);
+                self.history
+                    .record("battery current", battery.power / voltage);
+            }
+        }
+
+        // Brownout
+        if let Some(brownout) = &self.brownout {
+            self.history
+                .record("brownout", brownout.state() as u8 as f64);
+        }
+
+        // Mass
+        if let Some(propulsion) = &self.propulsion {
+            let mass = self.mass.expect("No mass is set!");
+            self.history.record("mass", mass);
+            self.history.record("propellant", propulsion.propellant);
+            self.history
+                .record("delta-v remaining", propulsion.delta_v(mass));
+        } else if self.maneuvers.is_some() {
+            if let Some(mass) = self.mass {
+                self.history.record("mass", mass);
+            }
+            if let Some(propellant) = self.propellant {
+                self.history.record("propellant", propellant);
+            }
+        }
+
+        // Temperatures
+        if let Some(thermal) = &self.thermal {
+            for node in &thermal.nodes {
+                let column = format!("{} temperature", node.name);
+                self.history.record(&column, node.temperature);
+            }
+        }
+        if let Some(heaters) = &self.heaters {
+            for heater in heaters {
+                let column = format!("{} heater", heater.name);
+                self.history.record(&column, heater.power_consumption());
+            }
+        }
+
+        // Faults
+        if let Some(faults) = &self.faults {
+            let injected = faults.iter().filter(|f| f.injected.is_some()).count();
+            self.history.record("injected faults", injected as f64);
+        }
+
+        // Disturbance torques
+        if self.geometry.is_some() {
+            let torques = attitude::disturbance_torques(self);
+            let history = &mut self.history;
+            history.record("gravity gradient torque", torques.gravity_gradient.abs());
+            history.record("aerodynamic torque", torques.aerodynamic.abs());
+            history.record("solar radiation torque", torques.solar_radiation.abs());
+            history.record("magnetic torque", torques.magnetic.abs());
+        }
     }
 
-    pub fn simulate(&mut self) {
+    pub fn validate(&self) -> Result<(), CubeSatError> {
+        // Settings the simulation loop relies on
+        let required = [
+            ("name", self.name.is_some()),
+            ("time", self.time.is_some()),
+            ("orbit type", self.orbit_type.is_some()),
+            ("position vector", self.pos.is_some()),
+            ("velocity vector", self.vel.is_some()),
+            ("acceleration vector", self.acc.is_some()),
+            ("rotation", self.rot.is_some()),
+            ("rotational velocity", self.rot_vel.is_some()),
+            ("sun", self.sun.is_some()),
+            ("solar panels", self.solar_panels.is_some()),
+            ("EPS", self.eps.is_some()),
+            ("components", self.components.is_some()),
+        ];
+        if let Some((setting, _)) = required.iter().find(|(_, set)| !set) {
+            return Err(CubeSatError::missing(setting));
+        }
+
+        // Values that would stall or break the loop
+        let time = self.time.expect("No time is set!");
+        if time.step.is_nan() || time.step <= 0.0 {
+            return Err(CubeSatError::InvalidParameter(format!(
+                "time step {} s is not positive",
+                time.step
+            )));
+        }
+        if self
+            .eps
+            .is_some_and(|e| e.max_charge.is_nan() || e.max_charge <= 0.0)
+        {
+            return Err(CubeSatError::InvalidParameter(
+                "EPS max charge is not positive".to_string(),
+            ));
+        }
+        let commands = self.timeline.iter().flat_map(|t| t.commands.iter());
+        let actions = self
+            .fdir_rules
+            .iter()
+            .flatten()
+            .flat_map(|r| r.actions.iter());
+        let commanded: Vec<&String> = commands
+            .map(|c| (&c.component, c.command))
+            .chain(actions.map(|a| (&a.component, a.command)))
+            .filter(|(_, kind)| *kind == schedule::CommandKind::On)
+            .map(|(name, _)| name)
+            .collect();
+        for c in self.components.as_ref().expect("No components are set!") {
+            // Anything that can switch a component on needs its active draw
+            let switched = c.active
+                || c.activation_interval.is_some()
+                || c.trigger.is_some()
+                || commanded.contains(&&c.name)
+                || self
+                    .safe_mode_policy
+                    .as_ref()
+                    .is_some_and(|p| p.forces(&c.name));
+            if switched && !c.has_active_consumption() {
+                return Err(CubeSatError::missing(&format!(
+                    "active consumption or mode table for component '{}'",
+                    c.name
+                )));
+            }
+            if c.activation_interval.is_none() && c.activation_duration.is_some() {
+                return Err(CubeSatError::InvalidParameter(format!(
+                    "component '{}' has an activation duration without an interval",
+                    c.name
+                )));
+            }
+            // A delayed cycle must end before the next one starts
+            if let (Some(interval), Some(duration), Some(jitter)) = (
+                c.activation_interval,
+                c.activation_duration,
+                c.activation_jitter,
+            ) {
+                if jitter > interval - duration {
+                    return Err(CubeSatError::InvalidParameter(format!(
+                        "component '{}' has an activation jitter above interval minus duration",
+                        c.name
+                    )));
+                }
+            }
+        }
+        let panels = self
+            .solar_panels
+            .as_ref()
+            .expect("No solar panels are set!");
+        for (i, panel) in panels.iter().enumerate() {
+            if !panel.has_power_model() {
+                return Err(CubeSatError::missing(&format!(
+                    "peak power or cell model for solar panel '{}'",
+                    panel.name
+                )));
+            }
+            // Per-panel History columns, faults and thermal nodes refer to the name
+            if panels[..i].iter().any(|p| p.name == panel.name) {
+                return Err(CubeSatError::InvalidParameter(format!(
+                    "solar panel name '{}' is used twice",
+                    panel.name
+                )));
+            }
+        }
+        if self.propulsion.is_some() && self.mass.is_none() {
+            return Err(CubeSatError::missing("mass"));
+        }
+        if let Some(srp) = &self.solar_radiation_pressure {
+            // The force is turned into an acceleration, the faces model needs the surfaces
+            if self.mass.is_none() {
+                return Err(CubeSatError::InvalidParameter(
+                    "solar radiation pressure needs the mass".to_string(),
+                ));
+            }
+            if srp.model == orbit::SrpModel::Faces && self.geometry.is_none() {
+                return Err(CubeSatError::InvalidParameter(
+                    "solar radiation pressure from faces needs the geometry".to_string(),
+                ));
+            }
+        }
+        let conditions = self
+            .components
+            .iter()
+            .flatten()
+            .filter_map(|c| c.trigger.as_ref())
+            .flat_map(|t| t.conditions.iter())
+            .filter(|c| c.kind == schedule::ConditionKind::Contact);
+        for condition in conditions {
+            let stations = self
+                .ground_stations
+                .as_ref()
+                .ok_or_else(|| CubeSatError::missing("ground stations for a contact trigger"))?;
+            if let Some(name) = &condition.station {
+                if !stations.iter().any(|s| s.name == *name) {
+                    return Err(CubeSatError::InvalidParameter(format!(
+                        "unknown ground station '{name}'"
+                    )));
+                }
+            }
+        }
+        for m in self.maneuvers.iter().flatten() {
+            // Finite burns need a direction, a duration, a thrust, possibly from the propulsion system, and the mass
+            let missing = match m.delta_v {
+                Some(_) => None,
+                None if m.direction.is_none() => Some("direction"),
+                None if m.duration.is_none() => Some("duration"),
+                None if m.thrust.is_none() && self.propulsion.is_none() => Some("thrust"),
+                None if self.mass.is_none() => Some("mass"),
+                None => None,
+            };
+            if let Some(setting) = missing {
+                return Err(CubeSatError::missing(&format!(
+                    "{setting} for maneuver '{}'",
+                    m.name
+                )));
+            }
+            // Without an Isp a finite burn would fire without using propellant
+            if m.delta_v.is_none() && m.isp.is_none() && self.propulsion.is_none() {
+                return Err(CubeSatError::InvalidParameter(format!(
+                    "maneuver '{}' has no Isp and no propulsion system",
+                    m.name
+                )));
+            }
+        }
+        if self
+            .brownout
+            .as_ref()
+            .is_some_and(|b| b.recovery.is_nan() || b.recovery <= b.threshold)
+        {
+            // Otherwise the loads would reboot straight into the next brownout
+            return Err(CubeSatError::InvalidParameter(
+                "brownout recovery is not above the threshold".to_string(),
+            ));
+        }
+        if self
+            .safe_mode_policy
+            .as_ref()
+            .is_some_and(|p| p.exit.is_nan() || p.exit <= p.enter)
+        {
+            // Otherwise safe mode would flip on and off every step
+            return Err(CubeSatError::InvalidParameter(
+                "safe mode exit is not above the entry threshold".to_string(),
+            ));
+        }
+        if self.heaters.is_some() && self.thermal.is_none() {
+            // Thermostats only switch on temperatures from the thermal network
+            return Err(CubeSatError::missing("thermal model for the heaters"));
+        }
+        if let Some(thermal) = &self.thermal {
+            thermal.validate()?;
+            let mut heaters = self.heaters.iter().flatten();
+            if let Some(heater) = heaters.find(|h| !thermal.contains(&h.node)) {
+                return Err(CubeSatError::InvalidParameter(format!(
+                    "unknown thermal node '{}'",
+                    heater.node
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn simulate(&mut self) -> Result<(), CubeSatError> {
+        // Check the configuration before anything runs
+        self.validate()?;
+
+        // Load the gravity field if it was not shared beforehand
+        if self.gravity_model.is_none() {
+            self.load_gravity_field(&mut Vec::new())?;
+        }
+
+        // Load the reflectivity grid
+        if let Some(albedo) = self.albedo.as_mut() {
+            albedo.load()?;
+        }
+
+        // Load the command timeline
+        if let Some(timeline) = self.timeline.as_mut() {
+            timeline.load()?;
+        }
+
+        // Expand planned transfers into maneuvers
+        self.plan_transfers()?;
+
+        // Sun direction from the ephemeris from the first step on
+        if self.third_body.is_some() {
+            self.update_sun();
+        }
+
         // Loop until end
         while self.active {
             // Check safety limit
             self.check_safety_limit();
 
+            // Power loads off on brownout and reboot after recovery
+            self.check_brownout();
+
+            // Apply timeline commands
+            self.apply_commands();
+
+            // Fault detection, isolation and recovery
+            fdir::update_fdir(self)?;
+
+            // Update orbit, triggers read the position of this step
+            self.update_orbit()?;
+
             // Update active components
             self.update_active_components(self.time.expect("No time is set!").now, self.safe_mode);
 
-            // Update orbit
-            self.update_orbit();
+            // Update component modes
+            self.update_modes();
+
+            // Inject faults
+            fdir::inject_faults(self);
+
+            // Update maneuvers
+            maneuver::update_maneuvers(self)?;
+
+            // Update attitude
+            self.update_attitude();
 
             // Update rotation
             self.update_rotation();
@@ -381,6 +1621,12 @@ impl CubeSat {
             // Update sun
             self.rotate_sun();
 
+            // Update solar panel deployment and tracking
+            self.update_solar_panels();
+
+            // Sunlight reflected and infrared emitted by the Earth on the panels
+            self.update_albedo();
+
             // Save history
             self.save_history();
 
@@ -390,23 +1636,20 @@ impl CubeSat {
             // Calculate power generation
             let generation = self.get_power_generation();
 
-            // Calculate power consumption
-            let consumption = self.get_power_consumption();
+            // Power bus, battery and energy accounting
+            self.update_power(generation);
 
-            // Current net power
-            let power = generation + consumption;
-
-            // Update battery
-            let step = self.time.as_ref().expect("No time is set!").step;
-            let eps = self.eps.as_mut().expect("No EPS is set!");
-            eps.update_capacity(power, step);
+            // Absorbed heat, dissipation and temperatures
+            self.update_thermal();
 
             // Next time step
             self.iterate();
         }
 
         // Save history
-        self.history.write(self.name.as_ref().unwrap());
+        self.summarize();
+        self.history
+            .write(self.name.as_ref().expect("No name is set!"))
     }
 
     #[allow(unused)]
@@ -422,6 +1665,7 @@ impl CubeSat {
         match &self.orbit_type {
             Some(orbit::OrbitType::CircularCosine) => println!("\t\tType: Circular cosine"),
             Some(orbit::OrbitType::Parametric) => println!("\t\tType: Parametric"),
+            Some(orbit::OrbitType::Numerical) => println!("\t\tType: Numerical"),
             None => println!("\t\tNo orbit type is set!"),
         }
         match &self.orbit_parameters {
@@ -490,12 +1734,13 @@ impl CubeSat {
         println!("\tSolar panels ({number}x):");
         if let Some(panels) = &self.solar_panels {
             for panel in panels {
+                let normal = panel.normal();
                 println!(
                     "\t\t({}, {}, {}), {} W",
-                    panel.orientation.x,
-                    panel.orientation.y,
-                    panel.orientation.z,
-                    panel.power_generation
+                    normal.x,
+                    normal.y,
+                    normal.z,
+                    panel.peak_power(orbit::SOLAR_CONSTANT, 0.0)
                 );
             }
         }
@@ -511,6 +1756,19 @@ impl CubeSat {
             ),
             None => println!("\t\tNo EPS has been set!"),
         }
+        // Battery
+        if let Some(b) = &self.battery {
+            println!("\tBattery:");
+            println!(
+                "\t\tEfficiency: {} / {}, cycles: {:.2}",
+                b.charge_efficiency,
+                b.discharge_efficiency,
+                b.cycles()
+            );
+            if let Some((mean, max)) = b.depth_of_discharge_statistics() {
+                println!("\t\tDepth of discharge: {mean:.2}% mean, {max:.2}% max");
+            }
+        }
         // Generic components
         println!("\tComponents:");
         match &self.components {
@@ -521,6 +1779,33 @@ impl CubeSat {
             }
             None => println!("\t\tNo components have been set!"),
         }
+        // Reaction wheels
+        if let Some(wheels) = &self.reaction_wheels {
+            println!("\tReaction wheels:");
+            for w in wheels {
+                println!(
+                    "\t\tName: {}, momentum: {} Nms, saturated: {}",
+                    w.name,
+                    w.momentum(),
+                    w.saturated
+                );
+            }
+        }
+        // Propulsion
+        if let Some(p) = &self.propulsion {
+            println!("\tPropulsion:");
+            println!(
+                "\t\tName: {}, propellant: {} kg, thrust: {} N, Isp: {} s",
+                p.name, p.propellant, p.thrust, p.isp
+            );
+        }
+        // Thermal
+        if let Some(thermal) = &self.thermal {
+            println!("\tThermal:");
+            for n in &thermal.nodes {
+                println!("\t\tName: {}, temperature: {} °C", n.name, n.temperature);
+            }
+        }
     }
 
     // Default values for deserialization
@@ -557,6 +1842,10 @@ pub struct History {
     rot_vel: Vec<(f64, f64, f64)>,
     sun: Vec<(f64, f64, f64)>,
     charge: Vec<f64>,
+    columns: Vec<(String, Vec<f64>)>,
+    events: Vec<(f64, String)>,
+    summary: Vec<(String, String)>,
+    schedule: String,
 }
 
 impl History {
@@ -570,9 +1859,61 @@ impl History {
             rot_vel: Vec::new(),
             sun: Vec::new(),
             charge: Vec::new(),
+            columns: Vec::new(),
+            events: Vec::new(),
+            summary: Vec::new(),
+            schedule: String::new(),
+        }
+    }
+
+    pub fn record(&mut self, column: &str, value: f64) {
+        match self.columns.iter_mut().find(|(name, _)| name == column) {
+            Some((_, values)) => values.push(value),
+            None => self.columns.push((column.to_string(), vec![value])),
         }
     }
 
+    pub fn steps(&self) -> usize {
+        self.time.len()
+    }
+
+    pub fn log_event(&mut self, time: f64, event: String) {
+        self.events.push((time, event));
+    }
+
+    pub fn summarize(&mut self, quantity: &str, value: String) {
+        self.summary.push((quantity.to_string(), value));
+    }
+
+    pub fn plan(&mut self, schedule: &str) {
+        self.schedule += schedule;
+    }
+
+    #[allow(unused)]
+    pub fn column(&self, column: &str) -> Option<&Vec<f64>> {
+        self.columns
+            .iter()
+            .find(|(name, _)| name == column)
+            .map(|(_, values)| values)
+    }
+
+    pub fn latest(&self, column: &str) -> Option<f64> {
+        self.columns
+            .iter()
+            .find(|(name, _)| name == column)
+            .and_then(|(_, values)| values.last().copied())
+    }
+
+    #[allow(unused)]
+    pub fn events(&self) -> &Vec<(f64, String)> {
+        &self.events
+    }
+
+    #[allow(unused)]
+    pub fn summary(&self) -> &Vec<(String, String)> {
+        &self.summary
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn save(
         &mut self,
@@ -626,25 +1967,22 @@ impl History {
         }
     }
 
-    pub fn write(&self, name: &str) {
+    pub fn write(&self, name: &str) -> Result<(), CubeSatError> {
         // File path
         let path = format!("{}{}.csv", "./output/", name);
         // Open file
-        let file = File::create(&path);
-        if let Err(e) = file {
-            println!("File could not be opened due to \"{e}\"!");
-            return;
-        }
+        let mut file = File::create(&path).map_err(|e| CubeSatError::io(&path, &e))?;
 
         // Write header
-        let header =
-            "time|position|velocity|acceleration|rotation|rotational velocity|sun|charge\n"
+        let mut header =
+            "time|position|velocity|acceleration|rotation|rotational velocity|sun|charge"
                 .to_string();
-        let result = file.as_ref().unwrap().write_all(&header.into_bytes());
-        if let Err(e) = result {
-            println!("File could not be saved due to \"{e}\"!");
-            return;
+        for (column, _) in &self.columns {
+            header += &format!("|{column}");
         }
+        header += "\n";
+        file.write_all(&header.into_bytes())
+            .map_err(|e| CubeSatError::io(&path, &e))?;
 
         // Write content
         for i in 0..self.time.len() {
@@ -661,17 +1999,87 @@ impl History {
             let sun = format!("{},{},{}", self.sun[i].0, self.sun[i].1, self.sun[i].2);
             let charge = self.charge[i];
             // Format line and write
-            let line = format!(
-                "{}|{}|{}|{}|{}|{}|{}|{}\n",
+            let mut line = format!(
+                "{}|{}|{}|{}|{}|{}|{}|{}",
                 time, pos, vel, acc, rot, rot_vel, sun, charge
             );
-            let result = file.as_ref().unwrap().write_all(&line.into_bytes());
-            if let Err(e) = result {
-                println!("File could not be saved due to \"{e}\"!");
-                return;
+            for (_, values) in &self.columns {
+                match values.get(i) {
+                    Some(v) => line += &format!("|{v}"),
+                    None => line += "|",
+                }
             }
+            line += "\n";
+            file.write_all(&line.into_bytes())
+                .map_err(|e| CubeSatError::io(&path, &e))?;
         }
 
         println!("File '{name}.csv' was written successfully!\n");
+
+        // Events
+        if !self.events.is_empty() {
+            self.write_events(name)?;
+        }
+
+        // Summary
+        if !self.summary.is_empty() {
+            self.write_summary(name)?;
+        }
+
+        // Planned maneuvers
+        if !self.schedule.is_empty() {
+            self.write_schedule(name)?;
+        }
+        Ok(())
+    }
+
+    pub fn write_events(&self, name: &str) -> Result<(), CubeSatError> {
+        // File path
+        let path = format!("{}{}_events.csv", "./output/", name);
+        // Open file
+        let mut file = File::create(&path).map_err(|e| CubeSatError::io(&path, &e))?;
+
+        // Write header and content
+        let mut content = "time|event\n".to_string();
+        for (time, event) in &self.events {
+            content += &format!("{time}|{event}\n");
+        }
+        file.write_all(&content.into_bytes())
+            .map_err(|e| CubeSatError::io(&path, &e))?;
+
+        println!("File '{name}_events.csv' was written successfully!\n");
+        Ok(())
+    }
+
+    pub fn write_summary(&self, name: &str) -> Result<(), CubeSatError> {
+        // File path
+        let path = format!("{}{}_summary.csv", "./output/", name);
+        // Open file
+        let mut file = File::create(&path).map_err(|e| CubeSatError::io(&path, &e))?;
+
+        // Write header and content
+        let mut content = "quantity|value\n".to_string();
+        for (quantity, value) in &self.summary {
+            content += &format!("{quantity}|{value}\n");
+        }
+        file.write_all(&content.into_bytes())
+            .map_err(|e| CubeSatError::io(&path, &e))?;
+
+        println!("File '{name}_summary.csv' was written successfully!\n");
+        Ok(())
+    }
+
+    pub fn write_schedule(&self, name: &str) -> Result<(), CubeSatError> {
+        // File path
+        let path = format!("{}{}_maneuvers.toml", "./output/", name);
+        // Open file
+        let mut file = File::create(&path).map_err(|e| CubeSatError::io(&path, &e))?;
+
+        // Same format as the [[maneuvers]] input
+        file.write_all(self.schedule.as_bytes())
+            .map_err(|e| CubeSatError::io(&path, &e))?;
+
+        println!("File '{name}_maneuvers.toml' was written successfully!\n");
+        Ok(())
     }
 }
@@ -0,0 +1,71 @@
+#[path = "./tests/error.rs"]
+mod tests;
+
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum CubeSatError {
+    ConfigMissing(String), // Required setting that is not set
+    Parse {
+        path: String,
+        line: usize,   // From 1
+        column: usize, // From 1
+        message: String,
+    },
+    InvalidParameter(String),
+    NumericalFailure(String),
+    Io {
+        path: String,
+        message: String,
+    },
+}
+
+impl CubeSatError {
+    pub fn missing(setting: &str) -> Self {
+        CubeSatError::ConfigMissing(setting.to_string())
+    }
+
+    pub fn parse(path: &str, text: &str, offset: usize, message: &str) -> Self {
+        // Line and column of a byte offset into the text
+        let before = &text[..offset.min(text.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+        CubeSatError::Parse {
+            path: path.to_string(),
+            line,
+            column,
+            message: message.trim().to_string(),
+        }
+    }
+
+    pub fn toml(path: &str, text: &str, error: &toml::de::Error) -> Self {
+        let offset = error.span().map(|s| s.start).unwrap_or(0);
+        CubeSatError::parse(path, text, offset, error.message())
+    }
+
+    pub fn io(path: &str, error: &std::io::Error) -> Self {
+        CubeSatError::Io {
+            path: path.to_string(),
+            message: error.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for CubeSatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CubeSatError::ConfigMissing(setting) => write!(f, "No {setting} is set"),
+            CubeSatError::Parse {
+                path,
+                line,
+                column,
+                message,
+            } => write!(f, "{path}:{line}:{column}: {message}"),
+            CubeSatError::InvalidParameter(message) => write!(f, "Invalid parameter: {message}"),
+            CubeSatError::NumericalFailure(message) => write!(f, "Numerical failure: {message}"),
+            CubeSatError::Io { path, message } => write!(f, "{path}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for CubeSatError {}
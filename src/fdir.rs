@@ -0,0 +1,389 @@
+#[path = "./tests/fdir.rs"]
+mod tests;
+
+use crate::component;
+use crate::cubesat;
+use crate::error::CubeSatError;
+use crate::maneuver;
+use crate::schedule;
+
+use serde::Deserialize;
+use serde_repr::Deserialize_repr;
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Action {
+    pub component: String,
+    pub command: schedule::CommandKind,
+    #[serde(default = "Action::default_arguments")]
+    pub arguments: Vec<String>,
+}
+
+impl Action {
+    #[allow(unused)]
+    pub fn new(component: &str, command: schedule::CommandKind, arguments: Vec<&str>) -> Self {
+        Action {
+            component: component.to_string(),
+            command,
+            arguments: arguments.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    pub fn to_command(&self, now: f64) -> schedule::Command {
+        schedule::Command {
+            time: now,
+            component: self.component.clone(),
+            command: self.command,
+            arguments: self.arguments.clone(),
+        }
+    }
+
+    // Default values for deserialization
+    fn default_arguments() -> Vec<String> {
+        Vec::new()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Rule {
+    pub name: String,
+    pub quantity: String, // Built-in quantity or History column
+    pub below: Option<f64>,
+    pub above: Option<f64>,
+    #[serde(default = "Rule::default_zero")]
+    pub persistence: f64, // [s] Condition holds before firing
+    #[serde(default = "Rule::default_priority")]
+    pub priority: u32, // Lower is more important
+    #[serde(default = "Rule::default_latch")]
+    pub latch: bool, // Stays fired for the rest of the run
+    pub actions: Vec<Action>,
+    #[serde(skip)]
+    pub since: Option<f64>,
+    #[serde(skip)]
+    pub fired: bool,
+    #[serde(skip)]
+    pub firings: usize,
+}
+
+impl Rule {
+    #[allow(unused)]
+    pub fn new(
+        name: &str,
+        quantity: &str,
+        below: Option<f64>,
+        above: Option<f64>,
+        actions: Vec<Action>,
+    ) -> Self {
+        Rule {
+            name: name.to_string(),
+            quantity: quantity.to_string(),
+            below,
+            above,
+            persistence: 0.0,
+            priority: Rule::default_priority(),
+            latch: false,
+            actions,
+            since: None,
+            fired: false,
+            firings: 0,
+        }
+    }
+
+    pub fn violated(&self, value: f64) -> bool {
+        self.below.is_some_and(|b| value < b) || self.above.is_some_and(|a| value > a)
+    }
+
+    pub fn update(&mut self, value: Option<f64>, now: f64, step: f64) -> Option<bool> {
+        // Some(true) when the rule fires, Some(false) when it clears
+        if self.fired && self.latch {
+            return None;
+        }
+        match value.is_some_and(|v| self.violated(v)) {
+            true => {
+                let since = *self.since.get_or_insert(now);
+                if !self.fired && now - since + 0.5 * step >= self.persistence {
+                    self.fired = true;
+                    self.firings += 1;
+                    return Some(true);
+                }
+                None
+            }
+            false => {
+                self.since = None;
+                match self.fired {
+                    true => {
+                        self.fired = false;
+                        Some(false)
+                    }
+                    false => None,
+                }
+            }
+        }
+    }
+
+    // Default values for deserialization
+    fn default_zero() -> f64 {
+        0.0
+    }
+    fn default_priority() -> u32 {
+        10
+    }
+    fn default_latch() -> bool {
+        false
+    }
+}
+
+pub fn update_fdir(cubesat: &mut cubesat::CubeSat) -> Result<(), CubeSatError> {
+    // Guards
+    let time = cubesat.time.expect("No time is set!");
+    let mut rules = match cubesat.fdir_rules.take() {
+        Some(r) => r,
+        None => return Ok(()),
+    };
+
+    // Higher priority rules act first
+    let mut order: Vec<usize> = (0..rules.len()).collect();
+    order.sort_by_key(|&i| rules[i].priority);
+
+    for i in order {
+        // History columns exist from the first saved step on, a quantity still missing is a typo
+        let value = cubesat.quantity(&rules[i].quantity);
+        if value.is_none() && cubesat.history.steps() > 0 {
+            return Err(CubeSatError::InvalidParameter(format!(
+                "FDIR rule '{}' quantity '{}' is not recorded",
+                rules[i].name, rules[i].quantity
+            )));
+        }
+        let rule = &mut rules[i];
+        match rule.update(value, time.now, time.step) {
+            Some(true) => {
+                let event = format!(
+                    "FDIR rule '{}' fired, {} at {:.3}",
+                    rule.name,
+                    rule.quantity,
+                    value.unwrap_or(f64::NAN)
+                );
+                cubesat.history.log_event(time.now, event);
+            }
+            Some(false) => {
+                let event = format!("FDIR rule '{}' cleared", rule.name);
+                cubesat.history.log_event(time.now, event);
+
+                // Components go back to their schedule unless another fired rule holds them
+                let name = rules[i].name.clone();
+                for action in rules[i].actions.clone() {
+                    let held = rules.iter().any(|r| {
+                        r.fired && r.actions.iter().any(|a| a.component == action.component)
+                    });
+                    if held || action.command == schedule::CommandKind::Auto {
+                        continue;
+                    }
+                    let mut command = action.to_command(time.now);
+                    command.command = schedule::CommandKind::Auto;
+                    command.arguments.clear();
+                    if cubesat.apply_command(&command, time.now).is_ok() {
+                        let event = format!("FDIR rule '{name}' released '{}'", command.component);
+                        cubesat.history.log_event(time.now, event);
+                    }
+                }
+                continue;
+            }
+            None => continue,
+        }
+
+        // Components held by a more important rule are left alone
+        let (name, priority) = (rules[i].name.clone(), rules[i].priority);
+        for action in rules[i].actions.clone() {
+            let held = rules.iter().any(|r| {
+                r.fired
+                    && r.priority < priority
+                    && r.actions.iter().any(|a| a.component == action.component)
+            });
+            let command = action.to_command(time.now);
+            let result = match held {
+                true => Err("held by a higher priority rule".to_string()),
+                false => cubesat.apply_command(&command, time.now),
+            };
+            let event = match result {
+                Ok(()) => format!(
+                    "FDIR rule '{name}' command '{}' to '{}'",
+                    command.describe(),
+                    command.component
+                ),
+                Err(reason) => format!(
+                    "FDIR rule '{name}' command '{}' to '{}' refused, {reason}",
+                    command.describe(),
+                    command.component
+                ),
+            };
+            cubesat.history.log_event(time.now, event);
+        }
+    }
+
+    cubesat.fdir_rules = Some(rules);
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Deserialize_repr)]
+#[repr(u8)]
+pub enum FaultKind {
+    PanelFailure, // Target: solar panel
+    CapacityLoss, // Value: remaining fraction of the battery capacity
+    ModeLock,     // Target: component, mode
+    SensorBias,   // Target: quantity, value: bias
+    WheelStuck,   // Target: reaction wheel
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Fault {
+    pub name: String,
+    pub kind: FaultKind,
+    #[serde(default = "Fault::default_zero")]
+    pub time: f64, // [s] Injection time, or earliest time for an event
+    pub event: Option<maneuver::OrbitEvent>,
+    pub target: Option<String>,
+    pub value: Option<f64>,
+    pub mode: Option<component::Mode>,
+    #[serde(skip)]
+    pub previous: Option<(f64, f64)>,
+    #[serde(skip)]
+    pub injected: Option<f64>, // [s]
+    #[serde(skip)]
+    pub failure: Option<String>, // Why the injection failed
+}
+
+impl Fault {
+    #[allow(unused)]
+    pub fn new(
+        name: &str,
+        kind: FaultKind,
+        time: f64,
+        target: Option<&str>,
+        value: Option<f64>,
+    ) -> Self {
+        Fault {
+            name: name.to_string(),
+            kind,
+            time,
+            event: None,
+            target: target.map(|t| t.to_string()),
+            value,
+            mode: None,
+            previous: None,
+            injected: None,
+            failure: None,
+        }
+    }
+
+    pub fn due(&mut self, now: f64, step: f64, current: (f64, f64)) -> bool {
+        // Trigger on time and optionally on the next orbital event, a single attempt
+        if self.injected.is_some() || self.failure.is_some() {
+            return false;
+        }
+        let mut ready = now + 0.5 * step >= self.time;
+        if let Some(event) = self.event {
+            let crossed = match self.previous {
+                Some(previous) => event.crossed(previous, current),
+                None => false,
+            };
+            self.previous = Some(current);
+            ready = ready && crossed;
+        }
+        ready
+    }
+
+    // Default values for deserialization
+    fn default_zero() -> f64 {
+        0.0
+    }
+}
+
+fn inject(cubesat: &mut cubesat::CubeSat, fault: &Fault, now: f64) -> Result<String, String> {
+    let target = fault.target.as_deref().unwrap_or("");
+    match fault.kind {
+        FaultKind::PanelFailure => {
+            let panel = cubesat
+                .solar_panels
+                .as_mut()
+                .and_then(|p| p.iter_mut().find(|p| p.name == target))
+                .ok_or(format!("unknown solar panel '{target}'"))?;
+            panel.failed = true;
+            Ok(format!("solar panel '{target}' failed"))
+        }
+        FaultKind::CapacityLoss => {
+            let fraction = fault.value.ok_or("no remaining fraction")?;
+            let eps = cubesat.eps.as_mut().ok_or("no EPS")?;
+            if let Some(battery) = cubesat.battery.as_mut() {
+                let nominal = battery.nominal_capacity.unwrap_or(eps.max_charge);
+                battery.nominal_capacity = Some(nominal * fraction);
+            }
+            eps.max_charge *= fraction;
+            eps.charge = eps.charge.min(eps.max_charge);
+            Ok(format!("battery capacity {:.2} Wh", eps.max_charge))
+        }
+        FaultKind::ModeLock => {
+            let mode = fault.mode.ok_or("no mode")?;
+            let component = cubesat
+                .components
+                .as_mut()
+                .and_then(|c| c.iter_mut().find(|c| c.name == target))
+                .ok_or(format!("unknown component '{target}'"))?;
+            if !component.modes.iter().any(|m| m.mode == mode) {
+                return Err(format!("{} is not in the mode table", mode.name()));
+            }
+            component.mode = Some(mode);
+            component.mode_since = now;
+            component.active = mode == component::Mode::Active;
+            component.commanded = Some(component.active);
+            component.locked = true;
+            Ok(format!("component '{target}' locked in {}", mode.name()))
+        }
+        FaultKind::SensorBias => {
+            let bias = fault.value.ok_or("no bias")?;
+            cubesat.sensor_biases.push((target.to_string(), bias));
+            Ok(format!("{target} biased by {bias}"))
+        }
+        FaultKind::WheelStuck => {
+            let wheel = cubesat
+                .reaction_wheels
+                .as_mut()
+                .and_then(|w| w.iter_mut().find(|w| w.name == target))
+                .ok_or(format!("unknown reaction wheel '{target}'"))?;
+            // The motor is lost, the rotor keeps its momentum and only friction slows it
+            wheel.max_torque = 0.0;
+            Ok(format!("reaction wheel '{target}' stuck"))
+        }
+    }
+}
+
+pub fn inject_faults(cubesat: &mut cubesat::CubeSat) {
+    // Guards
+    let time = cubesat.time.expect("No time is set!");
+    let mut faults = match cubesat.faults.take() {
+        Some(f) => f,
+        None => return,
+    };
+    let current = match (cubesat.pos, cubesat.vel) {
+        (Some(pos), Some(vel)) => (pos.dot(&vel), pos.z),
+        _ => (0.0, 0.0),
+    };
+
+    for fault in faults.iter_mut() {
+        if !fault.due(time.now, time.step, current) {
+            continue;
+        }
+        let event = match inject(cubesat, fault, time.now) {
+            Ok(effect) => {
+                fault.injected = Some(time.now);
+                format!("Fault '{}' injected, {effect}", fault.name)
+            }
+            Err(reason) => {
+                let event = format!("Fault '{}' not injected, {reason}", fault.name);
+                fault.failure = Some(reason);
+                event
+            }
+        };
+        cubesat.history.log_event(time.now, event);
+    }
+
+    cubesat.faults = Some(faults);
+}
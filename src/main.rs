@@ -1,10 +1,17 @@
+mod attitude;
 mod component;
 mod cubesat;
+mod error;
+mod fdir;
+mod maneuver;
 mod orbit;
+mod schedule;
+mod thermal;
 mod time;
 mod vector;
 
 use std::fs;
+use std::panic::{self, AssertUnwindSafe};
 use std::path::Path;
 use std::thread;
 
@@ -13,15 +20,36 @@ fn main() {
     let files = fs::read_dir(path).expect("Unable to read input path!");
 
     let handle = thread::spawn(|| {
+        // Gravity fields are loaded once and shared between all satellites
+        let mut gravity_fields = Vec::new();
+
         for file in files {
-            let f = file.expect("Unable to read file!").path();
-            let name = f.file_name().unwrap().to_str().unwrap();
+            let f = match file {
+                Ok(f) => f.path(),
+                Err(e) => {
+                    println!("Unable to read file due to \"{e}\"!\n");
+                    continue;
+                }
+            };
+            let name = f.file_name().unwrap_or_default().to_string_lossy();
             println!("Simulating '{name}'");
 
-            let mut cubesat = cubesat::CubeSat::from_toml(f.to_str().unwrap());
-            cubesat.simulate();
+            // A bad file is reported and the remaining files are still simulated, even after a panic
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                cubesat::CubeSat::from_toml(&f.to_string_lossy()).and_then(|mut cubesat| {
+                    cubesat.load_gravity_field(&mut gravity_fields)?;
+                    cubesat.simulate()
+                })
+            }));
+            match result {
+                Ok(Ok(())) => (),
+                Ok(Err(e)) => println!("Simulation of '{name}' failed: {e}\n"),
+                Err(_) => println!("Simulation of '{name}' panicked!\n"),
+            }
         }
     });
 
-    handle.join().unwrap();
+    if handle.join().is_err() {
+        println!("Simulation thread panicked!");
+    }
 }
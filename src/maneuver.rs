@@ -0,0 +1,379 @@
+#[path = "./tests/maneuver.rs"]
+mod tests;
+
+use crate::cubesat;
+use crate::error::CubeSatError;
+use crate::orbit;
+use crate::vector;
+
+use serde::Deserialize;
+use serde_repr::Deserialize_repr;
+
+pub static G0: f64 = 9.806_65; // [m/s^2]
+
+#[derive(Debug, PartialEq, Clone, Copy, Deserialize_repr)]
+#[repr(u8)]
+pub enum OrbitEvent {
+    Apoapsis,
+    Periapsis,
+    AscendingNode,
+    DescendingNode,
+}
+
+impl OrbitEvent {
+    pub fn crossed(&self, previous: (f64, f64), current: (f64, f64)) -> bool {
+        // (radial velocity, z) before and after the step
+        match self {
+            OrbitEvent::Apoapsis => previous.0 > 0.0 && current.0 <= 0.0,
+            OrbitEvent::Periapsis => previous.0 < 0.0 && current.0 >= 0.0,
+            OrbitEvent::AscendingNode => previous.1 < 0.0 && current.1 >= 0.0,
+            OrbitEvent::DescendingNode => previous.1 > 0.0 && current.1 <= 0.0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Deserialize_repr)]
+#[repr(u8)]
+pub enum ManeuverFrame {
+    Inertial,
+    Vnb, // Velocity, orbit normal, binormal
+    Rtn, // Radial, transverse, orbit normal
+}
+
+impl ManeuverFrame {
+    pub fn to_inertial(
+        self,
+        vector: &vector::Vector3,
+        pos: &vector::Vector3,
+        vel: &vector::Vector3,
+    ) -> vector::Vector3 {
+        let normal = pos.cross(vel).unit();
+        let (a, b, c) = match self {
+            ManeuverFrame::Inertial => return *vector,
+            ManeuverFrame::Vnb => {
+                let v = vel.unit();
+                (v, normal, v.cross(&normal))
+            }
+            ManeuverFrame::Rtn => {
+                let r = pos.unit();
+                (r, normal.cross(&r), normal)
+            }
+        };
+        a.scale(vector.x)
+            .add(&b.scale(vector.y))
+            .add(&c.scale(vector.z))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Maneuver {
+    #[serde(default = "Maneuver::default_name")]
+    pub name: String,
+    #[serde(default = "Maneuver::default_zero")]
+    pub time: f64, // [s] Execution time, or earliest time for an event
+    pub event: Option<OrbitEvent>,
+    #[serde(default = "Maneuver::default_frame")]
+    pub frame: ManeuverFrame,
+
+    // Impulsive
+    pub delta_v: Option<vector::Vector3>, // [m/s]
+
+    // Finite
+    pub direction: Option<vector::Vector3>,
+    pub thrust: Option<f64>,   // [N]
+    pub duration: Option<f64>, // [s]
+
+    // Thruster
+    pub isp: Option<f64>, // [s]
+    #[serde(default = "Maneuver::default_zero")]
+    pub consumption: f64, // [W]
+
+    // State
+    #[serde(skip)]
+    pub executed: bool,
+    #[serde(skip)]
+    pub firing: bool,
+    #[serde(skip)]
+    pub start: Option<f64>,
+    #[serde(skip)]
+    pub previous: Option<(f64, f64)>,
+    #[serde(skip)]
+    pub deferred: bool,
+}
+
+impl Maneuver {
+    #[allow(unused)]
+    pub fn impulsive(
+        name: &str,
+        time: f64,
+        frame: ManeuverFrame,
+        delta_v: (f64, f64, f64),
+    ) -> Self {
+        Maneuver {
+            name: name.to_string(),
+            time,
+            event: None,
+            frame,
+            delta_v: Some(vector::Vector3::new(delta_v.0, delta_v.1, delta_v.2)),
+            direction: None,
+            thrust: None,
+            duration: None,
+            isp: None,
+            consumption: 0.0,
+            executed: false,
+            firing: false,
+            start: None,
+            previous: None,
+            deferred: false,
+        }
+    }
+
+    #[allow(unused)]
+    pub fn finite(
+        name: &str,
+        time: f64,
+        frame: ManeuverFrame,
+        direction: (f64, f64, f64),
+        thrust: f64,
+        duration: f64,
+        isp: f64,
+    ) -> Self {
+        Maneuver {
+            name: name.to_string(),
+            time,
+            event: None,
+            frame,
+            delta_v: None,
+            direction: Some(vector::Vector3::new(direction.0, direction.1, direction.2)),
+            thrust: Some(thrust),
+            duration: Some(duration),
+            isp: Some(isp),
+            consumption: 0.0,
+            executed: false,
+            firing: false,
+            start: None,
+            previous: None,
+            deferred: false,
+        }
+    }
+
+    pub fn propellant_impulsive(&self, mass: f64) -> Option<f64> {
+        // Rocket equation: dm = m * (1 - exp(-dv / (Isp * g0)))
+        match (self.delta_v, self.isp) {
+            (Some(dv), Some(isp)) => Some(mass * (1.0 - (-dv.abs() / (isp * G0)).exp())),
+            _ => None,
+        }
+    }
+
+    pub fn propellant_rate(&self) -> f64 {
+        // Mass flow: F / (Isp * g0)
+        match (self.thrust, self.isp) {
+            (Some(thrust), Some(isp)) => thrust / (isp * G0),
+            _ => 0.0,
+        }
+    }
+
+    pub fn power_consumption(&self) -> f64 {
+        match self.firing {
+            true => self.consumption,
+            false => 0.0,
+        }
+    }
+
+    // Default values for deserialization
+    fn default_name() -> String {
+        "Maneuver".to_string()
+    }
+    fn default_zero() -> f64 {
+        0.0
+    }
+    fn default_frame() -> ManeuverFrame {
+        ManeuverFrame::Inertial
+    }
+}
+
+pub fn thrust_acceleration(
+    maneuvers: &[Maneuver],
+    mass: f64,
+    pos: &vector::Vector3,
+    vel: &vector::Vector3,
+) -> vector::Vector3 {
+    maneuvers
+        .iter()
+        .filter(|m| m.firing && m.delta_v.is_none())
+        .fold(vector::Vector3::origin(), |acc, m| {
+            let direction = m.direction.expect("No burn direction is set!");
+            let thrust = m.thrust.expect("No thrust is set!");
+            let direction = m.frame.to_inertial(&direction, pos, vel).unit();
+            acc.add(&direction.scale(thrust / mass))
+        })
+}
+
+pub fn update_maneuvers(cubesat: &mut cubesat::CubeSat) -> Result<(), CubeSatError> {
+    // Guards
+    let maneuvers = match cubesat.maneuvers.as_mut() {
+        Some(m) => m,
+        None => return Ok(()),
+    };
+    let time = cubesat.time.expect("No time is set!");
+    let pos = cubesat.pos.expect("No position vector is set!");
+    let vel = cubesat.vel.as_mut().expect("No velocity vector is set!");
+    let numerical = cubesat.orbit_type == Some(orbit::OrbitType::Numerical);
+    let current = (pos.dot(vel), pos.z);
+
+    // A propulsion system supplies the propellant, thruster specs and warm-up
+    let next_burn = maneuvers
+        .iter()
+        .filter(|m| !m.executed)
+        .map(|m| m.time)
+        .reduce(f64::min);
+    let (mut propellant, warm) = match cubesat.propulsion.as_mut() {
+        Some(p) => {
+            for maneuver in maneuvers.iter_mut() {
+                maneuver.isp = maneuver.isp.or(Some(p.isp));
+                if maneuver.delta_v.is_none() {
+                    maneuver.thrust = maneuver.thrust.or(Some(p.thrust));
+                }
+            }
+            let warm = p.update_heater(time.now, time.step, next_burn);
+            (Some(&mut p.propellant), warm)
+        }
+        None => (cubesat.propellant.as_mut(), true),
+    };
+
+    for maneuver in maneuvers.iter_mut() {
+        // Impulsive burns only fire during a single step
+        if maneuver.delta_v.is_some() {
+            maneuver.firing = false;
+        }
+        if maneuver.executed {
+            continue;
+        }
+
+        // Trigger on time and optionally on the next orbital event
+        let mut ready = time.now + 0.5 * time.step >= maneuver.time;
+        if let Some(event) = maneuver.event {
+            let crossed = match maneuver.previous {
+                Some(previous) => event.crossed(previous, current),
+                None => false,
+            };
+            maneuver.previous = Some(current);
+            ready = ready && crossed;
+        }
+        ready = ready && warm;
+
+        // Only a numerically integrated orbit keeps the new velocity
+        if ready && !numerical && maneuver.start.is_none() {
+            maneuver.executed = true;
+            let event = format!(
+                "Maneuver '{}' ignored, orbit is not numerical",
+                maneuver.name
+            );
+            cubesat.history.log_event(time.now, event);
+            continue;
+        }
+
+        // Not enough battery, burns wait or stop until safe mode ends
+        if cubesat.safe_mode && (ready || maneuver.firing) {
+            if let Some(start) = maneuver.start.take() {
+                // Only the remaining part of a finite burn fires later
+                if let Some(duration) = maneuver.duration.as_mut() {
+                    *duration -= time.now - start;
+                }
+                let event = format!("Maneuver '{}' stopped, safe mode", maneuver.name);
+                cubesat.history.log_event(time.now, event);
+            } else if !maneuver.deferred {
+                let event = format!("Maneuver '{}' deferred, safe mode", maneuver.name);
+                cubesat.history.log_event(time.now, event);
+            }
+            maneuver.firing = false;
+            maneuver.deferred = true;
+            continue;
+        }
+        maneuver.deferred = false;
+
+        match maneuver.delta_v {
+            // Impulsive
+            Some(dv) => {
+                if !ready {
+                    continue;
+                }
+                // Without Isp and mass the burn would cost no propellant
+                let mass = cubesat.mass.ok_or_else(|| CubeSatError::missing("mass"))?;
+                let needed = maneuver.propellant_impulsive(mass).ok_or_else(|| {
+                    CubeSatError::missing(&format!("Isp for maneuver '{}'", maneuver.name))
+                })?;
+                maneuver.executed = true;
+                if let Some(propellant) = propellant.as_deref_mut() {
+                    if *propellant < needed {
+                        let event = format!(
+                            "Maneuver '{}' refused, not enough propellant",
+                            maneuver.name
+                        );
+                        cubesat.history.log_event(time.now, event);
+                        continue;
+                    }
+                    *propellant -= needed;
+                }
+                if let Some(mass) = cubesat.mass.as_mut() {
+                    *mass -= needed;
+                }
+                let dv = maneuver.frame.to_inertial(&dv, &pos, vel);
+                *vel = vel.add(&dv);
+                maneuver.firing = true;
+                let event = format!(
+                    "Maneuver '{}' executed, dv = {} m/s",
+                    maneuver.name,
+                    dv.abs()
+                );
+                cubesat.history.log_event(time.now, event);
+            }
+            // Finite
+            None => {
+                if maneuver.start.is_none() {
+                    if !ready {
+                        continue;
+                    }
+                    maneuver.start = Some(time.now);
+                    maneuver.firing = true;
+                    let event = format!("Maneuver '{}' started", maneuver.name);
+                    cubesat.history.log_event(time.now, event);
+                }
+
+                // End of burn
+                let start = maneuver.start.expect("No burn start is set!");
+                let duration = maneuver.duration.expect("No burn duration is set!");
+                if time.now - start + 0.5 * time.step >= duration {
+                    maneuver.firing = false;
+                    maneuver.executed = true;
+                    let event = format!("Maneuver '{}' ended", maneuver.name);
+                    cubesat.history.log_event(time.now, event);
+                    continue;
+                }
+
+                // Propellant for the next step
+                let needed = maneuver.propellant_rate() * time.step;
+                if let Some(propellant) = propellant.as_deref_mut() {
+                    if *propellant < needed {
+                        maneuver.firing = false;
+                        maneuver.executed = true;
+                        let event =
+                            format!("Maneuver '{}' stopped, propellant depleted", maneuver.name);
+                        cubesat.history.log_event(time.now, event);
+                        continue;
+                    }
+                    *propellant -= needed;
+                }
+                if let Some(mass) = cubesat.mass.as_mut() {
+                    *mass -= needed;
+                }
+            }
+        }
+    }
+
+    // Valves are open while any thruster fires
+    if let Some(p) = cubesat.propulsion.as_mut() {
+        p.valve_open = maneuvers.iter().any(|m| m.firing);
+    }
+    Ok(())
+}
@@ -2,6 +2,9 @@
 mod tests;
 
 use crate::cubesat;
+use crate::error::CubeSatError;
+use crate::maneuver;
+use crate::time;
 use crate::vector;
 
 use serde::Deserialize;
@@ -9,14 +12,463 @@ use serde_repr::Deserialize_repr;
 
 static CONST_G: f64 = 6.674_301_5e-11; // [N*m^2*kg^-2]
 static MASS_EARTH: f64 = 5.972_2e24; // [kg]
-static CONST_MU: f64 = CONST_G * MASS_EARTH;
+pub static CONST_MU: f64 = CONST_G * MASS_EARTH;
 pub static RADIUS_EARTH: f64 = 6.378_1e6; // [m]
+pub static SOLAR_PRESSURE: f64 = 4.56e-6; // [N/m^2] at 1 AU
+pub static SOLAR_CONSTANT: f64 = 1_361.0; // [W/m^2] at 1 AU
+pub static ASTRONOMICAL_UNIT: f64 = 1.495_978_707e11; // [m]
+pub static MU_SUN: f64 = 1.327_124_400_18e20; // [m^3/s^2]
+pub static MU_MOON: f64 = 4.904_869_5e12; // [m^3/s^2]
+pub static OBLIQUITY: f64 = 23.439_291_11; // [deg]
+
+// Exponential atmosphere: base altitude [km], base density [kg/m^3], scale height [km]
+static ATMOSPHERE: [(f64, f64, f64); 28] = [
+    (0.0, 1.225, 7.249),
+    (25.0, 3.899e-2, 6.349),
+    (30.0, 1.774e-2, 6.682),
+    (40.0, 3.972e-3, 7.554),
+    (50.0, 1.057e-3, 8.382),
+    (60.0, 3.206e-4, 7.714),
+    (70.0, 8.770e-5, 6.549),
+    (80.0, 1.905e-5, 5.799),
+    (90.0, 3.396e-6, 5.382),
+    (100.0, 5.297e-7, 5.877),
+    (110.0, 9.661e-8, 7.263),
+    (120.0, 2.438e-8, 9.473),
+    (130.0, 8.484e-9, 12.636),
+    (140.0, 3.845e-9, 16.149),
+    (150.0, 2.070e-9, 22.523),
+    (180.0, 5.464e-10, 29.740),
+    (200.0, 2.789e-10, 37.105),
+    (250.0, 7.248e-11, 45.546),
+    (300.0, 2.418e-11, 53.628),
+    (350.0, 9.518e-12, 53.298),
+    (400.0, 3.725e-12, 58.515),
+    (450.0, 1.585e-12, 60.828),
+    (500.0, 6.967e-13, 63.822),
+    (600.0, 1.454e-13, 71.835),
+    (700.0, 3.614e-14, 88.667),
+    (800.0, 1.170e-14, 124.64),
+    (900.0, 5.245e-15, 181.05),
+    (1000.0, 3.019e-15, 268.00),
+];
 
 #[derive(Debug, PartialEq, Deserialize_repr)]
 #[repr(u8)]
 pub enum OrbitType {
     CircularCosine,
     Parametric,
+    Numerical,
+}
+
+#[derive(Debug, PartialEq, Deserialize_repr)]
+#[repr(u8)]
+pub enum SrpModel {
+    Cannonball,
+    Faces,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct SolarRadiationPressure {
+    pub model: SrpModel,
+    #[serde(default = "SolarRadiationPressure::default_area")]
+    pub area: f64, // [m^2] Cannonball only
+    #[serde(default = "SolarRadiationPressure::default_reflectivity")]
+    pub reflectivity: f64, // Cannonball only, 1.0 = absorbing, 2.0 = reflecting
+}
+
+impl SolarRadiationPressure {
+    pub fn acceleration(
+        &self,
+        cubesat: &cubesat::CubeSat,
+        pos: &vector::Vector3,
+    ) -> Result<vector::Vector3, CubeSatError> {
+        // Guards
+        let sun = &cubesat.sun.ok_or_else(|| CubeSatError::missing("sun"))?;
+        let mass = cubesat.mass.ok_or_else(|| CubeSatError::missing("mass"))?;
+
+        // No pressure in eclipse
+        if in_eclipse(pos, sun) {
+            return Ok(vector::Vector3::origin());
+        }
+
+        // Force along the sunlight
+        let force = match self.model {
+            SrpModel::Cannonball => sun
+                .unit()
+                .scale(SOLAR_PRESSURE * self.reflectivity * self.area),
+            SrpModel::Faces => {
+                let rot = &cubesat
+                    .rot
+                    .ok_or_else(|| CubeSatError::missing("rotation"))?;
+                let geometry = cubesat
+                    .geometry
+                    .as_ref()
+                    .ok_or_else(|| CubeSatError::missing("geometry"))?;
+                geometry
+                    .solar_radiation_force(&sun.to_body(rot), SOLAR_PRESSURE)
+                    .to_inertial(rot)
+            }
+        };
+        Ok(force.scale(1.0 / mass))
+    }
+
+    // Default values for deserialization
+    fn default_area() -> f64 {
+        0.01
+    }
+    fn default_reflectivity() -> f64 {
+        1.3
+    }
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct ThirdBody {
+    #[serde(default = "ThirdBody::default_enabled")]
+    pub sun: bool,
+    #[serde(default = "ThirdBody::default_enabled")]
+    pub moon: bool,
+}
+
+impl ThirdBody {
+    pub fn acceleration(&self, pos: &vector::Vector3, julian_date: f64) -> vector::Vector3 {
+        let mut acc = vector::Vector3::origin();
+        if self.sun {
+            acc = acc.add(&third_body_acceleration(
+                pos,
+                &sun_position(julian_date),
+                MU_SUN,
+            ));
+        }
+        if self.moon {
+            acc = acc.add(&third_body_acceleration(
+                pos,
+                &moon_position(julian_date),
+                MU_MOON,
+            ));
+        }
+        acc
+    }
+
+    // Default values for deserialization
+    fn default_enabled() -> bool {
+        false
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ReflectivityGrid {
+    latitudes: Vec<f64>,  // [deg] ascending
+    longitudes: Vec<f64>, // [deg] ascending
+    values: Vec<f64>,     // Latitude-major
+}
+
+impl ReflectivityGrid {
+    pub fn from_file(path: &str) -> Result<Self, CubeSatError> {
+        let file = std::fs::read_to_string(path).map_err(|e| CubeSatError::io(path, &e))?;
+
+        // Lines: latitude longitude reflectivity, other lines are skipped
+        let mut points = Vec::new();
+        for line in file.lines() {
+            let values: Vec<f64> = line
+                .split_whitespace()
+                .map_while(|v| v.parse::<f64>().ok())
+                .collect();
+            if values.len() >= 3 {
+                points.push((values[0], values[1], values[2]));
+            }
+        }
+
+        // Regular grid from the distinct coordinates
+        let mut latitudes: Vec<f64> = points.iter().map(|p| p.0).collect();
+        let mut longitudes: Vec<f64> = points.iter().map(|p| p.1).collect();
+        for axis in [&mut latitudes, &mut longitudes] {
+            axis.sort_by(|a, b| a.total_cmp(b));
+            axis.dedup();
+        }
+        let mut values = vec![0.0; latitudes.len() * longitudes.len()];
+        for (lat, lon, value) in points {
+            let i = latitudes.iter().position(|l| *l == lat).unwrap();
+            let j = longitudes.iter().position(|l| *l == lon).unwrap();
+            values[i * longitudes.len() + j] = value;
+        }
+        Ok(ReflectivityGrid {
+            latitudes,
+            longitudes,
+            values,
+        })
+    }
+
+    pub fn value(&self, latitude: f64, longitude: f64) -> f64 {
+        // Nearest grid point, longitude wraps around
+        let nearest = |axis: &Vec<f64>, x: f64, wrap: bool| {
+            let distance = |a: f64| match wrap {
+                true => ((a - x + 540.0).rem_euclid(360.0) - 180.0).abs(),
+                false => (a - x).abs(),
+            };
+            (0..axis.len())
+                .min_by(|a, b| distance(axis[*a]).total_cmp(&distance(axis[*b])))
+                .unwrap_or(0)
+        };
+        let i = nearest(&self.latitudes, latitude, false);
+        let j = nearest(&self.longitudes, longitude, true);
+        self.values
+            .get(i * self.longitudes.len() + j)
+            .cloned()
+            .unwrap_or(0.0)
+    }
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct Albedo {
+    #[serde(default = "Albedo::default_albedo")]
+    pub albedo: f64, // Constant reflectivity
+    pub path: Option<String>, // Gridded reflectivity: latitude longitude value
+    #[serde(default = "Albedo::default_zero")]
+    pub infrared: f64, // [W/m^2] Earth emitted exitance
+    #[serde(default = "Albedo::default_resolution")]
+    pub resolution: f64, // [deg] Earth surface grid
+    #[serde(skip)]
+    pub grid: Option<ReflectivityGrid>,
+}
+
+impl Albedo {
+    #[allow(unused)]
+    pub fn new(albedo: f64, infrared: f64) -> Self {
+        Albedo {
+            albedo,
+            path: None,
+            infrared,
+            resolution: Albedo::default_resolution(),
+            grid: None,
+        }
+    }
+
+    pub fn load(&mut self) -> Result<(), CubeSatError> {
+        if let (Some(path), None) = (&self.path, &self.grid) {
+            self.grid = Some(ReflectivityGrid::from_file(path)?);
+        }
+        Ok(())
+    }
+
+    pub fn irradiance(
+        &self,
+        pos: &vector::Vector3,
+        normal: &vector::Vector3,
+        to_sun: &vector::Vector3,
+        flux: f64,
+        julian_date: f64,
+    ) -> (f64, f64) {
+        // Reflected sunlight and emitted infrared on a plate [W/m^2], Lambertian Earth
+        // E = sum(rho * S * cos(sun) * cos(sat) * cos(plate) * dA / (pi * d^2))
+        let step = self.resolution.to_radians();
+        let angle = earth_rotation_angle(julian_date);
+        let normal = normal.unit();
+        let to_sun = to_sun.unit();
+        let (mut reflected, mut emitted) = (0.0, 0.0);
+        let rows = (180.0 / self.resolution).round() as usize;
+        let columns = (360.0 / self.resolution).round() as usize;
+        for i in 0..rows {
+            let latitude = -std::f64::consts::FRAC_PI_2 + (i as f64 + 0.5) * step;
+            let area = RADIUS_EARTH.powi(2) * latitude.cos() * step * step;
+            for j in 0..columns {
+                let longitude = -std::f64::consts::PI + (j as f64 + 0.5) * step;
+                let surface = vector::Vector3::new(
+                    latitude.cos() * longitude.cos(),
+                    latitude.cos() * longitude.sin(),
+                    latitude.sin(),
+                )
+                .rot_z(angle);
+
+                // Visible from the satellite and in front of the plate
+                let line = pos.sub(&surface.scale(RADIUS_EARTH));
+                let distance = line.abs();
+                let cos_sat = surface.dot(&line) / distance;
+                let cos_plate = -normal.dot(&line) / distance;
+                if cos_sat <= 0.0 || cos_plate <= 0.0 {
+                    continue;
+                }
+                let view =
+                    cos_sat * cos_plate * area / (std::f64::consts::PI * distance * distance);
+                emitted += self.infrared * view;
+
+                // Sunlit
+                let cos_sun = surface.dot(&to_sun);
+                if cos_sun > 0.0 {
+                    let reflectivity = match &self.grid {
+                        Some(grid) => grid.value(latitude.to_degrees(), longitude.to_degrees()),
+                        None => self.albedo,
+                    };
+                    reflected += reflectivity * flux * cos_sun * view;
+                }
+            }
+        }
+        (reflected, emitted)
+    }
+
+    // Default values for deserialization
+    fn default_albedo() -> f64 {
+        0.3
+    }
+    fn default_zero() -> f64 {
+        0.0
+    }
+    fn default_resolution() -> f64 {
+        5.0
+    }
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct GravityFieldParameters {
+    pub path: String,
+    pub degree: usize,
+    pub order: usize,
+    #[serde(default = "GravityFieldParameters::default_gm")]
+    pub gm: f64, // [m^3/s^2]
+    #[serde(default = "GravityFieldParameters::default_radius")]
+    pub radius: f64, // [m]
+}
+
+impl GravityFieldParameters {
+    // Default values for deserialization (EGM96/EGM2008)
+    fn default_gm() -> f64 {
+        3.986_004_415e14
+    }
+    fn default_radius() -> f64 {
+        6_378_136.3
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct GravityField {
+    pub path: String,
+    pub degree: usize,
+    pub order: usize,
+    pub gm: f64,      // [m^3/s^2]
+    pub radius: f64,  // [m]
+    c: Vec<Vec<f64>>, // Normalized
+    s: Vec<Vec<f64>>, // Normalized
+}
+
+impl GravityField {
+    pub fn new(parameters: &GravityFieldParameters) -> Self {
+        GravityField {
+            path: parameters.path.clone(),
+            degree: parameters.degree,
+            order: parameters.order.min(parameters.degree),
+            gm: parameters.gm,
+            radius: parameters.radius,
+            c: vec![vec![0.0; parameters.degree + 1]; parameters.degree + 1],
+            s: vec![vec![0.0; parameters.degree + 1]; parameters.degree + 1],
+        }
+    }
+
+    pub fn from_file(parameters: &GravityFieldParameters) -> Result<Self, CubeSatError> {
+        let file = std::fs::read_to_string(&parameters.path)
+            .map_err(|e| CubeSatError::io(&parameters.path, &e))?;
+        let mut field = GravityField::new(parameters);
+
+        // Lines: n m Cnm Snm [sigmas], Fortran 'D' exponents are allowed
+        for line in file.lines() {
+            let values: Vec<&str> = line.split_whitespace().collect();
+            if values.len() < 4 {
+                continue;
+            }
+            let (n, m) = match (values[0].parse::<usize>(), values[1].parse::<usize>()) {
+                (Ok(n), Ok(m)) => (n, m),
+                _ => continue,
+            };
+            let parse = |v: &str| v.replace(['D', 'd'], "E").parse::<f64>();
+            if let (Ok(c), Ok(s)) = (parse(values[2]), parse(values[3])) {
+                field.set_coefficient(n, m, c, s);
+            }
+        }
+        Ok(field)
+    }
+
+    pub fn set_coefficient(&mut self, n: usize, m: usize, c: f64, s: f64) {
+        // Truncate to the requested degree and order
+        if n <= self.degree && m <= self.order.min(n) {
+            self.c[n][m] = c;
+            self.s[n][m] = s;
+        }
+    }
+
+    pub fn acceleration(&self, pos: &vector::Vector3, julian_date: f64) -> vector::Vector3 {
+        // Earth-fixed position
+        let angle = earth_rotation_angle(julian_date);
+        let pos = pos.rot_z(-angle);
+
+        // Fully normalized V and W (Montenbruck & Gill), up to degree + 1
+        let size = self.degree + 1;
+        let (r2, re) = (pos.dot(&pos), self.radius);
+        let (x0, y0, z0) = (re * pos.x / r2, re * pos.y / r2, re * pos.z / r2);
+        let rho = re * re / r2;
+        let mut v = vec![vec![0.0; size + 1]; size + 1];
+        let mut w = vec![vec![0.0; size + 1]; size + 1];
+        v[0][0] = re / r2.sqrt();
+        for m in 0..=(self.order + 1).min(size) {
+            // Diagonal
+            if m > 0 {
+                let mf = m as f64;
+                let a = match m {
+                    1 => 3.0_f64.sqrt(),
+                    _ => ((2.0 * mf + 1.0) / (2.0 * mf)).sqrt(),
+                };
+                v[m][m] = a * (x0 * v[m - 1][m - 1] - y0 * w[m - 1][m - 1]);
+                w[m][m] = a * (x0 * w[m - 1][m - 1] + y0 * v[m - 1][m - 1]);
+            }
+            // Vertical
+            for n in (m + 1)..=size {
+                let (nf, mf) = (n as f64, m as f64);
+                let a = ((2.0 * nf + 1.0) * (2.0 * nf - 1.0) / ((nf - mf) * (nf + mf))).sqrt();
+                v[n][m] = a * z0 * v[n - 1][m];
+                w[n][m] = a * z0 * w[n - 1][m];
+                if n >= m + 2 {
+                    let b = ((2.0 * nf + 1.0) * (nf + mf - 1.0) * (nf - mf - 1.0)
+                        / ((2.0 * nf - 3.0) * (nf + mf) * (nf - mf)))
+                        .sqrt();
+                    v[n][m] -= b * rho * v[n - 2][m];
+                    w[n][m] -= b * rho * w[n - 2][m];
+                }
+            }
+        }
+
+        // Accelerations without the central term, normalization ratios are folded into the factors
+        let (mut ax, mut ay, mut az) = (0.0, 0.0, 0.0);
+        for n in 2..=self.degree {
+            let nf = n as f64;
+            let k = (2.0 * nf + 1.0) / (2.0 * nf + 3.0);
+            for m in 0..=self.order.min(n) {
+                let mf = m as f64;
+                let (c, s) = (self.c[n][m], self.s[n][m]);
+                let vertical = (k * (nf + mf + 1.0) * (nf - mf + 1.0)).sqrt();
+                if m == 0 {
+                    let up = (k * (nf + 2.0) * (nf + 1.0) / 2.0).sqrt();
+                    ax -= c * up * v[n + 1][1];
+                    ay -= c * up * w[n + 1][1];
+                } else {
+                    let up = (k * (nf + mf + 2.0) * (nf + mf + 1.0)).sqrt();
+                    let down = match m {
+                        1 => (2.0 * k * (nf - mf + 2.0) * (nf - mf + 1.0)).sqrt(),
+                        _ => (k * (nf - mf + 2.0) * (nf - mf + 1.0)).sqrt(),
+                    };
+                    ax += 0.5
+                        * (-up * (c * v[n + 1][m + 1] + s * w[n + 1][m + 1])
+                            + down * (c * v[n + 1][m - 1] + s * w[n + 1][m - 1]));
+                    ay += 0.5
+                        * (up * (-c * w[n + 1][m + 1] + s * v[n + 1][m + 1])
+                            + down * (-c * w[n + 1][m - 1] + s * v[n + 1][m - 1]));
+                }
+                az += vertical * (-c * v[n + 1][m] - s * w[n + 1][m]);
+            }
+        }
+
+        // Back to the inertial frame
+        vector::Vector3::new(ax, ay, az)
+            .scale(self.gm / (re * re))
+            .rot_z(angle)
+    }
 }
 
 #[derive(Debug, PartialEq, Deserialize)]
@@ -40,56 +492,463 @@ impl OrbitParameters {
         }
     }
 
+    #[allow(unused)]
     pub fn set_inclination(&mut self, inclination: f64) {
         self.inclination = Some(inclination);
     }
 
+    #[allow(unused)]
     pub fn set_argument_of_periapsis(&mut self, argument_of_periapsis: f64) {
         self.argument_of_periapsis = Some(argument_of_periapsis);
     }
 
+    #[allow(unused)]
     pub fn set_longitude_of_ascending_node(&mut self, longitude_of_ascending_node: f64) {
         self.longitude_of_ascending_node = Some(longitude_of_ascending_node);
     }
 
+    #[allow(unused)]
     pub fn set_semi_major_axis(&mut self, semi_major_axis: f64) {
         self.semi_major_axis = Some(semi_major_axis);
     }
 
+    #[allow(unused)]
     pub fn set_eccentricity(&mut self, eccentricity: f64) {
         self.eccentricity = Some(eccentricity);
     }
+
+    pub fn period(&self) -> Option<f64> {
+        self.semi_major_axis
+            .map(|a| 2.0 * std::f64::consts::PI * (a.powi(3) / CONST_MU).sqrt())
+    }
+}
+
+pub fn in_eclipse(pos: &vector::Vector3, sun: &vector::Vector3) -> bool {
+    // The conditions are:
+    // 1. 0 <= |pos| * sin(acos(( dot(pos,sun)) / (|pos|*|sun|) ))  <= RADIUS_EARTH
+    // 2. Angle between position and sun vectors is less than PI/2
+    let pos_dot_sun = pos.dot(sun);
+    let pos_times_sun = pos.abs() * sun.abs();
+    let inner = pos_dot_sun / pos_times_sun;
+
+    // Evaluate angle
+    let angle = inner.acos();
+    assert!(!angle.is_nan(), "angle is NaN!");
+
+    // Evaluate result
+    let result = pos.abs() * angle.sin();
+    (0.0 <= result && result <= RADIUS_EARTH) && (angle < std::f64::consts::FRAC_PI_2)
+}
+
+pub fn state_from_parameters(
+    parameters: &OrbitParameters,
+    t: f64,
+) -> Result<(vector::Vector3, vector::Vector3), CubeSatError> {
+    // Guards and values
+    let semi = parameters
+        .semi_major_axis
+        .ok_or_else(|| CubeSatError::missing("semi-major axis"))?;
+    let inc = parameters
+        .inclination
+        .ok_or_else(|| CubeSatError::missing("inclination"))?;
+    let ap = parameters
+        .argument_of_periapsis
+        .ok_or_else(|| CubeSatError::missing("argument of periapsis"))?;
+    let lan = parameters
+        .longitude_of_ascending_node
+        .ok_or_else(|| CubeSatError::missing("longitude of ascending node"))?;
+    let ecc = parameters.eccentricity.unwrap_or(0.0);
+    check_elements(semi, ecc)?;
+    let ang_to_rad = std::f64::consts::PI / 180.0;
+
+    // 1. Solve Kepler's equation M = E - e * sin(E) using Newton's method, periapsis at t = 0
+    let n = (CONST_MU / semi.powi(3)).sqrt();
+    let mean = (n * t).rem_euclid(2.0 * std::f64::consts::PI);
+    let mut ecc_anomaly = mean;
+    let mut converged = false;
+    for _ in 0..50 {
+        let delta =
+            (ecc_anomaly - ecc * ecc_anomaly.sin() - mean) / (1.0 - ecc * ecc_anomaly.cos());
+        ecc_anomaly -= delta;
+        if delta.abs() < 1e-14 {
+            converged = true;
+            break;
+        }
+    }
+    if !converged && (ecc_anomaly - ecc * ecc_anomaly.sin() - mean).abs() > 1e-9 {
+        return Err(CubeSatError::NumericalFailure(format!(
+            "Kepler's equation did not converge at t = {t} s"
+        )));
+    }
+
+    // 2. True anomaly
+    let v = 2.0
+        * ((1.0 + ecc).sqrt() * (ecc_anomaly / 2.0).sin())
+            .atan2((1.0 - ecc).sqrt() * (ecc_anomaly / 2.0).cos());
+
+    // 3. Position and velocity in the orbital plane, rotated like the parametric orbit
+    let p = semi * (1.0 - ecc.powi(2));
+    let r = p / (1.0 + ecc * v.cos());
+    let pos = vector::Vector3::new(r * v.cos(), r * v.sin(), 0.0)
+        .rot_z(ap * ang_to_rad)
+        .rot_y(inc * ang_to_rad)
+        .rot_z(lan * ang_to_rad);
+    let vel = vector::Vector3::new(
+        -(CONST_MU / p).sqrt() * v.sin(),
+        (CONST_MU / p).sqrt() * (ecc + v.cos()),
+        0.0,
+    )
+    .rot_z(ap * ang_to_rad)
+    .rot_y(inc * ang_to_rad)
+    .rot_z(lan * ang_to_rad);
+
+    Ok((pos, vel))
+}
+
+fn check_elements(semi: f64, ecc: f64) -> Result<(), CubeSatError> {
+    // Bound elliptical orbits only
+    if semi.is_nan() || semi <= 0.0 {
+        return Err(CubeSatError::InvalidParameter(format!(
+            "semi-major axis {semi} m is not positive"
+        )));
+    }
+    if ecc.is_nan() || !(0.0..1.0).contains(&ecc) {
+        return Err(CubeSatError::InvalidParameter(format!(
+            "eccentricity {ecc} is not in [0, 1)"
+        )));
+    }
+    Ok(())
+}
+
+pub fn sun_position(julian_date: f64) -> vector::Vector3 {
+    // Low precision solar ephemeris (Montenbruck & Gill), geocentric, equatorial [m]
+    let ang_to_rad = std::f64::consts::PI / 180.0;
+    let t = (julian_date - time::J2000) / time::CENTURY;
+    let m = (357.525_6 + 35_999.049 * t) * ang_to_rad;
+    let longitude = (282.94 + (357.525_6 + 35_999.049 * t)) * ang_to_rad
+        + (6_892.0 * m.sin() + 72.0 * (2.0 * m).sin()) / 3_600.0 * ang_to_rad;
+    let distance = (149.619 - 2.499 * m.cos() - 0.021 * (2.0 * m).cos()) * 1e9;
+
+    vector::Vector3::new(distance * longitude.cos(), distance * longitude.sin(), 0.0)
+        .rot_x(OBLIQUITY * ang_to_rad)
+}
+
+pub fn sun_direction(julian_date: f64) -> vector::Vector3 {
+    // Direction of the sunlight, from the sun towards the Earth
+    sun_position(julian_date).negative().unit()
+}
+
+pub fn moon_position(julian_date: f64) -> vector::Vector3 {
+    // Low precision lunar ephemeris (Montenbruck & Gill), geocentric, equatorial [m]
+    let ang_to_rad = std::f64::consts::PI / 180.0;
+    let arcsec = ang_to_rad / 3_600.0;
+    let t = (julian_date - time::J2000) / time::CENTURY;
+
+    // Mean arguments
+    let l0 = (218.316_17 + 481_267.880_88 * t - 1.397_2 * t) * ang_to_rad;
+    let l = (134.962_92 + 477_198.867_53 * t) * ang_to_rad;
+    let lp = (357.525_43 + 35_999.049_44 * t) * ang_to_rad;
+    let f = (93.272_83 + 483_202.018_73 * t) * ang_to_rad;
+    let d = (297.850_27 + 445_267.111_35 * t) * ang_to_rad;
+
+    // Ecliptic longitude, latitude and distance
+    let longitude = l0
+        + (22_640.0 * l.sin() + 769.0 * (2.0 * l).sin() - 4_586.0 * (l - 2.0 * d).sin()
+            + 2_370.0 * (2.0 * d).sin()
+            - 668.0 * lp.sin()
+            - 412.0 * (2.0 * f).sin()
+            - 212.0 * (2.0 * l - 2.0 * d).sin()
+            - 206.0 * (l + lp - 2.0 * d).sin()
+            + 192.0 * (l + 2.0 * d).sin()
+            - 165.0 * (lp - 2.0 * d).sin()
+            + 148.0 * (l - lp).sin()
+            - 125.0 * d.sin()
+            - 110.0 * (l + lp).sin()
+            - 55.0 * (2.0 * f - 2.0 * d).sin())
+            * arcsec;
+    let latitude = (18_520.0
+        * (f + longitude - l0 + (412.0 * (2.0 * f).sin() + 541.0 * lp.sin()) * arcsec).sin()
+        - 526.0 * (f - 2.0 * d).sin()
+        + 44.0 * (l + f - 2.0 * d).sin()
+        - 31.0 * (-l + f - 2.0 * d).sin()
+        - 25.0 * (-2.0 * l + f).sin()
+        - 23.0 * (lp + f - 2.0 * d).sin()
+        + 21.0 * (-l + f).sin()
+        + 11.0 * (-lp + f - 2.0 * d).sin())
+        * arcsec;
+    let distance = (385_000.0
+        - 20_905.0 * l.cos()
+        - 3_699.0 * (2.0 * d - l).cos()
+        - 2_956.0 * (2.0 * d).cos()
+        - 570.0 * (2.0 * l).cos()
+        + 246.0 * (2.0 * l - 2.0 * d).cos()
+        - 205.0 * (lp - 2.0 * d).cos()
+        - 171.0 * (l + 2.0 * d).cos()
+        - 152.0 * (l + lp - 2.0 * d).cos())
+        * 1e3;
+
+    vector::Vector3::new(
+        distance * longitude.cos() * latitude.cos(),
+        distance * longitude.sin() * latitude.cos(),
+        distance * latitude.sin(),
+    )
+    .rot_x(OBLIQUITY * ang_to_rad)
+}
+
+pub fn earth_rotation_angle(julian_date: f64) -> f64 {
+    // Greenwich mean sidereal time [rad]
+    let ang_to_rad = std::f64::consts::PI / 180.0;
+    let days = julian_date - time::J2000;
+    ((280.460_618_37 + 360.985_647_366_29 * days) * ang_to_rad)
+        .rem_euclid(2.0 * std::f64::consts::PI)
+}
+
+pub fn subsatellite_point(pos: &vector::Vector3, julian_date: f64) -> (f64, f64) {
+    // Geocentric latitude and longitude [deg], spherical Earth
+    let latitude = (pos.z / pos.abs()).asin().to_degrees();
+    let longitude = (pos.y.atan2(pos.x) - earth_rotation_angle(julian_date)).to_degrees();
+    (latitude, (longitude + 180.0).rem_euclid(360.0) - 180.0)
+}
+
+pub fn true_anomaly(pos: &vector::Vector3, vel: &vector::Vector3) -> f64 {
+    // Angle from periapsis [deg], from the ascending node or x axis for circular orbits
+    let r = pos.abs();
+    let ecc = pos
+        .scale(vel.dot(vel) - CONST_MU / r)
+        .sub(&vel.scale(pos.dot(vel)))
+        .scale(1.0 / CONST_MU);
+    let node = vector::Vector3::new(0.0, 0.0, 1.0).cross(&pos.cross(vel));
+    let reference = match (ecc.abs() > 1e-6, node.abs() > 1e-6) {
+        (true, _) => ecc,
+        (false, true) => node,
+        (false, false) => vector::Vector3::new(1.0, 0.0, 0.0),
+    };
+    let angle = (reference.dot(pos) / (reference.abs() * r))
+        .clamp(-1.0, 1.0)
+        .acos()
+        .to_degrees();
+    let rising = match ecc.abs() > 1e-6 {
+        true => pos.dot(vel) >= 0.0,
+        false => reference.cross(pos).dot(&pos.cross(vel)) >= 0.0,
+    };
+    match rising {
+        true => angle,
+        false => 360.0 - angle,
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct GroundStation {
+    pub name: String,
+    pub latitude: f64,  // [deg]
+    pub longitude: f64, // [deg]
+    #[serde(default = "GroundStation::default_altitude")]
+    pub altitude: f64, // [m]
+    #[serde(default = "GroundStation::default_min_elevation")]
+    pub min_elevation: f64, // [deg]
 }
 
-pub fn orbit_circular_cosine(cubesat: &mut cubesat::CubeSat) {
+impl GroundStation {
+    #[allow(unused)]
+    pub fn new(name: &str, latitude: f64, longitude: f64) -> Self {
+        GroundStation {
+            name: name.to_string(),
+            latitude,
+            longitude,
+            altitude: GroundStation::default_altitude(),
+            min_elevation: GroundStation::default_min_elevation(),
+        }
+    }
+
+    pub fn position(&self, julian_date: f64) -> vector::Vector3 {
+        // Inertial position, spherical Earth
+        let latitude = self.latitude.to_radians();
+        let longitude = self.longitude.to_radians() + earth_rotation_angle(julian_date);
+        vector::Vector3::new(
+            latitude.cos() * longitude.cos(),
+            latitude.cos() * longitude.sin(),
+            latitude.sin(),
+        )
+        .scale(RADIUS_EARTH + self.altitude)
+    }
+
+    pub fn elevation(&self, pos: &vector::Vector3, julian_date: f64) -> f64 {
+        // Angle of the satellite above the local horizon [deg]
+        let station = self.position(julian_date);
+        let line_of_sight = pos.sub(&station);
+        let sine = line_of_sight.dot(&station) / (line_of_sight.abs() * station.abs());
+        sine.clamp(-1.0, 1.0).asin().to_degrees()
+    }
+
+    pub fn in_contact(&self, pos: &vector::Vector3, julian_date: f64) -> bool {
+        self.elevation(pos, julian_date) >= self.min_elevation
+    }
+
+    // Default values for deserialization
+    fn default_altitude() -> f64 {
+        0.0
+    }
+    fn default_min_elevation() -> f64 {
+        10.0
+    }
+}
+
+pub fn third_body_acceleration(
+    pos: &vector::Vector3,
+    body: &vector::Vector3,
+    mu: f64,
+) -> vector::Vector3 {
+    // a = mu * ((s - r) / |s - r|^3 - s / |s|^3)
+    let relative = body.sub(pos);
+    relative
+        .scale(1.0 / relative.abs().powi(3))
+        .sub(&body.scale(1.0 / body.abs().powi(3)))
+        .scale(mu)
+}
+
+pub fn acceleration(
+    cubesat: &cubesat::CubeSat,
+    pos: &vector::Vector3,
+    vel: &vector::Vector3,
+    t: f64,
+) -> Result<vector::Vector3, CubeSatError> {
+    // Point mass Earth
+    let mut acc = pos.scale(-CONST_MU / pos.abs().powi(3));
+
+    // Perturbations
+    if let Some(srp) = &cubesat.solar_radiation_pressure {
+        acc = acc.add(&srp.acceleration(cubesat, pos)?);
+    }
+    let time = cubesat.time.ok_or_else(|| CubeSatError::missing("time"))?;
+    if let Some(third_body) = &cubesat.third_body {
+        acc = acc.add(&third_body.acceleration(pos, time.julian_date(t)));
+    }
+    if let Some(field) = &cubesat.gravity_model {
+        acc = acc.add(&field.acceleration(pos, time.julian_date(t)));
+    }
+
+    // Finite burns
+    if let Some(maneuvers) = &cubesat.maneuvers {
+        if maneuvers.iter().any(|m| m.firing) {
+            let mass = cubesat.mass.ok_or_else(|| CubeSatError::missing("mass"))?;
+            acc = acc.add(&maneuver::thrust_acceleration(maneuvers, mass, pos, vel));
+        }
+    }
+
+    Ok(acc)
+}
+
+pub fn orbit_numerical(cubesat: &mut cubesat::CubeSat) -> Result<(), CubeSatError> {
     // Guards and values
-    let pos = cubesat.pos.as_mut().expect("No position vector is set!");
-    let vel = cubesat.vel.as_mut().expect("No velocity vector is set!");
+    let time = cubesat.time.ok_or_else(|| CubeSatError::missing("time"))?;
+    let pos = cubesat
+        .pos
+        .ok_or_else(|| CubeSatError::missing("position vector"))?;
+    let vel = cubesat
+        .vel
+        .ok_or_else(|| CubeSatError::missing("velocity vector"))?;
+    let h = time.step;
+
+    // First step: use the given state vectors, or the orbit parameters if none are given
+    if time.now - time.start < 0.5 * h {
+        let (pos, vel) = match pos == vector::Vector3::origin() {
+            true => state_from_parameters(
+                cubesat
+                    .orbit_parameters
+                    .as_ref()
+                    .ok_or_else(|| CubeSatError::missing("orbit parameters"))?,
+                time.now,
+            )?,
+            false => (pos, vel),
+        };
+        cubesat.acc = Some(acceleration(cubesat, &pos, &vel, time.now)?);
+        cubesat.pos = Some(pos);
+        cubesat.vel = Some(vel);
+        return Ok(());
+    }
+
+    // Runge-Kutta 4 from the previous step
+    let t = time.now - h;
+    let f = |p: &vector::Vector3, v: &vector::Vector3, t: f64| acceleration(cubesat, p, v, t);
+    let k1_p = vel;
+    let k1_v = f(&pos, &vel, t)?;
+    let k2_p = vel.add(&k1_v.scale(h / 2.0));
+    let k2_v = f(&pos.add(&k1_p.scale(h / 2.0)), &k2_p, t + h / 2.0)?;
+    let k3_p = vel.add(&k2_v.scale(h / 2.0));
+    let k3_v = f(&pos.add(&k2_p.scale(h / 2.0)), &k3_p, t + h / 2.0)?;
+    let k4_p = vel.add(&k3_v.scale(h));
+    let k4_v = f(&pos.add(&k3_p.scale(h)), &k4_p, t + h)?;
+
+    let pos = pos.add(
+        &k1_p
+            .add(&k2_p.scale(2.0))
+            .add(&k3_p.scale(2.0))
+            .add(&k4_p)
+            .scale(h / 6.0),
+    );
+    let vel = vel.add(
+        &k1_v
+            .add(&k2_v.scale(2.0))
+            .add(&k3_v.scale(2.0))
+            .add(&k4_v)
+            .scale(h / 6.0),
+    );
+
+    // A diverging integration is not carried into the next step
+    let finite = |v: &vector::Vector3| v.x.is_finite() && v.y.is_finite() && v.z.is_finite();
+    if !finite(&pos) || !finite(&vel) {
+        return Err(CubeSatError::NumericalFailure(format!(
+            "orbit integration diverged at t = {} s",
+            time.now
+        )));
+    }
+
+    // Update state
+    cubesat.acc = Some(f(&pos, &vel, time.now)?);
+    cubesat.pos = Some(pos);
+    cubesat.vel = Some(vel);
+    Ok(())
+}
+
+pub fn orbit_circular_cosine(cubesat: &mut cubesat::CubeSat) -> Result<(), CubeSatError> {
+    // Guards and values
+    let pos = cubesat
+        .pos
+        .as_mut()
+        .ok_or_else(|| CubeSatError::missing("position vector"))?;
+    let vel = cubesat
+        .vel
+        .as_mut()
+        .ok_or_else(|| CubeSatError::missing("velocity vector"))?;
     let acc = cubesat
         .acc
         .as_mut()
-        .expect("No acceleration vector is set!");
+        .ok_or_else(|| CubeSatError::missing("acceleration vector"))?;
     let parameters = cubesat
         .orbit_parameters
         .as_ref()
-        .expect("No orbit parameters are set!");
+        .ok_or_else(|| CubeSatError::missing("orbit parameters"))?;
     let radius = parameters
         .semi_major_axis
         .as_ref()
-        .expect("No semi-major axis is set!");
+        .ok_or_else(|| CubeSatError::missing("semi-major axis"))?;
     let inc = parameters
         .inclination
         .as_ref()
-        .expect("No inclination is set!");
+        .ok_or_else(|| CubeSatError::missing("inclination"))?;
     let ap = parameters
         .argument_of_periapsis
         .as_ref()
-        .expect("No argument of periapsis is set!");
+        .ok_or_else(|| CubeSatError::missing("argument of periapsis"))?;
     let lan = parameters
         .longitude_of_ascending_node
         .as_ref()
-        .expect("No longitude of ascending node is set!");
-    let time = cubesat.time.as_ref().expect("No time is set!");
+        .ok_or_else(|| CubeSatError::missing("longitude of ascending node"))?;
+    let time = cubesat
+        .time
+        .as_ref()
+        .ok_or_else(|| CubeSatError::missing("time"))?;
+    check_elements(*radius, 0.0)?;
     let omega = (radius.powi(3) / (CONST_G * MASS_EARTH)).powf(-0.5);
     let ang_to_rad = std::f64::consts::PI / 180.0;
 
@@ -129,41 +988,56 @@ pub fn orbit_circular_cosine(cubesat: &mut cubesat::CubeSat) {
     .rot_z(*ap * ang_to_rad)
     .rot_y(*inc * ang_to_rad)
     .rot_z(*lan * ang_to_rad);
+    Ok(())
 }
 
 #[allow(non_snake_case)]
-pub fn orbit_parametric(cubesat: &mut cubesat::CubeSat) {
+pub fn orbit_parametric(cubesat: &mut cubesat::CubeSat) -> Result<(), CubeSatError> {
     // Guards and values
     let parameters = cubesat
         .orbit_parameters
         .as_ref()
-        .expect("No orbit parameters are set!");
+        .ok_or_else(|| CubeSatError::missing("orbit parameters"))?;
     let ecc = parameters
         .eccentricity
         .as_ref()
-        .expect("No eccentricity is set!");
-    if ecc.abs() < std::f64::EPSILON {
-        panic!("Parametric orbit not possible with e extremely close to 0.0! Use CircularCosine instead.");
+        .ok_or_else(|| CubeSatError::missing("eccentricity"))?;
+    if ecc.abs() < f64::EPSILON {
+        return Err(CubeSatError::InvalidParameter(
+            "parametric orbit not possible with e extremely close to 0.0, use CircularCosine instead"
+                .to_string(),
+        ));
     }
-    let t = cubesat.time.as_ref().expect("No time is set!").now;
-    let pos = cubesat.pos.as_mut().expect("No position vector is set!");
-    let vel = cubesat.vel.as_mut().expect("No velocity vector is set!");
+    let t = cubesat
+        .time
+        .as_ref()
+        .ok_or_else(|| CubeSatError::missing("time"))?
+        .now;
+    let pos = cubesat
+        .pos
+        .as_mut()
+        .ok_or_else(|| CubeSatError::missing("position vector"))?;
+    let vel = cubesat
+        .vel
+        .as_mut()
+        .ok_or_else(|| CubeSatError::missing("velocity vector"))?;
     let semi = parameters
         .semi_major_axis
         .as_ref()
-        .expect("No semi-major axis is set!");
+        .ok_or_else(|| CubeSatError::missing("semi-major axis"))?;
     let inc = parameters
         .inclination
         .as_ref()
-        .expect("No inclination is set!");
+        .ok_or_else(|| CubeSatError::missing("inclination"))?;
     let ap = parameters
         .argument_of_periapsis
         .as_ref()
-        .expect("No argument of periapsis is set!");
+        .ok_or_else(|| CubeSatError::missing("argument of periapsis"))?;
     let lan = parameters
         .longitude_of_ascending_node
         .as_ref()
-        .expect("No longitude of ascending node is set!");
+        .ok_or_else(|| CubeSatError::missing("longitude of ascending node"))?;
+    check_elements(*semi, *ecc)?;
     let PI = std::f64::consts::PI;
     let ang_to_rad = PI / 180.0;
 
@@ -221,4 +1095,309 @@ pub fn orbit_parametric(cubesat: &mut cubesat::CubeSat) {
     .rot_z(*lan * ang_to_rad);
 
     // (Acceleration?)
+    Ok(())
+}
+
+pub fn atmospheric_density(altitude: f64) -> f64 {
+    // Below the surface the sea-level density is used
+    let altitude = altitude / 1000.0; // [km]
+    if altitude < 0.0 {
+        return ATMOSPHERE[0].1;
+    }
+
+    // Find the band and extrapolate exponentially
+    let (base, density, scale) = ATMOSPHERE
+        .iter()
+        .rev()
+        .find(|(base, _, _)| *base <= altitude)
+        .expect("No atmosphere band found!");
+    density * (-(altitude - base) / scale).exp()
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Deserialize_repr)]
+#[repr(u8)]
+pub enum TransferKind {
+    Hohmann,
+    BiElliptic,
+    PlaneChange,
+    Phasing,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct TransferPlan {
+    #[serde(default = "TransferPlan::default_name")]
+    pub name: String,
+    pub kind: TransferKind,
+    #[serde(default = "TransferPlan::default_zero")]
+    pub start: f64, // [s]
+    pub target: OrbitParameters,
+    pub apoapsis: Option<f64>, // [m] Bi-elliptic only
+    #[serde(default = "TransferPlan::default_revolutions")]
+    pub revolutions: u32, // Phasing only
+    pub isp: Option<f64>,      // [s]
+}
+
+impl TransferPlan {
+    pub fn plan(&self, current: &OrbitParameters) -> Result<Transfer, CubeSatError> {
+        match self.kind {
+            TransferKind::Hohmann => hohmann(current, &self.target),
+            TransferKind::BiElliptic => {
+                let apoapsis = self.apoapsis.ok_or_else(|| {
+                    CubeSatError::missing(&format!("apoapsis for transfer '{}'", self.name))
+                })?;
+                bi_elliptic(current, &self.target, apoapsis)
+            }
+            TransferKind::PlaneChange => plane_change(current, &self.target, self.start),
+            TransferKind::Phasing => phasing(current, &self.target, self.revolutions, self.start),
+        }
+    }
+
+    // Default values for deserialization
+    fn default_name() -> String {
+        "Transfer".to_string()
+    }
+    fn default_zero() -> f64 {
+        0.0
+    }
+    fn default_revolutions() -> u32 {
+        1
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct TransferBurn {
+    pub time: f64,                // [s] after the start of the transfer
+    pub delta_v: vector::Vector3, // [m/s] VNB
+    pub event: Option<maneuver::OrbitEvent>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Transfer {
+    pub burns: Vec<TransferBurn>,
+    pub delta_v: f64,  // [m/s] Total
+    pub duration: f64, // [s]
+}
+
+impl Transfer {
+    fn new(burns: Vec<TransferBurn>, duration: f64) -> Self {
+        let delta_v = burns.iter().map(|b| b.delta_v.abs()).sum();
+        Transfer {
+            burns,
+            delta_v,
+            duration,
+        }
+    }
+
+    pub fn to_maneuvers(&self, name: &str, start: f64) -> Vec<maneuver::Maneuver> {
+        self.burns
+            .iter()
+            .enumerate()
+            .map(|(i, b)| {
+                let mut m = maneuver::Maneuver::impulsive(
+                    &format!("{name} {}", i + 1),
+                    start + b.time,
+                    maneuver::ManeuverFrame::Vnb,
+                    (b.delta_v.x, b.delta_v.y, b.delta_v.z),
+                );
+                m.event = b.event;
+                m
+            })
+            .collect()
+    }
+}
+
+pub fn schedule_toml(maneuvers: &[maneuver::Maneuver]) -> String {
+    // Impulsive burns in the [[maneuvers]] input format
+    let mut toml = String::new();
+    for m in maneuvers {
+        let dv = m.delta_v.expect("No delta-v is set!");
+        toml += "[[maneuvers]]\n";
+        toml += &format!("name = \"{}\"\n", m.name);
+        toml += &format!("time = {:?}\n", m.time);
+        if let Some(event) = m.event {
+            toml += &format!("event = {}\n", event as u8);
+        }
+        toml += &format!("frame = {}\n", m.frame as u8);
+        toml += &format!("delta_v = [{:?}, {:?}, {:?}]\n", dv.x, dv.y, dv.z);
+        if let Some(isp) = m.isp {
+            toml += &format!("isp = {isp:?}\n");
+        }
+        toml += "\n";
+    }
+    toml
+}
+
+fn circular_radius(parameters: &OrbitParameters) -> Result<f64, CubeSatError> {
+    let radius = parameters
+        .semi_major_axis
+        .ok_or_else(|| CubeSatError::missing("semi-major axis"))?;
+    check_elements(radius, 0.0)?;
+    Ok(radius)
+}
+
+fn orbit_normal(parameters: &OrbitParameters) -> Result<vector::Vector3, CubeSatError> {
+    // Rotated like the parametric orbit, the argument of periapsis stays in the plane
+    let inc = parameters
+        .inclination
+        .ok_or_else(|| CubeSatError::missing("inclination"))?;
+    let lan = parameters
+        .longitude_of_ascending_node
+        .ok_or_else(|| CubeSatError::missing("longitude of ascending node"))?;
+    Ok(vector::Vector3::new(0.0, 0.0, 1.0)
+        .rot_y(inc.to_radians())
+        .rot_z(lan.to_radians()))
+}
+
+pub fn hohmann(
+    current: &OrbitParameters,
+    target: &OrbitParameters,
+) -> Result<Transfer, CubeSatError> {
+    // Between circular orbits, prograde burns are positive
+    let (r1, r2) = (circular_radius(current)?, circular_radius(target)?);
+    let a = (r1 + r2) / 2.0;
+    let dv1 = (CONST_MU / r1).sqrt() * ((r2 / a).sqrt() - 1.0);
+    let dv2 = (CONST_MU / r2).sqrt() * (1.0 - (r1 / a).sqrt());
+    let duration = std::f64::consts::PI * (a.powi(3) / CONST_MU).sqrt();
+
+    Ok(Transfer::new(
+        vec![
+            TransferBurn {
+                time: 0.0,
+                delta_v: vector::Vector3::new(dv1, 0.0, 0.0),
+                event: None,
+            },
+            TransferBurn {
+                time: duration,
+                delta_v: vector::Vector3::new(dv2, 0.0, 0.0),
+                event: None,
+            },
+        ],
+        duration,
+    ))
+}
+
+pub fn bi_elliptic(
+    current: &OrbitParameters,
+    target: &OrbitParameters,
+    apoapsis: f64,
+) -> Result<Transfer, CubeSatError> {
+    // Between circular orbits via an intermediate apoapsis radius
+    let (r1, r2, rb) = (
+        circular_radius(current)?,
+        circular_radius(target)?,
+        apoapsis,
+    );
+    if rb.is_nan() || rb < r1.max(r2) {
+        return Err(CubeSatError::InvalidParameter(format!(
+            "bi-elliptic apoapsis {rb} m is below the current or target orbit"
+        )));
+    }
+    let (a1, a2) = ((r1 + rb) / 2.0, (r2 + rb) / 2.0);
+    let speed = |r: f64, a: f64| (CONST_MU * (2.0 / r - 1.0 / a)).sqrt();
+    let dv1 = speed(r1, a1) - speed(r1, r1);
+    let dv2 = speed(rb, a2) - speed(rb, a1);
+    let dv3 = speed(r2, r2) - speed(r2, a2);
+    let t1 = std::f64::consts::PI * (a1.powi(3) / CONST_MU).sqrt();
+    let t2 = std::f64::consts::PI * (a2.powi(3) / CONST_MU).sqrt();
+
+    Ok(Transfer::new(
+        vec![
+            TransferBurn {
+                time: 0.0,
+                delta_v: vector::Vector3::new(dv1, 0.0, 0.0),
+                event: None,
+            },
+            TransferBurn {
+                time: t1,
+                delta_v: vector::Vector3::new(dv2, 0.0, 0.0),
+                event: None,
+            },
+            TransferBurn {
+                time: t1 + t2,
+                delta_v: vector::Vector3::new(dv3, 0.0, 0.0),
+                event: None,
+            },
+        ],
+        t1 + t2,
+    ))
+}
+
+pub fn plane_change(
+    current: &OrbitParameters,
+    target: &OrbitParameters,
+    start: f64,
+) -> Result<Transfer, CubeSatError> {
+    // Angle between the planes from inclination and ascending node: dv = 2 * v * sin(theta / 2)
+    let r = circular_radius(current)?;
+    let (n1, n2) = (orbit_normal(current)?, orbit_normal(target)?);
+    let line = n1.cross(&n2);
+    if line.abs() < 1e-12 {
+        return Ok(Transfer::new(Vec::new(), 0.0));
+    }
+    let theta = line.abs().atan2(n1.dot(&n2));
+
+    // Burn at the next crossing of the target plane after the start
+    let (pos, _) = state_from_parameters(current, start)?;
+    let (p, l) = (pos.unit(), line.unit());
+    let angle = n1.dot(&p.cross(&l)).atan2(p.dot(&l));
+    let angle = angle.rem_euclid(std::f64::consts::PI);
+    let time = angle / (CONST_MU / r.powi(3)).sqrt();
+
+    // Tilt the velocity towards the target plane, normal component in VNB
+    let v = (CONST_MU / r).sqrt();
+    let (_, vel) = state_from_parameters(current, start + time)?;
+    let sign = -vel.dot(&n2).signum();
+    Ok(Transfer::new(
+        vec![TransferBurn {
+            time,
+            delta_v: vector::Vector3::new(v * (theta.cos() - 1.0), sign * v * theta.sin(), 0.0),
+            event: None,
+        }],
+        time,
+    ))
+}
+
+pub fn phasing(
+    current: &OrbitParameters,
+    target: &OrbitParameters,
+    revolutions: u32,
+    start: f64,
+) -> Result<Transfer, CubeSatError> {
+    // Catch up with (target ahead) or fall behind (target behind) a target in the same circular orbit
+    let r = circular_radius(current)?;
+    if revolutions == 0 {
+        return Err(CubeSatError::InvalidParameter(
+            "phasing needs at least one revolution".to_string(),
+        ));
+    }
+    let (pos, vel) = state_from_parameters(current, start)?;
+    let (other, _) = state_from_parameters(target, start)?;
+    let normal = pos.cross(&vel).unit();
+    let phase = normal
+        .dot(&pos.cross(&other))
+        .atan2(pos.dot(&other))
+        .to_degrees();
+
+    let period = 2.0 * std::f64::consts::PI * (r.powi(3) / CONST_MU).sqrt();
+    let k = revolutions as f64;
+    let phasing_period = period * (1.0 - phase / (360.0 * k));
+    let a = (CONST_MU * (phasing_period / (2.0 * std::f64::consts::PI)).powi(2)).cbrt();
+    let dv = (CONST_MU * (2.0 / r - 1.0 / a)).sqrt() - (CONST_MU / r).sqrt();
+    let duration = k * phasing_period;
+
+    Ok(Transfer::new(
+        vec![
+            TransferBurn {
+                time: 0.0,
+                delta_v: vector::Vector3::new(dv, 0.0, 0.0),
+                event: None,
+            },
+            TransferBurn {
+                time: duration,
+                delta_v: vector::Vector3::new(-dv, 0.0, 0.0),
+                event: None,
+            },
+        ],
+        duration,
+    ))
 }
@@ -0,0 +1,374 @@
+#[path = "./tests/schedule.rs"]
+mod tests;
+
+use crate::cubesat;
+use crate::error::CubeSatError;
+use crate::orbit;
+
+use serde::Deserialize;
+use serde_repr::Deserialize_repr;
+
+#[derive(Debug, PartialEq, Clone, Copy, Deserialize_repr)]
+#[repr(u8)]
+pub enum Logic {
+    And,
+    Or,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Deserialize_repr)]
+#[repr(u8)]
+pub enum ConditionKind {
+    Eclipse,
+    Sunlit,
+    Contact,  // Ground station in view
+    Latitude, // [deg] Subsatellite point
+    Anomaly,  // [deg] True anomaly
+    Battery,  // [%] State of charge
+    Time,     // [s] Simulation time
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Condition {
+    pub kind: ConditionKind,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub station: Option<String>, // Any station if not set
+}
+
+impl Condition {
+    #[allow(unused)]
+    pub fn new(kind: ConditionKind, min: Option<f64>, max: Option<f64>) -> Self {
+        Condition {
+            kind,
+            min,
+            max,
+            station: None,
+        }
+    }
+
+    pub fn within(&self, value: f64) -> bool {
+        // Band between min and max, wrapping around for angles when min > max
+        match (self.min, self.max) {
+            (Some(min), Some(max)) if min > max => value >= min || value <= max,
+            (min, max) => {
+                value >= min.unwrap_or(f64::NEG_INFINITY) && value <= max.unwrap_or(f64::INFINITY)
+            }
+        }
+    }
+
+    pub fn evaluate(&self, cubesat: &cubesat::CubeSat) -> bool {
+        // Guards
+        let time = cubesat.time.as_ref().expect("No time is set!");
+        let julian_date = time.julian_date(time.now);
+
+        match self.kind {
+            ConditionKind::Eclipse => cubesat.in_eclipse(),
+            ConditionKind::Sunlit => !cubesat.in_eclipse(),
+            ConditionKind::Contact => {
+                let pos = &cubesat.pos.expect("No position vector is set!");
+                let stations = cubesat
+                    .ground_stations
+                    .as_ref()
+                    .expect("No ground stations are set!");
+                stations
+                    .iter()
+                    .filter(|s| self.station.as_ref().is_none_or(|name| *name == s.name))
+                    .any(|s| s.in_contact(pos, julian_date))
+            }
+            ConditionKind::Latitude => {
+                let pos = &cubesat.pos.expect("No position vector is set!");
+                self.within(orbit::subsatellite_point(pos, julian_date).0)
+            }
+            ConditionKind::Anomaly => {
+                let pos = &cubesat.pos.expect("No position vector is set!");
+                let vel = &cubesat.vel.expect("No velocity vector is set!");
+                self.within(orbit::true_anomaly(pos, vel))
+            }
+            ConditionKind::Battery => {
+                self.within(cubesat.quantity("battery").expect("No EPS is set!"))
+            }
+            ConditionKind::Time => self.within(time.now),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Trigger {
+    #[serde(default = "Trigger::default_logic")]
+    pub logic: Logic,
+    pub conditions: Vec<Condition>,
+    pub duration: Option<f64>, // [s] From the rising edge, otherwise while the conditions hold
+    #[serde(skip)]
+    pub previous: bool,
+    #[serde(skip)]
+    pub start: Option<f64>,
+}
+
+impl Trigger {
+    #[allow(unused)]
+    pub fn new(logic: Logic, conditions: Vec<Condition>, duration: Option<f64>) -> Self {
+        Trigger {
+            logic,
+            conditions,
+            duration,
+            previous: false,
+            start: None,
+        }
+    }
+
+    pub fn evaluate(&self, cubesat: &cubesat::CubeSat) -> bool {
+        match self.logic {
+            Logic::And => self.conditions.iter().all(|c| c.evaluate(cubesat)),
+            Logic::Or => self.conditions.iter().any(|c| c.evaluate(cubesat)),
+        }
+    }
+
+    pub fn update(&mut self, now: f64, state: bool) -> bool {
+        // Level trigger, or a pulse of fixed duration on the rising edge
+        let rising = state && !self.previous;
+        self.previous = state;
+        let duration = match self.duration {
+            Some(d) => d,
+            None => return state,
+        };
+        if rising {
+            self.start = Some(now);
+        }
+        match self.start {
+            Some(start) if now - start < duration => true,
+            _ => {
+                self.start = None;
+                false
+            }
+        }
+    }
+
+    // Default values for deserialization
+    fn default_logic() -> Logic {
+        Logic::And
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandKind {
+    On,   // Force active
+    Off,  // Force inactive
+    Auto, // Back to periodic activation and triggers
+    Mode, // Argument: mode name
+}
+
+impl CommandKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CommandKind::On => "on",
+            CommandKind::Off => "off",
+            CommandKind::Auto => "auto",
+            CommandKind::Mode => "mode",
+        }
+    }
+
+    pub fn parse(command: &str) -> Option<Self> {
+        match command.trim().to_lowercase().as_str() {
+            "on" => Some(CommandKind::On),
+            "off" => Some(CommandKind::Off),
+            "auto" => Some(CommandKind::Auto),
+            "mode" => Some(CommandKind::Mode),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Command {
+    pub time: f64, // [s]
+    pub component: String,
+    pub command: CommandKind,
+    #[serde(default = "Command::default_arguments")]
+    pub arguments: Vec<String>,
+}
+
+impl Command {
+    #[allow(unused)]
+    pub fn new(time: f64, component: &str, command: CommandKind, arguments: Vec<&str>) -> Self {
+        Command {
+            time,
+            component: component.to_string(),
+            command,
+            arguments: arguments.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let mut text = self.command.name().to_string();
+        for argument in &self.arguments {
+            text = format!("{text} {argument}");
+        }
+        text
+    }
+
+    // Default values for deserialization
+    fn default_arguments() -> Vec<String> {
+        Vec::new()
+    }
+}
+
+#[derive(Deserialize)]
+struct CommandFile {
+    commands: Vec<Command>,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Timeline {
+    pub path: Option<String>, // TOML with [[commands]], or CSV: time, component, command, arguments
+    #[serde(default = "Timeline::default_commands")]
+    pub commands: Vec<Command>,
+    #[serde(skip)]
+    pub next: usize,
+    #[serde(skip)]
+    pub loaded: bool,
+}
+
+impl Timeline {
+    #[allow(unused)]
+    pub fn new(commands: Vec<Command>) -> Self {
+        Timeline {
+            path: None,
+            commands,
+            next: 0,
+            loaded: false,
+        }
+    }
+
+    pub fn from_csv(path: &str, text: &str) -> Result<Vec<Command>, CubeSatError> {
+        // Lines: time, component, command, arguments... Header, comments and blank lines are skipped
+        let mut commands = Vec::new();
+        let mut offset = 0;
+        for line in text.split_inclusive('\n') {
+            let start = offset;
+            offset += line.len();
+            let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+            if line.trim().is_empty()
+                || line.trim_start().starts_with('#')
+                || fields[0].eq_ignore_ascii_case("time")
+            {
+                continue;
+            }
+            let time = fields[0].parse::<f64>().map_err(|_| {
+                let column = line.find(fields[0]).unwrap_or(0);
+                let message = format!("invalid time '{}'", fields[0]);
+                CubeSatError::parse(path, text, start + column, &message)
+            })?;
+            let command = fields.get(2).ok_or_else(|| {
+                let message = "missing component or command";
+                CubeSatError::parse(path, text, start + line.trim_end().len(), message)
+            })?;
+            let kind = CommandKind::parse(command).ok_or_else(|| {
+                let column = line.find(command).unwrap_or(0);
+                let message = format!("unknown command '{command}'");
+                CubeSatError::parse(path, text, start + column, &message)
+            })?;
+            commands.push(Command::new(time, fields[1], kind, fields[3..].to_vec()));
+        }
+        Ok(commands)
+    }
+
+    pub fn load(&mut self) -> Result<(), CubeSatError> {
+        // Commands from the file are merged with the inline ones, in time order
+        if self.loaded {
+            return Ok(());
+        }
+        if let Some(path) = &self.path {
+            let file = std::fs::read_to_string(path).map_err(|e| CubeSatError::io(path, &e))?;
+            let commands = match path.ends_with(".toml") {
+                true => {
+                    toml::from_str::<CommandFile>(&file)
+                        .map_err(|e| CubeSatError::toml(path, &file, &e))?
+                        .commands
+                }
+                false => Timeline::from_csv(path, &file)?,
+            };
+            self.commands.extend(commands);
+        }
+        self.commands.sort_by(|a, b| a.time.total_cmp(&b.time));
+        self.loaded = true;
+        Ok(())
+    }
+
+    pub fn due(&mut self, now: f64, step: f64) -> Vec<Command> {
+        // Commands up to the current time step
+        let start = self.next;
+        while self.next < self.commands.len() && self.commands[self.next].time <= now + 0.5 * step {
+            self.next += 1;
+        }
+        self.commands[start..self.next].to_vec()
+    }
+
+    // Default values for deserialization
+    fn default_commands() -> Vec<Command> {
+        Vec::new()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct SafeModePolicy {
+    pub enter: f64, // [%] Battery at or below
+    pub exit: f64,  // [%] Battery at or above
+    #[serde(default = "SafeModePolicy::default_zero")]
+    pub min_dwell: f64, // [s] In safe mode before recovery
+    pub shed: Option<Vec<String>>, // Components switched off, all if not set
+    #[serde(default = "SafeModePolicy::default_names")]
+    pub force_on: Vec<String>, // Components switched on
+    #[serde(skip)]
+    pub entered: Option<f64>,
+}
+
+impl SafeModePolicy {
+    #[allow(unused)]
+    pub fn new(enter: f64, exit: f64, min_dwell: f64) -> Self {
+        SafeModePolicy {
+            enter,
+            exit,
+            min_dwell,
+            shed: None,
+            force_on: Vec::new(),
+            entered: None,
+        }
+    }
+
+    pub fn update(&mut self, safe_mode: bool, battery: f64, now: f64) -> bool {
+        // Hysteresis between the thresholds, recovery only after the dwell
+        match safe_mode {
+            false if battery <= self.enter => {
+                self.entered = Some(now);
+                true
+            }
+            true if battery >= self.exit
+                && now - self.entered.unwrap_or(f64::NEG_INFINITY) >= self.min_dwell =>
+            {
+                self.entered = None;
+                false
+            }
+            _ => safe_mode,
+        }
+    }
+
+    pub fn sheds(&self, component: &str) -> bool {
+        match &self.shed {
+            Some(names) => names.iter().any(|n| n == component),
+            None => !self.forces(component),
+        }
+    }
+
+    pub fn forces(&self, component: &str) -> bool {
+        self.force_on.iter().any(|n| n == component)
+    }
+
+    // Default values for deserialization
+    fn default_zero() -> f64 {
+        0.0
+    }
+    fn default_names() -> Vec<String> {
+        Vec::new()
+    }
+}
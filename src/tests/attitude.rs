@@ -0,0 +1,161 @@
+#[allow(unused_imports)]
+use crate::attitude::*;
+
+#[test]
+fn magnetic_field_dipole() {
+    // Equator: field points north with the surface strength
+    let equator = magnetic_field(&vector::Vector3::new(orbit::RADIUS_EARTH, 0.0, 0.0));
+    assert!(equator.x.abs() < 1e-15);
+    assert!(equator.y.abs() < 1e-15);
+    assert!((equator.z - MAGNETIC_FIELD_EARTH).abs() < 1e-15);
+
+    // Pole: twice as strong, pointing down
+    let pole = magnetic_field(&vector::Vector3::new(0.0, 0.0, orbit::RADIUS_EARTH));
+    assert!((pole.z + 2.0 * MAGNETIC_FIELD_EARTH).abs() < 1e-15);
+
+    // Falls off with r^3
+    let far = magnetic_field(&vector::Vector3::new(2.0 * orbit::RADIUS_EARTH, 0.0, 0.0));
+    assert!((far.z - MAGNETIC_FIELD_EARTH / 8.0).abs() < 1e-15);
+}
+
+#[test]
+fn rate_damping_with_reaction_wheels() {
+    let mut cubesat = cubesat::CubeSat::new()
+        .with_time(0.0, 100.0, 1.0)
+        .with_position(orbit::RADIUS_EARTH + 500_000.0, 0.0, 0.0)
+        .with_rotation(0.0, 0.0, 0.0)
+        .with_rotation_velocity(0.0, 0.0, 1.0)
+        .with_inertia(0.01, 0.01, 0.01)
+        .with_control_gain(0.1)
+        .with_reaction_wheel("RW X", (1.0, 0.0, 0.0), 1e-3, 1e-2, 1e-5, -0.1)
+        .with_reaction_wheel("RW Y", (0.0, 1.0, 0.0), 1e-3, 1e-2, 1e-5, -0.1)
+        .with_reaction_wheel("RW Z", (0.0, 0.0, 1.0), 1e-3, 1e-2, 1e-5, -0.1);
+
+    for _ in 0..100 {
+        cubesat.update_attitude();
+    }
+
+    // Body rate is damped and the momentum is stored in the z wheel
+    let rot_vel = cubesat.rot_vel.unwrap();
+    assert!(rot_vel.z.abs() < 0.01);
+    let wheels = cubesat.reaction_wheels.as_ref().unwrap();
+    let body_momentum = 0.01 * 1.0 * std::f64::consts::PI / 180.0;
+    assert!((wheels[2].momentum() - body_momentum).abs() < 1e-5);
+    assert_eq!(wheels[0].momentum(), 0.0);
+    assert!(!wheels[2].saturated);
+}
+
+#[test]
+fn reaction_wheel_saturation() {
+    let mut cubesat = cubesat::CubeSat::new()
+        .with_time(0.0, 100.0, 1.0)
+        .with_position(orbit::RADIUS_EARTH + 500_000.0, 0.0, 0.0)
+        .with_rotation(0.0, 0.0, 0.0)
+        .with_rotation_velocity(0.0, 0.0, 10.0)
+        .with_inertia(0.01, 0.01, 0.01)
+        .with_control_gain(0.1)
+        .with_reaction_wheel("RW Z", (0.0, 0.0, 1.0), 1e-3, 1e-4, 1e-5, -0.1);
+
+    for _ in 0..10 {
+        cubesat.update_attitude();
+    }
+
+    // Wheel saturates and cannot absorb the remaining rate
+    assert!(cubesat.reaction_wheels.as_ref().unwrap()[0].saturated);
+    assert!(cubesat.rot_vel.unwrap().z > 1.0);
+    assert_eq!(cubesat.history.events().len(), 1);
+}
+
+#[test]
+fn momentum_dumping() {
+    let mut cubesat = cubesat::CubeSat::new()
+        .with_time(0.0, 100.0, 1.0)
+        .with_position(orbit::RADIUS_EARTH + 500_000.0, 0.0, 0.0)
+        .with_rotation(0.0, 0.0, 0.0)
+        .with_rotation_velocity(0.0, 0.0, 0.0)
+        .with_inertia(0.01, 0.01, 0.01)
+        .with_control_gain(0.1)
+        .with_reaction_wheel("RW X", (1.0, 0.0, 0.0), 1e-3, 1e-2, 1e-5, -0.1)
+        .with_magnetorquer(0.2, -0.5, 0.8, 0.2);
+    cubesat.reaction_wheels.as_mut().unwrap()[0].speed = 900.0;
+
+    // Dumping starts above the threshold
+    let torque = update_momentum_dumping(&mut cubesat);
+    assert!(cubesat.magnetorquer.as_ref().unwrap().active);
+    assert!(torque.x < 0.0);
+    assert_eq!(cubesat.get_power_consumption(), -0.6);
+
+    // And stops below the lower threshold
+    cubesat.reaction_wheels.as_mut().unwrap()[0].speed = 100.0;
+    let torque = update_momentum_dumping(&mut cubesat);
+    assert!(!cubesat.magnetorquer.as_ref().unwrap().active);
+    assert_eq!(torque, vector::Vector3::origin());
+    assert_eq!(cubesat.history.events().len(), 2);
+}
+
+#[test]
+fn gravity_gradient() {
+    let r = orbit::RADIUS_EARTH + 500_000.0;
+
+    // Symmetric body has no gravity gradient torque
+    let symmetric = vector::Vector3::new(0.01, 0.01, 0.01);
+    let pos = vector::Vector3::new(r, r, 0.0).scale(0.5_f64.sqrt());
+    assert!(gravity_gradient_torque(&pos, &symmetric).abs() < 1e-20);
+
+    // Nadir along a principal axis has no torque either
+    let asymmetric = vector::Vector3::new(0.01, 0.02, 0.03);
+    let nadir = vector::Vector3::new(r, 0.0, 0.0);
+    assert_eq!(gravity_gradient_torque(&nadir, &asymmetric).abs(), 0.0);
+
+    // Tilted: torque about z, expected 3 * mu / r^3 * (Iy - Ix) * sin * cos
+    let torque = gravity_gradient_torque(&pos, &asymmetric);
+    let expected = 3.0 * orbit::CONST_MU / r.powi(3) * 0.01 * 0.5;
+    assert!((torque.z - expected).abs() < 1e-15);
+}
+
+#[test]
+fn geometry_forces() {
+    let geometry = Geometry::new(vec![((1.0, 0.0, 0.0), 0.01), ((-1.0, 0.0, 0.0), 0.01)]);
+
+    // Drag only on the face towards the flow, opposite to the velocity
+    let velocity = vector::Vector3::new(7500.0, 0.0, 0.0);
+    let force = geometry.aerodynamic_force(&velocity, 1e-12);
+    let expected = 0.5 * 1e-12 * 7500.0_f64.powi(2) * 2.2 * 0.01;
+    assert!((force.x + expected).abs() < 1e-15);
+    assert_eq!(force.y, 0.0);
+
+    // Sunlight travelling along -x pushes the +x face along -x
+    let sun = vector::Vector3::new(-1.0, 0.0, 0.0);
+    let force = geometry.solar_radiation_force(&sun, orbit::SOLAR_PRESSURE);
+    let expected = orbit::SOLAR_PRESSURE * 0.01 * (1.0 + 0.3);
+    assert!((force.x + expected).abs() < 1e-15);
+}
+
+#[test]
+fn disturbance_torques_eclipse() {
+    let r = orbit::RADIUS_EARTH + 500_000.0;
+    let mut cubesat = cubesat::CubeSat::new()
+        .with_position(r, 0.0, 0.0)
+        .with_velocity(0.0, 7600.0, 0.0)
+        .with_rotation(0.0, 0.0, 0.0)
+        .with_sun(-1.0, 0.0, 0.0)
+        .with_geometry(vec![
+            ((1.0, 0.0, 0.0), 0.01),
+            ((0.0, 1.0, 0.0), 0.03),
+            ((0.0, 0.0, 1.0), 0.01),
+        ]);
+    cubesat.geometry.as_mut().unwrap().center_of_pressure = vector::Vector3::new(0.0, 0.0, 0.01);
+    cubesat.geometry.as_mut().unwrap().residual_dipole = vector::Vector3::new(0.01, 0.0, 0.0);
+
+    // In the sun
+    let torques = disturbance_torques(&cubesat);
+    assert_eq!(torques.gravity_gradient, vector::Vector3::origin());
+    assert!(torques.aerodynamic.abs() > 0.0);
+    assert!(torques.solar_radiation.abs() > 0.0);
+    assert!(torques.magnetic.abs() > 0.0);
+
+    // In eclipse
+    cubesat.sun = Some(vector::Vector3::new(1.0, 0.0, 0.0));
+    let torques = disturbance_torques(&cubesat);
+    assert_eq!(torques.solar_radiation, vector::Vector3::origin());
+}
@@ -28,48 +28,49 @@ fn power_generation() {
     let sun_pos_z = vector::Vector3::new(0.0, 0.0, 1.0);
     let sun_neg_z = vector::Vector3::new(0.0, 0.0, -1.0);
     let rotation = vector::Vector3::new(0.0, 0.0, 0.0);
+    let flux = crate::orbit::SOLAR_CONSTANT;
 
-    assert_eq!(panel_pos_x.power_generation(&rotation, &sun_pos_x), 0.0);
-    assert_eq!(panel_pos_x.power_generation(&rotation, &sun_neg_x), 1.0);
-    assert_eq!(panel_pos_x.power_generation(&rotation, &sun_pos_y), 0.0);
-    assert_eq!(panel_pos_x.power_generation(&rotation, &sun_neg_y), 0.0);
-    assert_eq!(panel_pos_x.power_generation(&rotation, &sun_pos_z), 0.0);
-    assert_eq!(panel_pos_x.power_generation(&rotation, &sun_neg_z), 0.0);
-
-    assert_eq!(panel_neg_x.power_generation(&rotation, &sun_pos_x), 1.0);
-    assert_eq!(panel_neg_x.power_generation(&rotation, &sun_neg_x), 0.0);
-    assert_eq!(panel_neg_x.power_generation(&rotation, &sun_pos_y), 0.0);
-    assert_eq!(panel_neg_x.power_generation(&rotation, &sun_neg_y), 0.0);
-    assert_eq!(panel_neg_x.power_generation(&rotation, &sun_pos_z), 0.0);
-    assert_eq!(panel_neg_x.power_generation(&rotation, &sun_neg_z), 0.0);
-
-    assert_eq!(panel_pos_y.power_generation(&rotation, &sun_pos_x), 0.0);
-    assert_eq!(panel_pos_y.power_generation(&rotation, &sun_neg_x), 0.0);
-    assert_eq!(panel_pos_y.power_generation(&rotation, &sun_pos_y), 0.0);
-    assert_eq!(panel_pos_y.power_generation(&rotation, &sun_neg_y), 1.0);
-    assert_eq!(panel_pos_y.power_generation(&rotation, &sun_pos_z), 0.0);
-    assert_eq!(panel_pos_y.power_generation(&rotation, &sun_neg_z), 0.0);
-
-    assert_eq!(panel_neg_y.power_generation(&rotation, &sun_pos_x), 0.0);
-    assert_eq!(panel_neg_y.power_generation(&rotation, &sun_neg_x), 0.0);
-    assert_eq!(panel_neg_y.power_generation(&rotation, &sun_pos_y), 1.0);
-    assert_eq!(panel_neg_y.power_generation(&rotation, &sun_neg_y), 0.0);
-    assert_eq!(panel_neg_y.power_generation(&rotation, &sun_pos_z), 0.0);
-    assert_eq!(panel_neg_y.power_generation(&rotation, &sun_neg_z), 0.0);
-
-    assert_eq!(panel_pos_z.power_generation(&rotation, &sun_pos_x), 0.0);
-    assert_eq!(panel_pos_z.power_generation(&rotation, &sun_neg_x), 0.0);
-    assert_eq!(panel_pos_z.power_generation(&rotation, &sun_pos_y), 0.0);
-    assert_eq!(panel_pos_z.power_generation(&rotation, &sun_neg_y), 0.0);
-    assert_eq!(panel_pos_z.power_generation(&rotation, &sun_pos_z), 0.0);
-    assert_eq!(panel_pos_z.power_generation(&rotation, &sun_neg_z), 1.0);
-
-    assert_eq!(panel_neg_z.power_generation(&rotation, &sun_pos_x), 0.0);
-    assert_eq!(panel_neg_z.power_generation(&rotation, &sun_neg_x), 0.0);
-    assert_eq!(panel_neg_z.power_generation(&rotation, &sun_pos_y), 0.0);
-    assert_eq!(panel_neg_z.power_generation(&rotation, &sun_neg_y), 0.0);
-    assert_eq!(panel_neg_z.power_generation(&rotation, &sun_pos_z), 1.0);
-    assert_eq!(panel_neg_z.power_generation(&rotation, &sun_neg_z), 0.0);
+    assert_eq!(panel_pos_x.power(&rotation, &sun_pos_x, flux, 0.0), 0.0);
+    assert_eq!(panel_pos_x.power(&rotation, &sun_neg_x, flux, 0.0), 1.0);
+    assert_eq!(panel_pos_x.power(&rotation, &sun_pos_y, flux, 0.0), 0.0);
+    assert_eq!(panel_pos_x.power(&rotation, &sun_neg_y, flux, 0.0), 0.0);
+    assert_eq!(panel_pos_x.power(&rotation, &sun_pos_z, flux, 0.0), 0.0);
+    assert_eq!(panel_pos_x.power(&rotation, &sun_neg_z, flux, 0.0), 0.0);
+
+    assert_eq!(panel_neg_x.power(&rotation, &sun_pos_x, flux, 0.0), 1.0);
+    assert_eq!(panel_neg_x.power(&rotation, &sun_neg_x, flux, 0.0), 0.0);
+    assert_eq!(panel_neg_x.power(&rotation, &sun_pos_y, flux, 0.0), 0.0);
+    assert_eq!(panel_neg_x.power(&rotation, &sun_neg_y, flux, 0.0), 0.0);
+    assert_eq!(panel_neg_x.power(&rotation, &sun_pos_z, flux, 0.0), 0.0);
+    assert_eq!(panel_neg_x.power(&rotation, &sun_neg_z, flux, 0.0), 0.0);
+
+    assert_eq!(panel_pos_y.power(&rotation, &sun_pos_x, flux, 0.0), 0.0);
+    assert_eq!(panel_pos_y.power(&rotation, &sun_neg_x, flux, 0.0), 0.0);
+    assert_eq!(panel_pos_y.power(&rotation, &sun_pos_y, flux, 0.0), 0.0);
+    assert_eq!(panel_pos_y.power(&rotation, &sun_neg_y, flux, 0.0), 1.0);
+    assert_eq!(panel_pos_y.power(&rotation, &sun_pos_z, flux, 0.0), 0.0);
+    assert_eq!(panel_pos_y.power(&rotation, &sun_neg_z, flux, 0.0), 0.0);
+
+    assert_eq!(panel_neg_y.power(&rotation, &sun_pos_x, flux, 0.0), 0.0);
+    assert_eq!(panel_neg_y.power(&rotation, &sun_neg_x, flux, 0.0), 0.0);
+    assert_eq!(panel_neg_y.power(&rotation, &sun_pos_y, flux, 0.0), 1.0);
+    assert_eq!(panel_neg_y.power(&rotation, &sun_neg_y, flux, 0.0), 0.0);
+    assert_eq!(panel_neg_y.power(&rotation, &sun_pos_z, flux, 0.0), 0.0);
+    assert_eq!(panel_neg_y.power(&rotation, &sun_neg_z, flux, 0.0), 0.0);
+
+    assert_eq!(panel_pos_z.power(&rotation, &sun_pos_x, flux, 0.0), 0.0);
+    assert_eq!(panel_pos_z.power(&rotation, &sun_neg_x, flux, 0.0), 0.0);
+    assert_eq!(panel_pos_z.power(&rotation, &sun_pos_y, flux, 0.0), 0.0);
+    assert_eq!(panel_pos_z.power(&rotation, &sun_neg_y, flux, 0.0), 0.0);
+    assert_eq!(panel_pos_z.power(&rotation, &sun_pos_z, flux, 0.0), 0.0);
+    assert_eq!(panel_pos_z.power(&rotation, &sun_neg_z, flux, 0.0), 1.0);
+
+    assert_eq!(panel_neg_z.power(&rotation, &sun_pos_x, flux, 0.0), 0.0);
+    assert_eq!(panel_neg_z.power(&rotation, &sun_neg_x, flux, 0.0), 0.0);
+    assert_eq!(panel_neg_z.power(&rotation, &sun_pos_y, flux, 0.0), 0.0);
+    assert_eq!(panel_neg_z.power(&rotation, &sun_neg_y, flux, 0.0), 0.0);
+    assert_eq!(panel_neg_z.power(&rotation, &sun_pos_z, flux, 0.0), 1.0);
+    assert_eq!(panel_neg_z.power(&rotation, &sun_neg_z, flux, 0.0), 0.0);
 }
 
 #[test]
@@ -86,7 +87,7 @@ fn eps() {
     eps.update_capacity(-1.0, timestep);
     assert_eq!(
         eps.charge,
-        (-1.0 * timestep + (eps.max_charge * time::HOUR)) / time::HOUR
+        (-timestep + (eps.max_charge * time::HOUR)) / time::HOUR
     );
 }
 
@@ -94,9 +95,445 @@ fn eps() {
 fn component() {
     let component = Component::new("ADCS", -1.0, Some(-2.0), Some(40.0), Some(20.0));
     assert_eq!(component.name, "ADCS".to_string());
-    assert_eq!(component.active, false);
+    assert!(!component.active);
     assert_eq!(component.consumption_passive, -1.0);
     assert_eq!(component.consumption_active, Some(-2.0));
     assert_eq!(component.activation_interval, Some(40.0));
     assert_eq!(component.activation_duration, Some(20.0));
 }
+
+#[test]
+fn reaction_wheel() {
+    let mut wheel = ReactionWheel::new("RW", (1.0, 0.0, 0.0), 0.001, 0.01, 1e-4, -0.1);
+    assert_eq!(wheel.momentum(), 0.0);
+    assert_eq!(wheel.power_consumption(), -0.1);
+
+    // Torque is limited by max torque
+    let change = wheel.apply_torque(1.0, 1.0);
+    assert_eq!(change, 0.001);
+    assert!((wheel.momentum() - 0.001).abs() < 1e-12);
+    assert!(!wheel.saturated);
+
+    // Momentum is limited by max momentum
+    for _ in 0..20 {
+        wheel.apply_torque(0.001, 1.0);
+    }
+    assert!(wheel.saturated);
+    assert!((wheel.momentum() - 0.01).abs() < 1e-12);
+    assert_eq!(wheel.apply_torque(0.001, 1.0), 0.0);
+
+    // Unloading clears saturation
+    wheel.apply_torque(-0.001, 1.0);
+    assert!(!wheel.saturated);
+
+    // Speed-dependent consumption
+    wheel.consumption_speed = -0.001;
+    assert!(wheel.power_consumption() < -0.1);
+}
+
+#[test]
+fn reaction_wheel_friction() {
+    let mut wheel = ReactionWheel::new("RW", (0.0, 0.0, 1.0), 0.001, 0.01, 1e-4, -0.1);
+    wheel.friction = 1e-6;
+    wheel.speed = 50.0;
+    let change = wheel.apply_torque(0.0, 1.0);
+    assert!(change < 0.0);
+    assert!(wheel.speed < 50.0);
+}
+
+#[test]
+fn magnetorquer() {
+    let mut magnetorquer = Magnetorquer::new(0.2, -0.5, 0.8, 0.2);
+    assert!(!magnetorquer.active);
+    assert_eq!(magnetorquer.power_consumption(), 0.0);
+    magnetorquer.active = true;
+    assert_eq!(magnetorquer.power_consumption(), -0.5);
+}
+
+#[test]
+fn propulsion() {
+    let mut propulsion = Propulsion::new("Propulsion", 0.2, 0.3, 0.5, 0.1, 220.0);
+    propulsion.warm_up = 60.0;
+    propulsion.consumption_heater = -2.0;
+    propulsion.consumption_valve = -1.0;
+    assert_eq!(propulsion.wet_mass(), 1.0);
+
+    // Rocket equation
+    let expected = 220.0 * maneuver::G0 * (4.0_f64 / 3.5).ln();
+    assert!((propulsion.delta_v(4.0) - expected).abs() < 1e-9);
+
+    // Heater starts a warm-up ahead of the burn
+    assert!(!propulsion.update_heater(0.0, 10.0, Some(100.0)));
+    assert_eq!(propulsion.power_consumption(), 0.0);
+    assert!(!propulsion.update_heater(40.0, 10.0, Some(100.0)));
+    assert_eq!(propulsion.power_consumption(), -2.0);
+    assert!(propulsion.update_heater(100.0, 10.0, Some(100.0)));
+    propulsion.valve_open = true;
+    assert_eq!(propulsion.power_consumption(), -3.0);
+
+    // Off once no burn is pending
+    propulsion.valve_open = false;
+    assert!(!propulsion.update_heater(110.0, 10.0, None));
+    assert_eq!(propulsion.power_consumption(), 0.0);
+}
+
+#[test]
+fn battery_efficiency_and_rate() {
+    let mut eps = Eps::new(0.0, 10.0);
+    eps.charge = 5.0;
+    let mut battery = Battery::new(0.9, 0.8);
+
+    // 36 W for 100 s in, 90% stored
+    battery.update(&mut eps, 36.0, 100.0);
+    assert!((eps.charge - 5.9).abs() < 1e-12);
+    assert!((battery.power - 36.0).abs() < 1e-9);
+
+    // 36 W for 100 s out, 1.25 Wh from the cells
+    battery.update(&mut eps, -36.0, 100.0);
+    assert!((eps.charge - 4.65).abs() < 1e-12);
+
+    // 0.5 C limits the charge to 5 W
+    battery.max_charge_rate = Some(0.5);
+    battery.charge_efficiency = 1.0;
+    battery.update(&mut eps, 36.0, 360.0);
+    assert!((eps.charge - 5.15).abs() < 1e-12);
+    assert!((battery.power - 5.0).abs() < 1e-9);
+}
+
+#[test]
+fn battery_voltage_curve() {
+    let mut battery = Battery::new(1.0, 1.0);
+    assert_eq!(battery.voltage(50.0), None);
+    battery.voltage_curve = vec![(0.0, 3.0), (20.0, 3.6), (100.0, 4.2)];
+    assert_eq!(battery.voltage(-5.0), Some(3.0));
+    assert!((battery.voltage(10.0).unwrap() - 3.3).abs() < 1e-12);
+    assert!((battery.voltage(60.0).unwrap() - 3.9).abs() < 1e-12);
+    assert_eq!(battery.voltage(100.0), Some(4.2));
+}
+
+#[test]
+fn battery_temperature_and_fade() {
+    // Cold battery only charges to 80%
+    let mut eps = Eps::new(0.0, 10.0);
+    eps.charge = 7.0;
+    let mut battery = Battery::new(1.0, 1.0);
+    battery.temperature = 0.0;
+    battery.temperature_coefficient = 0.01;
+    battery.update(&mut eps, 100.0, time::HOUR);
+    assert!((eps.charge - 8.0).abs() < 1e-12);
+
+    // A full discharge is half a cycle, fading the capacity by 0.5%
+    let mut eps = Eps::new(0.0, 10.0);
+    let mut battery = Battery::new(1.0, 1.0);
+    battery.fade_per_cycle = 0.01;
+    battery.update(&mut eps, -10.0, time::HOUR);
+    assert_eq!(eps.charge, 0.0);
+    assert!((battery.cycles() - 0.5).abs() < 1e-12);
+    assert!((eps.max_charge - 9.95).abs() < 1e-12);
+    battery.update(&mut eps, 10.0, time::HOUR);
+    assert!(eps.max_charge < 9.95);
+    assert_eq!(eps.charge, eps.max_charge);
+}
+
+#[test]
+fn battery_depth_of_discharge() {
+    let mut battery = Battery::new(1.0, 1.0);
+    assert_eq!(battery.depth_of_discharge_statistics(), None);
+    assert_eq!(
+        battery.update_depth_of_discharge(0.0, 10.0, 30.0, 100.0),
+        None
+    );
+    assert_eq!(
+        battery.update_depth_of_discharge(10.0, 10.0, 30.0, 70.0),
+        None
+    );
+    assert_eq!(
+        battery.update_depth_of_discharge(20.0, 10.0, 30.0, 90.0),
+        None
+    );
+    assert_eq!(
+        battery.update_depth_of_discharge(30.0, 10.0, 30.0, 95.0),
+        Some(30.0)
+    );
+    for (t, soc) in [(40.0, 85.0), (50.0, 95.0)] {
+        battery.update_depth_of_discharge(t, 10.0, 30.0, soc);
+    }
+    assert_eq!(
+        battery.update_depth_of_discharge(60.0, 10.0, 30.0, 95.0),
+        Some(10.0)
+    );
+    assert_eq!(battery.depth_of_discharge_statistics(), Some((20.0, 30.0)));
+}
+
+#[test]
+fn power_bus() {
+    let mut bus = PowerBus::new(Regulator::Mppt, 0.95);
+    assert!((bus.array_power(10.0) - 9.5).abs() < 1e-12);
+
+    // DET array works off its maximum power point
+    bus.regulator = Regulator::Det;
+    bus.mismatch = 0.1;
+    assert!((bus.array_power(10.0) - 8.55).abs() < 1e-12);
+
+    // Loads on a rail are drawn through its converter
+    bus.rails.push(Rail::new("3V3", 0.8, vec!["OBC"]));
+    assert_eq!(bus.rail("OBC").unwrap().name, "3V3".to_string());
+    assert_eq!(bus.rail("Radio"), None);
+    assert_eq!(bus.load_power("OBC", -1.0), -1.25);
+    assert_eq!(bus.load_power("Radio", -1.0), -1.0);
+}
+
+#[test]
+fn panel_cells() {
+    let mut panel = SolarPanel::with_cells(1.0, 0.0, 0.0, 0.03, 0.3);
+    panel.packing_factor = 0.9;
+    let peak = 1_361.0 * 0.03 * 0.3 * 0.9;
+    assert!((panel.peak_power(1_361.0, 0.0) - peak).abs() < 1e-12);
+
+    // Hot cells lose efficiency
+    panel.temperature_coefficient = -0.0025;
+    panel.temperature = 68.0;
+    assert!((panel.peak_power(1_361.0, 0.0) - 0.9 * peak).abs() < 1e-12);
+
+    // Degradation compounds every year
+    panel.temperature = 28.0;
+    panel.degradation = 0.1;
+    assert!((panel.peak_power(1_361.0, 2.0) - 0.81 * peak).abs() < 1e-12);
+
+    // Flux and incidence
+    let rotation = vector::Vector3::new(0.0, 0.0, 0.0);
+    let sun = vector::Vector3::new(-1.0, 0.0, 0.0);
+    assert!((panel.power(&rotation, &sun, 680.5, 0.0) - peak / 2.0).abs() < 1e-12);
+
+    // Without a cell model the peak power is fixed
+    let panel = SolarPanel::new(2.0, 1.0, 0.0, 0.0);
+    assert_eq!(panel.peak_power(680.5, 10.0), 2.0);
+}
+
+#[test]
+fn panel_deployment() {
+    // Stowed along +x, deploys 90 degrees about z over 20 s
+    let mut panel = SolarPanel::new(1.0, 1.0, 0.0, 0.0);
+    panel.deployment = Some(Deployment::new(10.0, (0.0, 0.0, 1.0), 90.0, 20.0));
+    let deployment = panel.deployment.as_mut().unwrap();
+    assert_eq!(deployment.update(0.0, 10.0, (0.0, 0.0)), None);
+    assert_eq!(
+        deployment.update(10.0, 10.0, (0.0, 0.0)),
+        Some("deployment started")
+    );
+    assert_eq!(deployment.update(20.0, 10.0, (0.0, 0.0)), None);
+    assert_eq!(deployment.progress, 0.5);
+    assert!(!panel.deployed());
+
+    // No power until fully deployed, even facing the sun
+    let rotation = vector::Vector3::new(0.0, 0.0, 0.0);
+    let sun = vector::Vector3::new(-1.0, -1.0, 0.0).unit();
+    assert_eq!(panel.power(&rotation, &sun, 1361.0, 0.0), 0.0);
+    let halfway = 45.0_f64.to_radians();
+    assert!(
+        panel
+            .normal()
+            .sub(&vector::Vector3::new(halfway.cos(), halfway.sin(), 0.0))
+            .abs()
+            < 1e-12
+    );
+
+    let deployment = panel.deployment.as_mut().unwrap();
+    assert_eq!(deployment.update(30.0, 10.0, (0.0, 0.0)), Some("deployed"));
+    assert_eq!(deployment.update(40.0, 10.0, (0.0, 0.0)), None);
+    assert!(panel.deployed());
+    assert!(panel.power(&rotation, &sun, 1361.0, 0.0) > 0.0);
+    assert!(
+        panel
+            .normal()
+            .sub(&vector::Vector3::new(0.0, 1.0, 0.0))
+            .abs()
+            < 1e-12
+    );
+}
+
+#[test]
+fn panel_deployment_at_event() {
+    let mut deployment = Deployment::new(0.0, (0.0, 0.0, 1.0), 90.0, 0.0);
+    deployment.event = Some(maneuver::OrbitEvent::AscendingNode);
+    assert_eq!(deployment.update(0.0, 1.0, (0.0, -1.0)), None);
+    assert_eq!(deployment.update(1.0, 1.0, (0.0, -0.5)), None);
+    assert_eq!(deployment.update(2.0, 1.0, (0.0, 0.5)), Some("deployed"));
+    assert_eq!(deployment.progress, 1.0);
+}
+
+#[test]
+fn panel_tracking() {
+    // Normal along +x, drive about +z at 1 deg/s, sun towards +y
+    let mut panel = SolarPanel::new(1.0, 1.0, 0.0, 0.0);
+    panel.tracking = Some(Tracking::new((0.0, 0.0, 1.0), 1.0, -0.5));
+    let to_sun = vector::Vector3::new(0.0, 1.0, 0.0);
+    let normal = panel.deployed_normal();
+
+    let tracking = panel.tracking.as_mut().unwrap();
+    tracking.update(&normal, &to_sun, 30.0);
+    assert!((tracking.angle - 30.0).abs() < 1e-9);
+    assert_eq!(panel.power_consumption(), -0.5);
+
+    let tracking = panel.tracking.as_mut().unwrap();
+    for _ in 0..3 {
+        tracking.update(&normal, &to_sun, 30.0);
+    }
+    assert!((tracking.angle - 90.0).abs() < 1e-9);
+    assert!(!tracking.moving);
+    assert_eq!(panel.power_consumption(), 0.0);
+    assert!(panel.normal().sub(&to_sun).abs() < 1e-12);
+
+    // Shortest way round, only the component perpendicular to the drive axis counts
+    let tracking = panel.tracking.as_mut().unwrap();
+    tracking.update(&normal, &vector::Vector3::new(0.0, 1.0, -5.0), 1.0);
+    assert!(!tracking.moving);
+    tracking.update(&normal, &vector::Vector3::new(-1.0, 1.0, 0.0), 10.0);
+    assert!((tracking.angle - 100.0).abs() < 1e-9);
+    tracking.update(&normal, &vector::Vector3::new(1.0, -0.1, 0.0), 10.0);
+    assert!((tracking.angle - 90.0).abs() < 1e-9);
+}
+
+#[test]
+fn rectangle() {
+    // +x face of a 10 cm cube
+    let face = Rectangle::new((0.05, -0.05, -0.05), (0.0, 0.1, 0.0), (0.0, 0.0, 0.1));
+    assert_eq!(face.center(), vector::Vector3::new(0.05, 0.0, 0.0));
+
+    let origin = vector::Vector3::new(0.0, 0.0, 0.0);
+    assert!(face.intersects(&origin, &vector::Vector3::new(1.0, 0.0, 0.0)));
+    assert!(!face.intersects(&origin, &vector::Vector3::new(-1.0, 0.0, 0.0)));
+    assert!(!face.intersects(&origin, &vector::Vector3::new(1.0, 2.0, 0.0)));
+    assert!(!face.intersects(&origin, &vector::Vector3::new(0.0, 1.0, 0.0)));
+
+    // Quarter turn about z through the origin moves it to the +y face
+    let turned = face.rotate(
+        &origin,
+        &vector::Vector3::new(0.0, 0.0, 1.0),
+        std::f64::consts::FRAC_PI_2,
+    );
+    assert!(
+        turned
+            .center()
+            .sub(&vector::Vector3::new(0.0, 0.05, 0.0))
+            .abs()
+            < 1e-12
+    );
+}
+
+#[test]
+fn shadow_fraction() {
+    let face = Rectangle::new((0.05, -0.05, -0.05), (0.0, 0.1, 0.0), (0.0, 0.0, 0.1));
+    let plate = Rectangle::new((0.15, -0.05, -0.05), (0.0, 0.05, 0.0), (0.0, 0.0, 0.1));
+    let to_sun = vector::Vector3::new(1.0, 0.0, 0.0);
+    assert_eq!(face.shadow_fraction(&[], &to_sun, 8), 0.0);
+    assert_eq!(face.shadow_fraction(&[plate], &to_sun, 8), 0.5);
+    // Sun from behind the plate's side
+    let to_sun = vector::Vector3::new(0.0, 1.0, 0.0);
+    assert_eq!(face.shadow_fraction(&[plate], &to_sun, 8), 0.0);
+}
+
+#[test]
+fn heater_hysteresis() {
+    let mut heater = Heater::new("Heater", "Battery", -1.5, 0.0, 5.0);
+    heater.update(10.0, 10.0);
+    assert_eq!(heater.power_consumption(), 0.0);
+
+    // On below the lower setpoint, stays on inside the band
+    heater.update(-1.0, 10.0);
+    assert_eq!(heater.power_consumption(), -1.5);
+    heater.update(3.0, 10.0);
+    assert!(heater.on);
+
+    // Off above the upper setpoint, stays off inside the band
+    heater.update(5.0, 10.0);
+    assert!(!heater.on);
+    heater.update(3.0, 10.0);
+    assert!(!heater.on);
+    assert!((heater.duty_cycle() - 0.4).abs() < 1e-12);
+}
+
+#[test]
+fn component_modes() {
+    let mut standby = OperatingMode::new(Mode::Standby, -0.2);
+    standby.transitions = vec![Mode::Idle, Mode::Off];
+    let mut idle = OperatingMode::new(Mode::Idle, -0.5);
+    idle.min_dwell = 30.0;
+    let mut active = OperatingMode::new(Mode::Active, -2.0);
+    active.warm_up = 20.0;
+    active.warm_up_consumption = Some(-3.0);
+    let mut component = Component::new("Payload", -1.0, None, None, None)
+        .with_mode(standby)
+        .with_mode(idle)
+        .with_mode(active);
+
+    // Starts in the first mode of the table
+    assert_eq!(component.current_mode().unwrap().mode, Mode::Standby);
+    assert_eq!(component.power_consumption(), -0.2);
+
+    // Modes outside the table and disallowed transitions are refused
+    assert!(component.set_mode(Mode::Transmit, 0.0).is_err());
+    assert!(component.set_mode(Mode::Active, 0.0).is_err());
+    assert!(component.set_mode(Mode::Idle, 0.0).is_ok());
+    assert_eq!(component.power_consumption(), -0.5);
+
+    // Minimum dwell before leaving idle
+    assert!(component.set_mode(Mode::Active, 20.0).is_err());
+    assert!(component.set_mode(Mode::Active, 30.0).is_ok());
+
+    // Warm-up before the mode is usable, at the warm-up draw
+    component.active = true;
+    assert!(!component.ready(40.0));
+    assert_eq!(component.update_mode(40.0), None);
+    assert_eq!(component.power_consumption(), -3.0);
+    assert!(component.ready(50.0));
+    assert_eq!(
+        component.update_mode(50.0),
+        Some("Component 'Payload' ready in ACTIVE".to_string())
+    );
+    assert_eq!(component.power_consumption(), -2.0);
+
+    // Time and energy per mode
+    component.account_mode(3600.0);
+    let active = &component.modes[2];
+    assert_eq!(active.time, 3600.0);
+    assert_eq!(active.energy, 2.0);
+    assert_eq!(component.modes[0].time, 0.0);
+}
+
+#[test]
+fn activation_jitter() {
+    let component =
+        Component::new("Payload", -1.0, Some(-2.0), Some(10.0), Some(3.0)).with_offset(5.0, 2.0);
+
+    // Reproducible, bounded and different between cycles
+    let delays: Vec<f64> = (0..20).map(|c| component.jitter(c)).collect();
+    assert!(delays.iter().all(|d| (0.0..2.0).contains(d)));
+    assert!(delays.windows(2).any(|w| w[0] != w[1]));
+    assert_eq!(component.jitter(3), delays[3]);
+
+    // Inactive before the offset, active one step after the delayed start
+    assert_eq!(component.periodic_active(0.0, 1.0), Some(false));
+    let start = 5.0 + delays[0];
+    assert_eq!(component.periodic_active(start.ceil(), 1.0), Some(true));
+    assert_eq!(
+        Component::new("Bus", -1.0, None, None, None).periodic_active(0.0, 1.0),
+        None
+    );
+}
+
+#[test]
+fn brownout_states() {
+    let mut brownout = Brownout::new(5.0, 30.0, 0.0, -1.0);
+    assert!(brownout.powers("Camera"));
+
+    // Browns out again while the battery has not recovered, no boot time
+    assert!(brownout.update(10.0, 0.0, 1.0).is_empty());
+    assert_eq!(brownout.update(5.0, 1.0, 1.0).len(), 1);
+    assert_eq!(brownout.state(), BrownoutState::Dead);
+    assert!(!brownout.powers("Camera"));
+    assert!(brownout.update(29.0, 2.0, 1.0).is_empty());
+    assert_eq!(brownout.update(30.0, 3.0, 1.0).len(), 2);
+    assert_eq!(brownout.state(), BrownoutState::Powered);
+    assert_eq!(brownout.dead_time, 2.0);
+}
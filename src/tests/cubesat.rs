@@ -1,9 +1,12 @@
 #[allow(unused_imports)]
 use crate::cubesat::*;
 
+#[allow(unused_imports)]
+use crate::schedule::{self, Condition, ConditionKind, Logic, Trigger};
+
 #[test]
 fn new_toml_complete() {
-    let cubesat = CubeSat::from_toml("src/tests/complete.toml");
+    let cubesat = CubeSat::from_toml("src/tests/complete.toml").unwrap();
 
     let orbit_type = orbit::OrbitType::CircularCosine;
     let params = orbit::OrbitParameters {
@@ -16,42 +19,219 @@ fn new_toml_complete() {
     let time = time::Time::new(0.0, 100.0, 1.0);
     let vec = vector::Vector3::new(1.0, 1.0, 1.0);
     let sun = vector::Vector3::new(1.0, 0.0, 0.0);
-    let panel = component::SolarPanel::new(1.0, 1.0, 0.0, 0.0);
+    let mut panel = component::SolarPanel::new(1.0, 1.0, 0.0, 0.0);
+    panel.name = "Solar panel 1".to_string();
     let eps = component::Eps::new(-1.0, 10.0);
+    let mut contact = Condition::new(ConditionKind::Contact, None, None);
+    contact.station = Some("Kiruna".to_string());
+    let battery = Condition::new(ConditionKind::Battery, None, Some(30.0));
+    let trigger = Trigger::new(Logic::Or, vec![contact, battery], Some(30.0));
+    let mut idle = component::OperatingMode::new(component::Mode::Idle, -1.0);
+    idle.min_dwell = 5.0;
+    idle.transitions = vec![component::Mode::Active, component::Mode::Off];
+    let mut active = component::OperatingMode::new(component::Mode::Active, -2.0);
+    active.warm_up = 2.0;
+    active.warm_up_consumption = Some(-2.5);
     let component =
-        component::Component::new("Component", -1.0, Some(-2.0), Some(100.0), Some(10.0));
+        component::Component::new("Component", -1.0, Some(-2.0), Some(100.0), Some(10.0))
+            .with_mode(idle)
+            .with_mode(active)
+            .with_trigger(trigger)
+            .with_offset(5.0, 1.0);
+    let wheel =
+        component::ReactionWheel::new("Reaction wheel", (1.0, 0.0, 0.0), 0.001, 0.01, 0.0001, -0.1);
+    let magnetorquer = component::Magnetorquer::new(0.2, -0.5, 0.8, 0.2);
+    let mut geometry = attitude::Geometry::new(vec![((1.0, 0.0, 0.0), 0.01)]);
+    geometry.center_of_pressure = vector::Vector3::new(0.0, 0.0, 0.005);
+    geometry.residual_dipole = vector::Vector3::new(0.0, 0.0, 0.001);
 
     assert_eq!(cubesat.name, Some("APTAS".to_string()));
-    assert_eq!(cubesat.active, true);
+    assert!(cubesat.active);
     assert_eq!(cubesat.history, History::new());
-    assert_eq!(cubesat.safe_mode, false);
+    assert!(!cubesat.safe_mode);
     assert_eq!(cubesat.safe_limit, Some(20.0));
+    let mut policy = schedule::SafeModePolicy::new(20.0, 40.0, 600.0);
+    policy.shed = Some(vec!["Component".to_string()]);
+    policy.force_on = vec!["Beacon".to_string()];
+    assert_eq!(cubesat.safe_mode_policy, Some(policy));
+    let mut rule = fdir::Rule::new(
+        "Over-temperature",
+        "Battery temperature",
+        None,
+        Some(45.0),
+        vec![fdir::Action::new(
+            "Component",
+            schedule::CommandKind::Mode,
+            vec!["IDLE"],
+        )],
+    );
+    rule.persistence = 10.0;
+    rule.priority = 1;
+    rule.latch = true;
+    assert_eq!(cubesat.fdir_rules, Some(vec![rule]));
+    let mut fault = fdir::Fault::new(
+        "Stuck payload",
+        fdir::FaultKind::ModeLock,
+        100.0,
+        Some("Component"),
+        None,
+    );
+    fault.event = Some(maneuver::OrbitEvent::Apoapsis);
+    fault.mode = Some(component::Mode::Active);
+    assert_eq!(cubesat.faults, Some(vec![fault]));
     assert_eq!(cubesat.orbit_type, Some(orbit_type));
     assert_eq!(cubesat.orbit_parameters, Some(params));
     assert_eq!(cubesat.time, Some(time));
+    assert_eq!(cubesat.mass, Some(1.33));
+    assert_eq!(cubesat.propellant, Some(0.1));
+    let mut maneuver =
+        maneuver::Maneuver::impulsive("Raise", 50.0, maneuver::ManeuverFrame::Vnb, (1.0, 0.0, 0.0));
+    maneuver.event = Some(maneuver::OrbitEvent::Apoapsis);
+    maneuver.isp = Some(220.0);
+    maneuver.consumption = -5.0;
+    assert_eq!(cubesat.maneuvers, Some(vec![maneuver]));
+    let transfers = cubesat.transfers.as_ref().unwrap();
+    assert_eq!(transfers[0].kind, orbit::TransferKind::Phasing);
+    assert_eq!(transfers[0].start, 60.0);
+    assert_eq!(transfers[0].revolutions, 2);
+    assert_eq!(transfers[0].target.argument_of_periapsis, Some(10.0));
+    let mut propulsion = component::Propulsion::new("Thruster", 0.1, 0.2, 0.1, 0.05, 220.0);
+    propulsion.warm_up = 60.0;
+    propulsion.consumption_heater = -1.5;
+    propulsion.consumption_valve = -0.5;
+    assert_eq!(cubesat.propulsion, Some(propulsion));
+    let mut battery = component::Battery::new(0.95, 0.9);
+    battery.max_charge_rate = Some(1.0);
+    battery.voltage_curve = vec![(0.0, 6.0), (100.0, 8.4)];
+    battery.fade_per_cycle = 0.0001;
+    assert_eq!(cubesat.battery, Some(battery));
+    let mut brownout = component::Brownout::new(1.0, 10.0, 30.0, -2.0);
+    brownout.essential = Some(vec!["EPS".to_string()]);
+    assert_eq!(cubesat.brownout, Some(brownout));
+    let mut power_bus = component::PowerBus::new(component::Regulator::Det, 0.97);
+    power_bus.mismatch = 0.05;
+    power_bus
+        .rails
+        .push(component::Rail::new("3V3", 0.9, vec!["Component"]));
+    assert_eq!(cubesat.power_bus, Some(power_bus));
+    assert_eq!(
+        cubesat.gravity_field.as_ref().unwrap().path,
+        "src/tests/gravity.txt".to_string()
+    );
+    assert_eq!(cubesat.gravity_field.as_ref().unwrap().degree, 4);
+    let mut albedo = orbit::Albedo::new(0.3, 237.0);
+    albedo.path = Some("src/tests/albedo.txt".to_string());
+    albedo.resolution = 10.0;
+    assert_eq!(cubesat.albedo, Some(albedo));
+    let mut station = orbit::GroundStation::new("Kiruna", 67.9, 21.1);
+    station.min_elevation = 5.0;
+    assert_eq!(cubesat.ground_stations, Some(vec![station]));
+    assert!(cubesat.gravity_model.is_none());
+    assert_eq!(
+        cubesat.third_body,
+        Some(orbit::ThirdBody {
+            sun: true,
+            moon: true,
+        })
+    );
+    assert_eq!(
+        cubesat.solar_radiation_pressure,
+        Some(orbit::SolarRadiationPressure {
+            model: orbit::SrpModel::Cannonball,
+            area: 0.03,
+            reflectivity: 1.5,
+        })
+    );
     assert_eq!(cubesat.pos, Some(vec));
     assert_eq!(cubesat.vel, Some(vec));
     assert_eq!(cubesat.acc, Some(vec));
     assert_eq!(cubesat.rot, Some(vec));
     assert_eq!(cubesat.rot_vel, Some(vec));
     assert_eq!(cubesat.sun, Some(sun));
-    assert_eq!(cubesat.solar_panels.unwrap()[0], panel);
+    assert_eq!(cubesat.solar_panels.as_ref().unwrap()[0], panel);
+    let mut cells = component::SolarPanel::with_cells(0.0, 1.0, 0.0, 0.03, 0.3);
+    cells.name = "Solar panel 2".to_string();
+    cells.packing_factor = 0.9;
+    cells.temperature_coefficient = -0.0022;
+    cells.degradation = 0.0275;
+    assert_eq!(cubesat.solar_panels.as_ref().unwrap()[1], cells);
+    let mut wing = component::SolarPanel::new(2.0, 0.0, -1.0, 0.0);
+    wing.name = "Wing".to_string();
+    let mut deployment = component::Deployment::new(60.0, (0.0, 0.0, 1.0), 90.0, 10.0);
+    deployment.event = Some(maneuver::OrbitEvent::AscendingNode);
+    wing.deployment = Some(deployment);
+    wing.tracking = Some(component::Tracking::new((1.0, 0.0, 0.0), 0.5, -0.3));
+    wing.shape = Some(component::Rectangle::new(
+        (0.0, -0.05, -0.05),
+        (0.0, 0.0, 0.1),
+        (-0.2, 0.0, 0.0),
+    ));
+    wing.deployment.as_mut().unwrap().pivot = vector::Vector3::new(0.0, -0.05, 0.0);
+    assert_eq!(cubesat.solar_panels.as_ref().unwrap()[2], wing);
+    let bus = component::Rectangle::new((0.05, -0.05, -0.05), (0.0, 0.1, 0.0), (0.0, 0.0, 0.1));
+    let mut shadowing = component::Shadowing::new(vec![bus]);
+    shadowing.samples = 4;
+    assert_eq!(cubesat.shadowing, Some(shadowing));
+    assert_eq!(cubesat.lifetime, Some(5.0));
     assert_eq!(cubesat.eps, Some(eps));
     assert_eq!(cubesat.components, Some(vec![component]));
+    assert_eq!(
+        cubesat.inertia,
+        Some(vector::Vector3::new(0.01, 0.01, 0.01))
+    );
+    assert_eq!(cubesat.control_gain, Some(0.1));
+    assert_eq!(cubesat.geometry, Some(geometry));
+    assert_eq!(cubesat.reaction_wheels, Some(vec![wheel]));
+    assert_eq!(cubesat.magnetorquer, Some(magnetorquer));
+    let mut timeline = schedule::Timeline::new(vec![schedule::Command::new(
+        5.0,
+        "Component",
+        schedule::CommandKind::Auto,
+        Vec::new(),
+    )]);
+    timeline.path = Some("src/tests/timeline.csv".to_string());
+    assert_eq!(cubesat.timeline, Some(timeline));
+    let mut face = thermal::Node::face("+X", 50.0, (1.0, 0.0, 0.0), 0.01, 0.9, 0.8);
+    face.solar_panels = vec!["Solar panel 1".to_string()];
+    let mut battery = thermal::Node::new("Battery", 100.0, 15.0);
+    battery.dissipation = vec!["EPS".to_string(), "Component".to_string()];
+    let mut thermal = thermal::Thermal::new(vec![face, battery]);
+    thermal.conduction = vec![thermal::Link::new("+X", "Battery", 0.1)];
+    thermal.radiation = vec![thermal::Link::new("+X", "Battery", 0.005)];
+    thermal.battery = Some("Battery".to_string());
+    assert_eq!(cubesat.thermal, Some(thermal));
+    let heater = component::Heater::new("Battery heater", "Battery", -1.5, 0.0, 5.0);
+    assert_eq!(cubesat.heaters, Some(vec![heater]));
 }
 
 #[test]
 fn new_toml_default() {
-    let cubesat = CubeSat::from_toml("src/tests/default.toml");
+    let cubesat = CubeSat::from_toml("src/tests/default.toml").unwrap();
 
     assert_eq!(cubesat.name, Some("CubeSat".to_string()));
-    assert_eq!(cubesat.active, true);
+    assert!(cubesat.active);
     assert_eq!(cubesat.history, History::new());
-    assert_eq!(cubesat.safe_mode, false);
+    assert!(!cubesat.safe_mode);
     assert_eq!(cubesat.safe_limit, Some(0.0));
+    assert_eq!(cubesat.safe_mode_policy, None);
+    assert_eq!(cubesat.fdir_rules, None);
+    assert_eq!(cubesat.faults, None);
     assert_eq!(cubesat.orbit_type, None);
     assert_eq!(cubesat.orbit_parameters, None);
     assert_eq!(cubesat.time, None);
+    assert_eq!(cubesat.mass, None);
+    assert_eq!(cubesat.propellant, None);
+    assert_eq!(cubesat.maneuvers, None);
+    assert_eq!(cubesat.transfers, None);
+    assert_eq!(cubesat.propulsion, None);
+    assert_eq!(cubesat.battery, None);
+    assert_eq!(cubesat.brownout, None);
+    assert_eq!(cubesat.power_bus, None);
+    assert_eq!(cubesat.solar_radiation_pressure, None);
+    assert_eq!(cubesat.third_body, None);
+    assert_eq!(cubesat.albedo, None);
+    assert_eq!(cubesat.ground_stations, None);
+    assert_eq!(cubesat.gravity_field, None);
     assert_eq!(cubesat.pos, Some(vector::Vector3::origin()));
     assert_eq!(cubesat.vel, Some(vector::Vector3::origin()));
     assert_eq!(cubesat.acc, Some(vector::Vector3::origin()));
@@ -59,17 +239,212 @@ fn new_toml_default() {
     assert_eq!(cubesat.rot_vel, Some(vector::Vector3::origin()));
     assert_eq!(cubesat.sun, Some(vector::Vector3::new(1.0, 0.0, 0.0)));
     assert_eq!(cubesat.solar_panels, None);
+    assert_eq!(cubesat.lifetime, None);
+    assert_eq!(cubesat.shadowing, None);
     assert_eq!(cubesat.eps, None);
     assert_eq!(cubesat.components, None);
+    assert_eq!(cubesat.inertia, None);
+    assert_eq!(cubesat.geometry, None);
+    assert_eq!(cubesat.reaction_wheels, None);
+    assert_eq!(cubesat.magnetorquer, None);
+    assert_eq!(cubesat.timeline, None);
+    assert_eq!(cubesat.thermal, None);
+    assert_eq!(cubesat.heaters, None);
+}
+
+#[test]
+fn new_toml_errors() {
+    // Missing file
+    assert!(matches!(
+        CubeSat::from_toml("src/tests/missing.toml"),
+        Err(CubeSatError::Io { .. })
+    ));
+
+    // Wrong type, with the position of the value
+    match CubeSat::from_toml("src/tests/invalid.toml") {
+        Err(CubeSatError::Parse {
+            path, line, column, ..
+        }) => assert_eq!(
+            (path.as_str(), line, column),
+            ("src/tests/invalid.toml", 6, 8)
+        ),
+        _ => panic!("Expected a parse error"),
+    }
+}
+
+#[test]
+fn simulate_validation() {
+    // Missing settings are reported before the simulation starts
+    let mut cubesat = CubeSat::new().with_name("Invalid");
+    assert_eq!(cubesat.simulate(), Err(CubeSatError::missing("time")));
+
+    // A step that never reaches the end
+    let mut cubesat = CubeSat::new()
+        .with_name("Invalid")
+        .with_time(0.0, 10.0, 0.0)
+        .with_orbit_type("circular cosine")
+        .with_position(0.0, 0.0, 0.0)
+        .with_velocity(0.0, 0.0, 0.0)
+        .with_acceleration(0.0, 0.0, 0.0)
+        .with_rotation(0.0, 0.0, 0.0)
+        .with_rotation_velocity(0.0, 0.0, 0.0)
+        .with_sun(-1.0, 0.0, 0.0)
+        .with_solar_panels(vec![(1.0, 0.0, 0.0)], 1.0)
+        .with_eps(-1.0, 10.0)
+        .with_component("Camera", -1.0, None, None, None);
+    assert!(matches!(
+        cubesat.validate(),
+        Err(CubeSatError::InvalidParameter(_))
+    ));
+    cubesat.time.as_mut().unwrap().step = 1.0;
+    assert_eq!(cubesat.validate(), Ok(()));
+
+    // A panel needs a peak power or a cell model
+    cubesat.solar_panels.as_mut().unwrap()[0].power_generation = None;
+    assert_eq!(
+        cubesat.validate(),
+        Err(CubeSatError::missing(
+            "peak power or cell model for solar panel 'Solar panel'"
+        ))
+    );
+    cubesat.solar_panels.as_mut().unwrap()[0].power_generation = Some(1.0);
+
+    // Panels share History columns and faults by name
+    let twin = component::SolarPanel::new(1.0, 0.0, 1.0, 0.0);
+    cubesat.solar_panels.as_mut().unwrap().push(twin);
+    assert!(matches!(
+        cubesat.validate(),
+        Err(CubeSatError::InvalidParameter(_))
+    ));
+    cubesat.solar_panels.as_mut().unwrap().pop();
+
+    // Jitter that would overlap the next cycle
+    cubesat.components.as_mut().unwrap()[0] =
+        component::Component::new("Camera", -1.0, Some(-2.0), Some(10.0), Some(4.0))
+            .with_offset(0.0, 7.0);
+    assert!(matches!(
+        cubesat.validate(),
+        Err(CubeSatError::InvalidParameter(_))
+    ));
+    cubesat.components.as_mut().unwrap()[0].activation_jitter = Some(6.0);
+    assert_eq!(cubesat.validate(), Ok(()));
+
+    // Components that can be switched on need an active consumption
+    cubesat.components.as_mut().unwrap()[0].consumption_active = None;
+    assert_eq!(
+        cubesat.validate(),
+        Err(CubeSatError::missing(
+            "active consumption or mode table for component 'Camera'"
+        ))
+    );
+    cubesat.components.as_mut().unwrap()[0].consumption_active = Some(-2.0);
+
+    // Contact triggers need ground stations
+    let mut contact = Condition::new(ConditionKind::Contact, None, None);
+    contact.station = Some("Kiruna".to_string());
+    cubesat.components.as_mut().unwrap()[0].trigger =
+        Some(Trigger::new(Logic::And, vec![contact], None));
+    assert_eq!(
+        cubesat.validate(),
+        Err(CubeSatError::missing(
+            "ground stations for a contact trigger"
+        ))
+    );
+    cubesat.ground_stations = Some(vec![orbit::GroundStation::new("Svalbard", 78.2, 15.4)]);
+    assert!(matches!(
+        cubesat.validate(),
+        Err(CubeSatError::InvalidParameter(_))
+    ));
+    cubesat.ground_stations = Some(vec![orbit::GroundStation::new("Kiruna", 67.9, 21.1)]);
+    assert_eq!(cubesat.validate(), Ok(()));
+
+    // Finite burns need direction, duration, thrust and the mass
+    let mut burn = maneuver::Maneuver::finite(
+        "Push",
+        0.0,
+        maneuver::ManeuverFrame::Vnb,
+        (1.0, 0.0, 0.0),
+        0.1,
+        10.0,
+        220.0,
+    );
+    burn.thrust = None;
+    let mut cubesat = cubesat.with_maneuver(burn);
+    assert_eq!(
+        cubesat.validate(),
+        Err(CubeSatError::missing("thrust for maneuver 'Push'"))
+    );
+    cubesat.maneuvers.as_mut().unwrap()[0].thrust = Some(0.1);
+    assert_eq!(
+        cubesat.validate(),
+        Err(CubeSatError::missing("mass for maneuver 'Push'"))
+    );
+    cubesat.mass = Some(4.0);
+    cubesat.maneuvers.as_mut().unwrap()[0].isp = None;
+    assert!(matches!(
+        cubesat.validate(),
+        Err(CubeSatError::InvalidParameter(_))
+    ));
+    cubesat.maneuvers.as_mut().unwrap()[0].isp = Some(220.0);
+    assert_eq!(cubesat.validate(), Ok(()));
+
+    // Loads would reboot straight into the next brownout
+    cubesat.brownout = Some(component::Brownout::new(20.0, 20.0, 0.0, 0.0));
+    assert!(matches!(
+        cubesat.validate(),
+        Err(CubeSatError::InvalidParameter(_))
+    ));
+    cubesat.brownout = None;
+
+    // Safe mode needs hysteresis between its thresholds
+    cubesat.safe_mode_policy = Some(schedule::SafeModePolicy::new(50.0, 30.0, 0.0));
+    assert!(matches!(
+        cubesat.validate(),
+        Err(CubeSatError::InvalidParameter(_))
+    ));
+    cubesat.safe_mode_policy = None;
+
+    // Heaters need the thermal network to read their node temperature
+    let mut cubesat = cubesat.with_heater("Heater", "Battery", -1.0, 0.0, 5.0);
+    assert_eq!(
+        cubesat.validate(),
+        Err(CubeSatError::missing("thermal model for the heaters"))
+    );
+    cubesat.thermal = Some(crate::thermal::Thermal::new(vec![
+        crate::thermal::Node::new("Battery", 100.0, 20.0),
+    ]));
+    assert_eq!(cubesat.validate(), Ok(()));
+
+    // Solar radiation pressure needs the mass and, from faces, the geometry
+    cubesat.mass = None;
+    cubesat = cubesat.with_solar_radiation_pressure(orbit::SrpModel::Faces, 0.01, 1.3);
+    assert!(matches!(
+        cubesat.validate(),
+        Err(CubeSatError::InvalidParameter(_))
+    ));
+    cubesat.mass = Some(4.0);
+    assert!(matches!(
+        cubesat.validate(),
+        Err(CubeSatError::InvalidParameter(_))
+    ));
+    cubesat = cubesat.with_geometry(vec![((1.0, 0.0, 0.0), 0.01)]);
+    assert_eq!(cubesat.validate(), Ok(()));
+    cubesat.mass = None;
+
+    // The remaining delta-v needs the spacecraft mass
+    let mut cubesat = cubesat.with_propulsion(0.2, 0.3, 0.5, 0.1, 220.0);
+    assert_eq!(cubesat.validate(), Err(CubeSatError::missing("mass")));
+    cubesat.mass = Some(4.0);
+    assert_eq!(cubesat.validate(), Ok(()));
 }
 
 #[test]
 fn new() {
     let cubesat = CubeSat::new();
     assert_eq!(cubesat.name, Option::None);
-    assert_eq!(cubesat.active, true);
+    assert!(cubesat.active);
     assert_eq!(cubesat.history, History::new());
-    assert_eq!(cubesat.safe_mode, false);
+    assert!(!cubesat.safe_mode);
     assert_eq!(cubesat.safe_limit, Option::None);
     assert_eq!(cubesat.orbit_type, Option::None);
     assert_eq!(cubesat.orbit_parameters, Option::None);
@@ -240,7 +615,7 @@ fn with_solar_panels() {
     for i in 0..6 {
         assert_eq!(
             cubesat.solar_panels.as_ref().unwrap()[i].power_generation,
-            1.0
+            Some(1.0)
         );
     }
     assert_eq!(cubesat.solar_panels.as_ref().unwrap()[0].orientation.x, 1.0);
@@ -258,6 +633,14 @@ fn with_solar_panels() {
         cubesat.solar_panels.as_ref().unwrap()[5].orientation.z,
         -1.0
     );
+    assert_eq!(
+        cubesat.solar_panels.as_ref().unwrap()[0].name,
+        "Solar panel 1"
+    );
+    assert_eq!(
+        cubesat.solar_panels.as_ref().unwrap()[5].name,
+        "Solar panel 6"
+    );
 }
 
 #[test]
@@ -286,7 +669,7 @@ fn with_component() {
 #[test]
 fn with_safety_limit() {
     let cubesat = CubeSat::new().with_safety_limit(50.0);
-    assert_eq!(cubesat.safe_mode, false);
+    assert!(!cubesat.safe_mode);
     assert_eq!(cubesat.safe_limit, Option::Some(50.0));
 }
 
@@ -309,11 +692,11 @@ fn in_eclipse() {
     let cubesat_mz = CubeSat::new()
         .with_sun(-1.0, 0.0, 0.0)
         .with_position(0.0, 0.0, -radius);
-    assert_eq!(cubesat_x.in_eclipse(), false);
-    assert_eq!(cubesat_y.in_eclipse(), false);
-    assert_eq!(cubesat_my.in_eclipse(), false);
-    assert_eq!(cubesat_z.in_eclipse(), false);
-    assert_eq!(cubesat_mz.in_eclipse(), false);
+    assert!(!cubesat_x.in_eclipse());
+    assert!(!cubesat_y.in_eclipse());
+    assert!(!cubesat_my.in_eclipse());
+    assert!(!cubesat_z.in_eclipse());
+    assert!(!cubesat_mz.in_eclipse());
 
     // In the eclipse
     let cubesat_center = CubeSat::new()
@@ -335,11 +718,11 @@ fn in_eclipse() {
         CubeSat::new()
             .with_sun(-1.0, 0.0, 0.0)
             .with_position(-radius, 0.0, -orbit::RADIUS_EARTH);
-    assert_eq!(cubesat_center.in_eclipse(), true);
-    assert_eq!(cubesat_center_y.in_eclipse(), true);
-    assert_eq!(cubesat_center_my.in_eclipse(), true);
-    assert_eq!(cubesat_center_z.in_eclipse(), true);
-    assert_eq!(cubesat_center_mz.in_eclipse(), true);
+    assert!(cubesat_center.in_eclipse());
+    assert!(cubesat_center_y.in_eclipse());
+    assert!(cubesat_center_my.in_eclipse());
+    assert!(cubesat_center_z.in_eclipse());
+    assert!(cubesat_center_mz.in_eclipse());
 }
 
 #[test]
@@ -382,10 +765,10 @@ fn get_power_generation() {
 fn iterate() {
     let mut cubesat = CubeSat::new().with_time(0.0, 10.0, 1.0);
     for _ in 0..11 {
-        assert_eq!(cubesat.active, true);
+        assert!(cubesat.active);
         cubesat.iterate();
     }
-    assert_eq!(cubesat.active, false);
+    assert!(!cubesat.active);
 }
 
 #[test]
@@ -471,6 +854,25 @@ fn rotate_sun() {
     assert_eq!(sun.z, 0.0);
 }
 
+#[test]
+fn rotate_sun_ephemeris() {
+    // With third-body perturbations the sun follows the ephemeris, not the yearly rotation
+    let mut cubesat = CubeSat::new()
+        .with_time(0.0, 0.0, time::DAY)
+        .with_sun(1.0, 0.0, 0.0)
+        .with_third_body(true, false);
+    let time = cubesat.time.unwrap();
+    cubesat.rotate_sun();
+    let expected = orbit::sun_position(time.julian_date(0.0)).negative().unit();
+    assert!(cubesat.sun.unwrap().sub(&expected).abs() < 1e-12);
+
+    // Eclipse is on the far side from the ephemeris sun
+    cubesat.pos = Some(expected.scale(orbit::RADIUS_EARTH + 500_000.0));
+    assert!(cubesat.in_eclipse());
+    cubesat.pos = Some(expected.scale(-orbit::RADIUS_EARTH - 500_000.0));
+    assert!(!cubesat.in_eclipse());
+}
+
 #[test]
 fn update_active_components() {
     let mut cubesat = CubeSat::new().with_time(0.0, 10.0, 1.0).with_component(
@@ -481,35 +883,26 @@ fn update_active_components() {
         Some(2.0),
     );
     // Initially deactivated
-    assert_eq!(cubesat.components.as_ref().unwrap()[0].active, false);
+    assert!(!cubesat.components.as_ref().unwrap()[0].active);
 
     // Until activated
     for _ in 0..3 {
         cubesat.iterate();
-        cubesat.update_active_components(
-            cubesat.time.as_ref().unwrap().now.clone(),
-            cubesat.safe_mode.clone(),
-        );
+        cubesat.update_active_components(cubesat.time.as_ref().unwrap().now, cubesat.safe_mode);
     }
-    assert_eq!(cubesat.components.as_ref().unwrap()[0].active, true);
+    assert!(cubesat.components.as_ref().unwrap()[0].active);
 
     // Until deactivated
     for _ in 0..2 {
         cubesat.iterate();
-        cubesat.update_active_components(
-            cubesat.time.as_ref().unwrap().now.clone(),
-            cubesat.safe_mode.clone(),
-        );
+        cubesat.update_active_components(cubesat.time.as_ref().unwrap().now, cubesat.safe_mode);
     }
-    assert_eq!(cubesat.components.as_ref().unwrap()[0].active, false);
+    assert!(!cubesat.components.as_ref().unwrap()[0].active);
 
     // Active again
     cubesat.iterate();
-    cubesat.update_active_components(
-        cubesat.time.as_ref().unwrap().now.clone(),
-        cubesat.safe_mode.clone(),
-    );
-    assert_eq!(cubesat.components.as_ref().unwrap()[0].active, true);
+    cubesat.update_active_components(cubesat.time.as_ref().unwrap().now, cubesat.safe_mode);
+    assert!(cubesat.components.as_ref().unwrap()[0].active);
 }
 
 #[test]
@@ -528,17 +921,77 @@ fn check_safety_limit() {
     let mut cubesat = CubeSat::new().with_eps(0.0, 10.0).with_safety_limit(50.0);
 
     // Initially = false
-    assert_eq!(cubesat.safe_mode, false);
+    assert!(!cubesat.safe_mode);
 
     // Below limit = true
     cubesat.eps.as_mut().unwrap().charge = 4.0;
     cubesat.check_safety_limit();
-    assert_eq!(cubesat.safe_mode, true);
+    assert!(cubesat.safe_mode);
 
     // Above limit = false
     cubesat.eps.as_mut().unwrap().charge = 6.0;
     cubesat.check_safety_limit();
-    assert_eq!(cubesat.safe_mode, false);
+    assert!(!cubesat.safe_mode);
+}
+
+#[test]
+fn brownout() {
+    // Empty battery, the EPS keeps running and the camera loses power
+    let mut brownout = component::Brownout::new(0.0, 20.0, 2.0, -1.0);
+    brownout.essential = Some(vec!["EPS".to_string()]);
+    let mut cubesat = CubeSat::new()
+        .with_time(0.0, 10.0, 1.0)
+        .with_eps(-0.5, 10.0)
+        .with_component("Camera", -3.0, None, None, None);
+    cubesat.brownout = Some(brownout);
+
+    let mut consumption = Vec::new();
+    for charge in [5.0, 0.0, 1.0, 2.0, 2.0, 2.0, 2.0] {
+        cubesat.eps.as_mut().unwrap().charge = charge;
+        cubesat.check_brownout();
+        consumption.push(cubesat.get_power_consumption());
+        cubesat.iterate();
+    }
+
+    // Dead until 20%, then the boot sequence for 2 s
+    assert_eq!(consumption, vec![-3.5, -0.5, -0.5, -1.5, -1.5, -3.5, -3.5]);
+
+    // Loads are switched off into their initial mode and stay off until rebooted
+    let idle = component::OperatingMode::new(component::Mode::Idle, -0.5);
+    let active = component::OperatingMode::new(component::Mode::Active, -2.0);
+    let payload = component::Component::new("Payload", -1.0, Some(-2.0), None, None)
+        .with_mode(idle)
+        .with_mode(active);
+    let mut dead = CubeSat::new()
+        .with_time(0.0, 10.0, 1.0)
+        .with_eps(-0.5, 10.0);
+    dead.components = Some(vec![payload]);
+    dead.brownout = Some(component::Brownout::new(0.0, 20.0, 0.0, 0.0));
+    let on = schedule::Command::new(0.0, "Payload", schedule::CommandKind::On, Vec::new());
+    dead.apply_command(&on, 0.0).unwrap();
+    dead.update_active_components(0.0, false);
+    dead.update_modes();
+    dead.eps.as_mut().unwrap().charge = 0.0;
+    dead.check_brownout();
+    let payload = &dead.components.as_ref().unwrap()[0];
+    assert!(!payload.active);
+    assert_eq!(payload.commanded, None);
+    assert_eq!(payload.current_mode().unwrap().mode, component::Mode::Idle);
+    assert_eq!(dead.apply_command(&on, 0.0), Err("no power".to_string()));
+    dead.update_active_components(0.0, false);
+    assert!(!dead.components.as_ref().unwrap()[0].active);
+    let brownout = cubesat.brownout.as_ref().unwrap();
+    assert_eq!(brownout.brownouts, 1);
+    assert_eq!(brownout.dead_time, 2.0);
+    let events = cubesat.history.events();
+    assert_eq!(
+        events,
+        &vec![
+            (1.0, "Brownout, battery at 0.00%".to_string()),
+            (3.0, "Reboot started, battery at 20.00%".to_string()),
+            (5.0, "Reboot complete".to_string()),
+        ]
+    );
 }
 
 #[test]
@@ -587,3 +1040,515 @@ fn history_save() {
     assert_eq!(history.sun[1], (1.0, 1.0, 1.0));
     assert_eq!(history.charge[1], 1.0);
 }
+
+#[test]
+fn history_record() {
+    let mut history = History::new();
+    history.record("a", 1.0);
+    history.record("b", 2.0);
+    history.record("a", 3.0);
+    assert_eq!(history.column("a"), Some(&vec![1.0, 3.0]));
+    assert_eq!(history.column("b"), Some(&vec![2.0]));
+    assert_eq!(history.column("c"), None);
+
+    history.log_event(1.0, "Event".to_string());
+    assert_eq!(history.events(), &vec![(1.0, "Event".to_string())]);
+}
+
+#[test]
+fn history_save_reaction_wheels() {
+    let mut cubesat = CubeSat::new().with_time(0.0, 1.0, 1.0).with_reaction_wheel(
+        "RW",
+        (1.0, 0.0, 0.0),
+        1e-3,
+        1e-2,
+        1e-5,
+        -0.1,
+    );
+    cubesat.reaction_wheels.as_mut().unwrap()[0].speed = 100.0;
+    cubesat.save_history();
+    assert_eq!(cubesat.history.column("RW momentum"), Some(&vec![1e-3]));
+    assert_eq!(cubesat.get_power_consumption(), -0.1);
+}
+
+#[test]
+fn history_save_disturbance_torques() {
+    let mut cubesat = CubeSat::new()
+        .with_time(0.0, 1.0, 1.0)
+        .with_position(orbit::RADIUS_EARTH + 500_000.0, 0.0, 0.0)
+        .with_velocity(0.0, 7600.0, 0.0)
+        .with_rotation(0.0, 0.0, 0.0)
+        .with_sun(-1.0, 0.0, 0.0)
+        .with_inertia(0.01, 0.02, 0.03)
+        .with_geometry(vec![((0.0, 1.0, 0.0), 0.03)]);
+    cubesat.save_history();
+    for column in [
+        "gravity gradient torque",
+        "aerodynamic torque",
+        "solar radiation torque",
+        "magnetic torque",
+    ] {
+        assert_eq!(cubesat.history.column(column).unwrap().len(), 1);
+    }
+}
+
+#[test]
+fn update_battery() {
+    // Ideal battery by default
+    let mut ideal = CubeSat::new().with_time(0.0, 10.0, 1.0).with_eps(0.0, 10.0);
+    ideal.update_battery(-36.0);
+    assert_eq!(ideal.eps.unwrap().charge, 9.99);
+
+    // Depth of discharge once per orbit
+    let mut cubesat = CubeSat::new()
+        .with_time(0.0, 10_000.0, 60.0)
+        .with_orbit_parameters(vec![("semi-major axis", orbit::RADIUS_EARTH + 500_000.0)])
+        .with_eps(0.0, 10.0)
+        .with_battery(0.95, 0.95);
+    let period = cubesat.orbit_parameters.as_ref().unwrap().period().unwrap();
+    while cubesat.time.unwrap().now < period + 60.0 {
+        let now = cubesat.time.unwrap().now;
+        let power = match now % period < period / 2.0 {
+            true => -5.0,
+            false => 5.0,
+        };
+        cubesat.save_history();
+        cubesat.update_battery(power);
+        cubesat.iterate();
+    }
+    let events = cubesat.history.events();
+    assert_eq!(events.len(), 1);
+    assert!(events[0].1.starts_with("Orbit 1 depth of discharge"));
+    let battery = cubesat.battery.as_ref().unwrap();
+    assert!(battery.depth_of_discharge[0] > 30.0);
+    assert!(battery.cycles() > 0.0);
+    assert!(cubesat.history.column("battery cycles").is_some());
+}
+
+#[test]
+fn update_power() {
+    let mut bus = component::PowerBus::new(component::Regulator::Mppt, 0.9);
+    bus.rails
+        .push(component::Rail::new("5V", 0.8, vec!["Radio"]));
+    let mut cubesat = CubeSat::new()
+        .with_time(0.0, 10.0, 36.0)
+        .with_eps(-1.0, 10.0)
+        .with_component("Radio", -2.0, None, None, None)
+        .with_power_bus(bus);
+    cubesat.eps.as_mut().unwrap().charge = 5.0;
+
+    // 10 W generated, 9 W delivered, 3.5 W drawn, 5.5 W stored
+    cubesat.update_power(10.0);
+    assert!((cubesat.eps.unwrap().charge - 5.055).abs() < 1e-12);
+    assert_eq!(cubesat.history.column("5V load"), Some(&vec![-2.0]));
+    let energy = cubesat.energy;
+    assert!((energy.generated - 0.1).abs() < 1e-12);
+    assert!((energy.consumed - 0.03).abs() < 1e-12);
+    assert!((energy.stored - 0.055).abs() < 1e-12);
+    assert!(energy.wasted.abs() < 1e-12);
+    assert!((energy.losses - 0.015).abs() < 1e-12);
+
+    // Full battery, the surplus is clipped
+    cubesat.eps.as_mut().unwrap().charge = 10.0;
+    cubesat.update_power(10.0);
+    assert_eq!(cubesat.history.column("stored power").unwrap()[1], 0.0);
+    assert!((cubesat.history.column("wasted power").unwrap()[1] - 5.5).abs() < 1e-12);
+
+    // Empty battery, the load it cannot supply is unmet
+    cubesat.eps.as_mut().unwrap().charge = 0.0;
+    cubesat.update_power(0.0);
+    assert_eq!(cubesat.history.column("stored power").unwrap()[2], 0.0);
+    assert!((cubesat.history.column("unmet load").unwrap()[2] - 3.5).abs() < 1e-12);
+    assert_eq!(cubesat.history.column("unmet load").unwrap().len(), 3);
+
+    // Energy balance
+    let energy = cubesat.energy;
+    let balance = energy.consumed + energy.stored + energy.wasted + energy.losses;
+    assert!((energy.generated + energy.unmet - balance).abs() < 1e-12);
+
+    cubesat.summarize();
+    assert_eq!(
+        cubesat.history.summary()[0].0,
+        "energy generated".to_string()
+    );
+    assert_eq!(cubesat.history.summary()[0].1, "0.2000 Wh".to_string());
+    assert_eq!(cubesat.history.summary()[4].0, "energy unmet".to_string());
+}
+
+#[test]
+fn solar_flux_and_end_of_life() {
+    // Earth is closest to the sun in early January
+    let mut cubesat = CubeSat::new().with_time(0.0, 1.0, 1.0);
+    let january = cubesat.solar_flux();
+    cubesat.time.as_mut().unwrap().now = 182.0 * time::DAY;
+    let july = cubesat.solar_flux();
+    assert!(january > 1_400.0 && january < 1_420.0);
+    assert!(july > 1_310.0 && july < 1_330.0);
+
+    // End-of-life power from the cell model
+    let mut panel = component::SolarPanel::with_cells(1.0, 0.0, 0.0, 0.03, 0.3);
+    panel.degradation = 0.03;
+    cubesat.solar_panels = Some(vec![panel]);
+    cubesat.lifetime = Some(5.0);
+    cubesat.summarize();
+    let summary = cubesat.history.summary();
+    let beginning = orbit::SOLAR_CONSTANT * 0.03 * 0.3;
+    let end = beginning * 0.97_f64.powi(5);
+    assert!(summary.contains(&(
+        "beginning-of-life power".to_string(),
+        format!("{beginning:.4} W")
+    )));
+    assert!(summary.contains(&("end-of-life power".to_string(), format!("{end:.4} W"))));
+}
+
+#[test]
+fn update_solar_panels() {
+    // Wing stowed against -x, deploys towards the sun at +y
+    let mut wing = component::SolarPanel::new(1.0, -1.0, 0.0, 0.0);
+    wing.name = "Wing".to_string();
+    wing.deployment = Some(component::Deployment::new(1.0, (0.0, 0.0, 1.0), -90.0, 0.0));
+    wing.tracking = Some(component::Tracking::new((0.0, 0.0, 1.0), 10.0, -0.2));
+    let mut cubesat = CubeSat::new()
+        .with_time(0.0, 10.0, 1.0)
+        .with_position(orbit::RADIUS_EARTH + 500_000.0, 0.0, 0.0)
+        .with_velocity(0.0, 7600.0, 0.0)
+        .with_rotation(0.0, 0.0, 0.0)
+        .with_sun(-0.6, -0.8, 0.0);
+    cubesat.solar_panels = Some(vec![wing]);
+
+    // Stowed, facing away from the sun
+    cubesat.update_solar_panels();
+    assert_eq!(cubesat.get_power_generation(), 0.0);
+    assert_eq!(cubesat.get_power_consumption(), 0.0);
+
+    // Deployed, then tracking
+    cubesat.iterate();
+    cubesat.update_solar_panels();
+    assert_eq!(cubesat.history.events()[0].1, "Solar panel 'Wing' deployed");
+    assert_eq!(cubesat.get_power_consumption(), -0.2);
+    for _ in 0..5 {
+        cubesat.iterate();
+        cubesat.update_solar_panels();
+    }
+    assert!((cubesat.get_power_generation() - 1.0).abs() < 1e-9);
+    assert_eq!(cubesat.get_power_consumption(), 0.0);
+    cubesat.save_history();
+    assert_eq!(cubesat.history.column("Wing deployment"), Some(&vec![1.0]));
+}
+
+#[test]
+fn self_shadowing() {
+    // Body panel on +x, a deployed wing in front of half of it
+    let mut body = component::SolarPanel::new(1.0, 1.0, 0.0, 0.0);
+    body.name = "Body".to_string();
+    body.shape = Some(component::Rectangle::new(
+        (0.05, -0.05, -0.05),
+        (0.0, 0.1, 0.0),
+        (0.0, 0.0, 0.1),
+    ));
+    let mut wing = component::SolarPanel::new(1.0, 0.0, 1.0, 0.0);
+    wing.name = "Wing".to_string();
+    wing.shape = Some(component::Rectangle::new(
+        (0.15, -0.05, -0.05),
+        (0.0, 0.0, 0.1),
+        (0.0, 0.05, 0.0),
+    ));
+    let mut cubesat = CubeSat::new()
+        .with_time(0.0, 10.0, 1.0)
+        .with_position(orbit::RADIUS_EARTH + 500_000.0, 0.0, 0.0)
+        .with_velocity(0.0, 7600.0, 0.0)
+        .with_rotation(0.0, 0.0, 0.0)
+        .with_sun(-1.0, 0.0, 0.0);
+    cubesat.solar_panels = Some(vec![body, wing]);
+
+    // Without shadowing the panels are independent
+    cubesat.update_solar_panels();
+    assert_eq!(cubesat.get_power_generation(), 1.0);
+
+    cubesat.shadowing = Some(component::Shadowing::new(Vec::new()));
+    cubesat.update_solar_panels();
+    assert_eq!(cubesat.get_power_generation(), 0.5);
+    cubesat.save_history();
+    assert_eq!(cubesat.history.column("Body shadow"), Some(&vec![0.5]));
+    assert_eq!(cubesat.history.column("Wing shadow"), Some(&vec![0.0]));
+}
+
+#[test]
+fn albedo_power() {
+    // Nadir panel gets no direct sunlight with the sun overhead
+    let mut cubesat = CubeSat::new()
+        .with_time(0.0, 10.0, 1.0)
+        .with_position(orbit::RADIUS_EARTH + 500_000.0, 0.0, 0.0)
+        .with_rotation(0.0, 0.0, 0.0)
+        .with_sun(-1.0, 0.0, 0.0)
+        .with_solar_panels(vec![(-1.0, 0.0, 0.0)], 2.0);
+    assert_eq!(cubesat.get_power_generation(), 0.0);
+    cubesat.albedo = Some(orbit::Albedo::new(0.3, 237.0));
+    cubesat.update_albedo();
+    let overhead = cubesat.get_power_generation();
+    assert!(overhead > 0.5 * 0.3 * 2.0 && overhead < 0.3 * 2.0);
+
+    // Shaded cells see less of the reflected light
+    cubesat.solar_panels.as_mut().unwrap()[0].shadow = 0.5;
+    assert!((cubesat.get_power_generation() - 0.5 * overhead).abs() < 1e-12);
+    cubesat.solar_panels.as_mut().unwrap()[0].shadow = 0.0;
+
+    // Over the terminator only part of the visible Earth is sunlit
+    cubesat.sun = Some(vector::Vector3::new(0.0, -1.0, 0.0));
+    cubesat.update_albedo();
+    assert!(cubesat.get_power_generation() > 0.0);
+    assert!(cubesat.get_power_generation() < overhead);
+    cubesat.save_history();
+    assert!(cubesat.history.column("albedo power").unwrap()[0] > 0.0);
+
+    // Earth infrared is recorded, not converted
+    let infrared = cubesat
+        .history
+        .column("Solar panel earth infrared")
+        .unwrap()[0];
+    assert!(infrared > 0.5 * 237.0 && infrared < 237.0);
+}
+
+#[test]
+fn update_modes() {
+    // Periodic activation drives the mode table
+    let mut idle = component::OperatingMode::new(component::Mode::Idle, -0.5);
+    idle.min_dwell = 2.0;
+    let mut active = component::OperatingMode::new(component::Mode::Active, -2.0);
+    active.warm_up = 1.0;
+    let payload = component::Component::new("Payload", -1.0, Some(-2.0), Some(4.0), Some(1.0))
+        .with_mode(idle)
+        .with_mode(active);
+    let mut cubesat = CubeSat::new().with_time(0.0, 8.0, 1.0);
+    cubesat.components = Some(vec![payload]);
+
+    for _ in 0..8 {
+        let now = cubesat.time.as_ref().unwrap().now;
+        cubesat.update_active_components(now, cubesat.safe_mode);
+        cubesat.update_modes();
+        cubesat.save_history();
+        cubesat.iterate();
+    }
+
+    // Activation at t = 0 is held back by the dwell in idle, t = 4 goes through
+    let events = cubesat.history.events();
+    assert_eq!(events[0].0, 0.0);
+    assert!(events[0].1.contains("refused, minimum dwell in IDLE"));
+    assert_eq!(
+        events[1],
+        (4.0, "Component 'Payload' IDLE to ACTIVE".to_string())
+    );
+    assert_eq!(
+        events[2],
+        (5.0, "Component 'Payload' ACTIVE to IDLE".to_string())
+    );
+    assert_eq!(
+        cubesat.history.column("Payload mode").unwrap()[4],
+        component::Mode::Active as u8 as f64
+    );
+    assert_eq!(cubesat.history.column("Payload ready").unwrap()[4], 0.0);
+    assert_eq!(cubesat.history.column("Payload ready").unwrap()[5], 1.0);
+    assert_eq!(cubesat.get_power_consumption(), -0.5);
+
+    // Per-mode time and energy in the summary
+    cubesat.summarize();
+    let summary = cubesat.history.summary();
+    assert!(summary.contains(&("Payload IDLE time".to_string(), "7.0 s".to_string())));
+    assert!(summary.contains(&("Payload ACTIVE time".to_string(), "1.0 s".to_string())));
+}
+
+#[test]
+fn triggered_components() {
+    // Camera over the equator while sunlit, transmitter for 2 s after eclipse entry
+    let sunlit = Condition::new(ConditionKind::Sunlit, None, None);
+    let equator = Condition::new(ConditionKind::Latitude, Some(-5.0), Some(5.0));
+    let eclipse = Condition::new(ConditionKind::Eclipse, None, None);
+    let camera = component::Component::new("Camera", 0.0, Some(-3.0), None, None)
+        .with_trigger(Trigger::new(Logic::And, vec![sunlit, equator], None));
+    let radio = component::Component::new("Radio", 0.0, Some(-4.0), None, None)
+        .with_trigger(Trigger::new(Logic::Or, vec![eclipse], Some(2.0)));
+    let r = orbit::RADIUS_EARTH + 500_000.0;
+    let mut cubesat = CubeSat::new()
+        .with_time(0.0, 10.0, 1.0)
+        .with_position(0.0, 0.0, 0.0)
+        .with_sun(-1.0, 0.0, 0.0);
+    cubesat.components = Some(vec![camera, radio]);
+
+    let active = |cubesat: &CubeSat| {
+        let components = cubesat.components.as_ref().unwrap();
+        (components[0].active, components[1].active)
+    };
+
+    // No position before the first orbit update, triggers wait
+    cubesat.update_active_components(0.0, false);
+    assert_eq!(active(&cubesat), (false, false));
+    cubesat.pos = Some(vector::Vector3::new(r, 0.0, 0.0));
+    cubesat.update_active_components(0.0, false);
+    assert_eq!(active(&cubesat), (true, false));
+
+    // Enter eclipse: camera off, radio pulse
+    cubesat.pos = Some(vector::Vector3::new(-r, 0.0, 0.0));
+    cubesat.update_active_components(1.0, false);
+    assert_eq!(active(&cubesat), (false, true));
+    cubesat.update_active_components(2.0, false);
+    assert_eq!(active(&cubesat), (false, true));
+    cubesat.update_active_components(3.0, false);
+    assert_eq!(active(&cubesat), (false, false));
+
+    // Safe mode keeps triggered components off
+    cubesat.pos = Some(vector::Vector3::new(r, 0.0, 0.0));
+    cubesat.update_active_components(4.0, true);
+    assert_eq!(active(&cubesat), (false, false));
+}
+
+#[test]
+fn timeline_commands() {
+    // Periodic payload with an operations plan on top
+    let idle = component::OperatingMode::new(component::Mode::Idle, -0.5);
+    let active = component::OperatingMode::new(component::Mode::Active, -2.0);
+    let transmit = component::OperatingMode::new(component::Mode::Transmit, -4.0);
+    let payload = component::Component::new("Payload", -1.0, Some(-2.0), Some(10.0), Some(2.0))
+        .with_mode(idle)
+        .with_mode(active)
+        .with_mode(transmit);
+    let bus = component::Component::new("Bus", -0.5, None, None, None);
+    let mut cubesat = CubeSat::new().with_time(0.0, 12.0, 1.0);
+    cubesat.components = Some(vec![payload, bus]);
+    cubesat.timeline = Some(schedule::Timeline::new(vec![
+        schedule::Command::new(3.0, "Payload", schedule::CommandKind::On, Vec::new()),
+        schedule::Command::new(5.0, "Payload", schedule::CommandKind::Auto, Vec::new()),
+        schedule::Command::new(
+            6.0,
+            "Payload",
+            schedule::CommandKind::Mode,
+            vec!["Transmit"],
+        ),
+        schedule::Command::new(7.0, "Radio", schedule::CommandKind::Off, Vec::new()),
+        schedule::Command::new(8.0, "Bus", schedule::CommandKind::On, Vec::new()),
+    ]));
+
+    let mut active = Vec::new();
+    for _ in 0..12 {
+        let now = cubesat.time.as_ref().unwrap().now;
+        cubesat.apply_commands();
+        cubesat.update_active_components(now, cubesat.safe_mode);
+        cubesat.update_modes();
+        active.push(cubesat.components.as_ref().unwrap()[0].active);
+        cubesat.iterate();
+    }
+
+    // Forced on from 3 s until handed back to the schedule at 5 s, the mode holds over the next cycle
+    assert_eq!(
+        active,
+        vec![true, true, false, true, true, false, false, false, false, false, false, false]
+    );
+    assert_eq!(
+        cubesat.components.as_ref().unwrap()[0].mode,
+        Some(component::Mode::Transmit)
+    );
+    let events = cubesat.history.events();
+    assert!(events.contains(&(3.0, "Command 'on' to 'Payload'".to_string())));
+    assert!(events.contains(&(6.0, "Command 'mode Transmit' to 'Payload'".to_string())));
+    assert!(events.contains(&(
+        7.0,
+        "Command 'off' to 'Radio' refused, unknown component".to_string()
+    )));
+    assert!(events.contains(&(
+        8.0,
+        "Command 'on' to 'Bus' refused, no active consumption".to_string()
+    )));
+}
+
+#[test]
+fn periodic_activation_matrix() {
+    // Every cycle is caught for awkward steps, start times and offsets
+    for step in [1.0, 0.3, 0.7, 0.1, 2.5] {
+        for start in [0.0, 0.5, 7.3] {
+            for (offset, jitter) in [(0.0, 0.0), (4.0, 0.0), (0.0, 2.0)] {
+                let payload =
+                    component::Component::new("Payload", -1.0, Some(-2.0), Some(10.0), Some(3.0))
+                        .with_offset(offset, jitter);
+                let mut cubesat = CubeSat::new().with_time(start, start + 100.0, step);
+                cubesat.components = Some(vec![payload]);
+
+                let (mut activations, mut active_time, mut previous) = (0, 0.0, false);
+                let mut first = None;
+                while cubesat.active {
+                    let now = cubesat.time.as_ref().unwrap().now;
+                    if now >= start + 100.0 - 0.5 * step {
+                        break;
+                    }
+                    cubesat.update_active_components(now, false);
+                    let active = cubesat.components.as_ref().unwrap()[0].active;
+                    if active && !previous {
+                        activations += 1;
+                        first.get_or_insert(now - start);
+                    }
+                    if active {
+                        active_time += step;
+                    }
+                    previous = active;
+                    cubesat.iterate();
+                }
+
+                let case = format!("step {step}, start {start}, offset {offset}, jitter {jitter}");
+                assert_eq!(activations, 10, "{case}");
+                assert!((active_time - 30.0_f64).abs() <= 10.0 * step, "{case}");
+                let first = first.unwrap();
+                assert!(
+                    first >= offset - step && first <= offset + jitter + step,
+                    "{case}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn safe_mode_policy() {
+    // Camera and the always-on logger are shed, the beacon is forced on and the radio keeps its schedule
+    let mut policy = schedule::SafeModePolicy::new(30.0, 50.0, 2.0);
+    policy.shed = Some(vec!["Camera".to_string(), "Logger".to_string()]);
+    policy.force_on = vec!["Beacon".to_string()];
+    let mut cubesat = CubeSat::new()
+        .with_time(0.0, 10.0, 1.0)
+        .with_eps(0.0, 10.0)
+        .with_component("Camera", 0.0, Some(-3.0), Some(1.0), Some(1.0))
+        .with_component("Radio", 0.0, Some(-2.0), Some(1.0), Some(1.0))
+        .with_component("Beacon", 0.0, Some(-0.5), None, None)
+        .with_component("Logger", 0.0, Some(-0.1), None, None);
+    cubesat.components.as_mut().unwrap()[3].active = true;
+    cubesat.safe_mode_policy = Some(policy);
+
+    let mut states = Vec::new();
+    for charge in [5.0, 2.9, 4.0, 6.0, 6.0, 4.0] {
+        cubesat.eps.as_mut().unwrap().charge = charge;
+        cubesat.check_safety_limit();
+        let now = cubesat.time.as_ref().unwrap().now;
+        cubesat.update_active_components(now, cubesat.safe_mode);
+        let components = cubesat.components.as_ref().unwrap();
+        states.push((
+            cubesat.safe_mode,
+            components[0].active,
+            components[1].active,
+            components[2].active,
+            components[3].active,
+        ));
+        cubesat.iterate();
+    }
+
+    // Enter at 2.9 Wh, hold through 4 Wh and the dwell, exit at 6 Wh after 2 s
+    assert_eq!(states[0], (false, true, true, false, true));
+    assert_eq!(states[1], (true, false, true, true, false));
+    assert_eq!(states[2], (true, false, true, true, false));
+    assert_eq!(states[3], (false, true, true, false, true));
+    assert_eq!(states[5], (false, true, true, false, true));
+    let events = cubesat.history.events();
+    assert_eq!(
+        events,
+        &vec![
+            (1.0, "Safe mode entered, battery at 29.00%".to_string()),
+            (3.0, "Safe mode exited, battery at 60.00%".to_string()),
+        ]
+    );
+}
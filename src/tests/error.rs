@@ -0,0 +1,48 @@
+#[allow(unused_imports)]
+use crate::error::*;
+
+#[test]
+fn parse_position() {
+    let text = "name = \"A\"\nmass = x\n";
+    let error = CubeSatError::parse("a.toml", text, 18, "invalid value");
+    assert_eq!(
+        error,
+        CubeSatError::Parse {
+            path: "a.toml".to_string(),
+            line: 2,
+            column: 8,
+            message: "invalid value".to_string(),
+        }
+    );
+    assert_eq!(error.to_string(), "a.toml:2:8: invalid value");
+
+    // Offsets past the end point at the last line
+    let error = CubeSatError::parse("a.toml", text, 100, "");
+    assert!(matches!(
+        error,
+        CubeSatError::Parse {
+            line: 3,
+            column: 1,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn toml_error() {
+    let text = "[time]\nstep = = 1.0\n";
+    let error = toml::from_str::<toml::Table>(text).unwrap_err();
+    match CubeSatError::toml("b.toml", text, &error) {
+        CubeSatError::Parse { line, column, .. } => assert_eq!((line, column), (2, 8)),
+        e => panic!("Unexpected error {e:?}"),
+    }
+}
+
+#[test]
+fn display() {
+    assert_eq!(CubeSatError::missing("time").to_string(), "No time is set");
+    assert_eq!(
+        CubeSatError::InvalidParameter("step must be positive".to_string()).to_string(),
+        "Invalid parameter: step must be positive"
+    );
+}
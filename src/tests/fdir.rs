@@ -0,0 +1,272 @@
+#[allow(unused_imports)]
+use crate::fdir::*;
+
+#[allow(unused_imports)]
+use crate::schedule::CommandKind;
+
+#[allow(unused_imports)]
+use crate::{component, maneuver, orbit};
+
+#[test]
+fn rule_persistence_and_latch() {
+    let mut rule = Rule::new(
+        "Over-temperature",
+        "Battery temperature",
+        None,
+        Some(45.0),
+        Vec::new(),
+    );
+    rule.persistence = 2.0;
+
+    // Fires once the violation has persisted, clears when it is gone
+    assert_eq!(rule.update(Some(50.0), 0.0, 1.0), None);
+    assert_eq!(rule.update(Some(50.0), 1.0, 1.0), None);
+    assert_eq!(rule.update(Some(50.0), 2.0, 1.0), Some(true));
+    assert_eq!(rule.update(Some(50.0), 3.0, 1.0), None);
+    assert_eq!(rule.update(Some(40.0), 4.0, 1.0), Some(false));
+    assert_eq!(rule.update(None, 5.0, 1.0), None);
+
+    // Latched rules never clear
+    rule.latch = true;
+    rule.persistence = 0.0;
+    assert_eq!(rule.update(Some(49.0), 6.0, 1.0), Some(true));
+    assert_eq!(rule.update(Some(20.0), 7.0, 1.0), None);
+    assert!(rule.fired);
+    assert_eq!(rule.firings, 2);
+
+    // Both limits
+    let band = Rule::new("Band", "x", Some(0.0), Some(1.0), Vec::new());
+    assert!(band.violated(-0.1));
+    assert!(!band.violated(0.5));
+    assert!(band.violated(1.1));
+}
+
+#[test]
+fn update_fdir_priority() {
+    // Undervoltage switches the camera off, a less important rule wants it on
+    let mut undervoltage = Rule::new(
+        "Undervoltage",
+        "battery",
+        Some(30.0),
+        None,
+        vec![Action::new("Camera", CommandKind::Off, Vec::new())],
+    );
+    undervoltage.priority = 1;
+    undervoltage.latch = true;
+    let attitude = Rule::new(
+        "Attitude lost",
+        "rate",
+        None,
+        Some(0.1),
+        vec![
+            Action::new("Camera", CommandKind::On, Vec::new()),
+            Action::new("Magnetometer", CommandKind::On, Vec::new()),
+        ],
+    );
+    let mut cubesat = cubesat::CubeSat::new()
+        .with_time(0.0, 10.0, 1.0)
+        .with_rotation_velocity(0.0, 0.0, 0.5)
+        .with_eps(0.0, 10.0)
+        .with_component("Camera", 0.0, Some(-3.0), None, None)
+        .with_component("Magnetometer", 0.0, Some(-0.1), None, None);
+    cubesat.eps.as_mut().unwrap().charge = 2.0;
+    cubesat.fdir_rules = Some(vec![attitude, undervoltage]);
+
+    update_fdir(&mut cubesat).unwrap();
+    cubesat.update_active_components(0.0, false);
+    let components = cubesat.components.as_ref().unwrap();
+    assert!(!components[0].active);
+    assert!(components[1].active);
+
+    // Every firing and action is recorded
+    let events: Vec<&String> = cubesat.history.events().iter().map(|e| &e.1).collect();
+    assert_eq!(
+        events,
+        vec![
+            "FDIR rule 'Undervoltage' fired, battery at 20.000",
+            "FDIR rule 'Undervoltage' command 'off' to 'Camera'",
+            "FDIR rule 'Attitude lost' fired, rate at 0.500",
+            "FDIR rule 'Attitude lost' command 'on' to 'Camera' refused, held by a higher priority rule",
+            "FDIR rule 'Attitude lost' command 'on' to 'Magnetometer'",
+        ]
+    );
+    cubesat.summarize();
+    assert!(cubesat
+        .history
+        .summary()
+        .contains(&("Undervoltage firings".to_string(), "1".to_string())));
+}
+
+#[test]
+fn fault_injection() {
+    let idle = component::OperatingMode::new(component::Mode::Idle, -0.5);
+    let active = component::OperatingMode::new(component::Mode::Active, -2.0);
+    let payload = component::Component::new("Payload", -1.0, Some(-2.0), Some(10.0), Some(5.0))
+        .with_mode(idle)
+        .with_mode(active);
+    let mut lock = Fault::new(
+        "Payload stuck",
+        FaultKind::ModeLock,
+        2.0,
+        Some("Payload"),
+        None,
+    );
+    lock.mode = Some(component::Mode::Active);
+    let mut cubesat = cubesat::CubeSat::new()
+        .with_time(0.0, 10.0, 1.0)
+        .with_position(orbit::RADIUS_EARTH + 500_000.0, 0.0, 0.0)
+        .with_rotation(0.0, 0.0, 0.0)
+        .with_sun(-1.0, 0.0, 0.0)
+        .with_solar_panels(vec![(1.0, 0.0, 0.0)], 2.0)
+        .with_eps(0.0, 10.0)
+        .with_safety_limit(30.0)
+        .with_reaction_wheel("RW X", (1.0, 0.0, 0.0), 1e-3, 1e-2, 1e-5, -0.1);
+    cubesat.components = Some(vec![payload]);
+    cubesat.eps.as_mut().unwrap().charge = 5.0;
+    cubesat.reaction_wheels.as_mut().unwrap()[0].speed = 100.0;
+    cubesat.faults = Some(vec![
+        Fault::new(
+            "Panel",
+            FaultKind::PanelFailure,
+            1.0,
+            Some("Solar panel"),
+            None,
+        ),
+        Fault::new("Cell", FaultKind::CapacityLoss, 1.0, None, Some(0.75)),
+        lock,
+        Fault::new(
+            "Gauge",
+            FaultKind::SensorBias,
+            2.0,
+            Some("battery"),
+            Some(-40.0),
+        ),
+        Fault::new("Wheel", FaultKind::WheelStuck, 2.0, Some("RW X"), None),
+        Fault::new("Ghost", FaultKind::PanelFailure, 3.0, Some("Missing"), None),
+    ]);
+    let power = cubesat.get_power_generation();
+    assert!(power > 0.0);
+
+    for _ in 0..4 {
+        inject_faults(&mut cubesat);
+        cubesat.check_safety_limit();
+        cubesat.save_history();
+        cubesat.iterate();
+    }
+
+    // Degraded power, capacity and wheel
+    assert_eq!(cubesat.get_power_generation(), 0.0);
+    assert_eq!(cubesat.eps.as_ref().unwrap().max_charge, 7.5);
+    let wheel = &cubesat.reaction_wheels.as_ref().unwrap()[0];
+    assert_eq!((wheel.max_torque, wheel.speed), (0.0, 100.0));
+
+    // Locked payload refuses to leave its mode
+    let payload = &mut cubesat.components.as_mut().unwrap()[0];
+    assert_eq!(
+        payload.current_mode().unwrap().mode,
+        component::Mode::Active
+    );
+    assert!(payload.set_mode(component::Mode::Idle, 100.0).is_err());
+
+    // The biased gauge reads 26.67% instead of 66.67% and triggers safe mode
+    assert!((cubesat.quantity("battery").unwrap() - 100.0 * 5.0 / 7.5 + 40.0).abs() < 1e-9);
+    assert!(cubesat.safe_mode);
+
+    // Visible in History and the summary
+    let events = cubesat.history.events();
+    assert!(events.contains(&(
+        1.0,
+        "Fault 'Panel' injected, solar panel 'Solar panel' failed".to_string()
+    )));
+    assert!(events.contains(&(
+        3.0,
+        "Fault 'Ghost' not injected, unknown solar panel 'Missing'".to_string()
+    )));
+    assert_eq!(
+        cubesat.history.column("injected faults").unwrap(),
+        &vec![0.0, 2.0, 5.0, 5.0]
+    );
+    cubesat.summarize();
+    let summary = cubesat.history.summary();
+    assert!(summary.contains(&("fault Gauge".to_string(), "injected at 2.0 s".to_string())));
+    assert!(summary.contains(&(
+        "fault Ghost".to_string(),
+        "failed, unknown solar panel 'Missing'".to_string()
+    )));
+}
+
+#[test]
+fn fault_at_event() {
+    // Waits for the next ascending node after the earliest time
+    let mut fault = Fault::new(
+        "Panel",
+        FaultKind::PanelFailure,
+        10.0,
+        Some("Solar panel"),
+        None,
+    );
+    fault.event = Some(maneuver::OrbitEvent::AscendingNode);
+    assert!(!fault.due(0.0, 1.0, (0.0, -1.0)));
+    assert!(!fault.due(5.0, 1.0, (0.0, 1.0)));
+    assert!(!fault.due(10.0, 1.0, (0.0, -1.0)));
+    assert!(fault.due(11.0, 1.0, (0.0, 1.0)));
+}
+
+#[test]
+fn fdir_mode_holds_against_schedule() {
+    // Always-on payload by schedule, a low battery sends it to IDLE
+    let idle = component::OperatingMode::new(component::Mode::Idle, -0.5);
+    let active = component::OperatingMode::new(component::Mode::Active, -2.0);
+    let payload = component::Component::new("Payload", -1.0, Some(-2.0), Some(10.0), Some(10.0))
+        .with_mode(idle)
+        .with_mode(active);
+    let rule = Rule::new(
+        "Low battery",
+        "battery",
+        Some(30.0),
+        None,
+        vec![Action::new("Payload", CommandKind::Mode, vec!["IDLE"])],
+    );
+    let mut cubesat = cubesat::CubeSat::new()
+        .with_time(0.0, 10.0, 1.0)
+        .with_eps(0.0, 10.0);
+    cubesat.components = Some(vec![payload]);
+    cubesat.fdir_rules = Some(vec![rule]);
+    cubesat.eps.as_mut().unwrap().charge = 2.0;
+
+    let mut modes = Vec::new();
+    for step in 0..4 {
+        if step == 3 {
+            cubesat.eps.as_mut().unwrap().charge = 8.0;
+        }
+        let now = cubesat.time.as_ref().unwrap().now;
+        update_fdir(&mut cubesat).unwrap();
+        cubesat.update_active_components(now, cubesat.safe_mode);
+        cubesat.update_modes();
+        let payload = &cubesat.components.as_ref().unwrap()[0];
+        modes.push(payload.current_mode().unwrap().mode);
+        cubesat.iterate();
+    }
+
+    // Held in IDLE over the next steps, back to the schedule once the rule clears
+    let (idle, active) = (component::Mode::Idle, component::Mode::Active);
+    assert_eq!(modes, vec![idle, idle, idle, active]);
+    assert!(cubesat.history.events().contains(&(
+        3.0,
+        "FDIR rule 'Low battery' released 'Payload'".to_string()
+    )));
+}
+
+#[test]
+fn fdir_unknown_quantity() {
+    // Nothing is recorded yet on the first step, a missing column afterwards is a typo
+    let rule = Rule::new("Hot", "Batery temperature", None, Some(45.0), Vec::new());
+    let mut cubesat = cubesat::CubeSat::new().with_time(0.0, 10.0, 1.0);
+    cubesat.fdir_rules = Some(vec![rule]);
+    assert_eq!(update_fdir(&mut cubesat), Ok(()));
+    cubesat.save_history();
+    assert!(matches!(
+        update_fdir(&mut cubesat),
+        Err(CubeSatError::InvalidParameter(_))
+    ));
+}
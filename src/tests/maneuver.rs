@@ -0,0 +1,331 @@
+#[allow(unused_imports)]
+use crate::maneuver::*;
+
+#[allow(unused_imports)]
+use crate::error::CubeSatError;
+
+#[allow(unused)]
+fn leo() -> cubesat::CubeSat {
+    cubesat::CubeSat::new()
+        .with_orbit_type("numerical")
+        .with_orbit_parameters(vec![
+            ("semi-major axis", orbit::RADIUS_EARTH + 500_000.0),
+            ("inclination", 0.0),
+            ("argument of periapsis", 0.0),
+            ("longitude of ascending node", 0.0),
+        ])
+        .with_position(0.0, 0.0, 0.0)
+        .with_velocity(0.0, 0.0, 0.0)
+        .with_acceleration(0.0, 0.0, 0.0)
+        .with_time(0.0, 6000.0, 10.0)
+        .with_mass(4.0)
+        .with_propellant(0.5)
+}
+
+#[test]
+fn frames() {
+    let pos = vector::Vector3::new(7_000_000.0, 0.0, 0.0);
+    let vel = vector::Vector3::new(0.0, 7_500.0, 0.0);
+    let unit_x = vector::Vector3::new(1.0, 0.0, 0.0);
+    let unit_y = vector::Vector3::new(0.0, 1.0, 0.0);
+    let unit_z = vector::Vector3::new(0.0, 0.0, 1.0);
+    let close = |a: vector::Vector3, b: vector::Vector3| a.sub(&b).abs() < 1e-12;
+
+    assert_eq!(
+        ManeuverFrame::Inertial.to_inertial(&unit_x, &pos, &vel),
+        unit_x
+    );
+    // VNB: x along the velocity, y along the orbit normal
+    assert!(close(
+        ManeuverFrame::Vnb.to_inertial(&unit_x, &pos, &vel),
+        unit_y
+    ));
+    assert!(close(
+        ManeuverFrame::Vnb.to_inertial(&unit_y, &pos, &vel),
+        unit_z
+    ));
+    assert!(close(
+        ManeuverFrame::Vnb.to_inertial(&unit_z, &pos, &vel),
+        unit_x
+    ));
+    // RTN: x along the position, y along the velocity, z along the orbit normal
+    assert!(close(
+        ManeuverFrame::Rtn.to_inertial(&unit_x, &pos, &vel),
+        unit_x
+    ));
+    assert!(close(
+        ManeuverFrame::Rtn.to_inertial(&unit_y, &pos, &vel),
+        unit_y
+    ));
+    assert!(close(
+        ManeuverFrame::Rtn.to_inertial(&unit_z, &pos, &vel),
+        unit_z
+    ));
+}
+
+#[test]
+fn events() {
+    assert!(OrbitEvent::Apoapsis.crossed((1.0, 0.0), (-1.0, 0.0)));
+    assert!(!OrbitEvent::Apoapsis.crossed((-1.0, 0.0), (1.0, 0.0)));
+    assert!(OrbitEvent::Periapsis.crossed((-1.0, 0.0), (1.0, 0.0)));
+    assert!(OrbitEvent::AscendingNode.crossed((0.0, -1.0), (0.0, 1.0)));
+    assert!(OrbitEvent::DescendingNode.crossed((0.0, 1.0), (0.0, -1.0)));
+    assert!(!OrbitEvent::DescendingNode.crossed((0.0, -1.0), (0.0, 1.0)));
+}
+
+#[test]
+fn impulsive() {
+    let mut maneuver = Maneuver::impulsive("Raise", 100.0, ManeuverFrame::Vnb, (10.0, 0.0, 0.0));
+    maneuver.isp = Some(220.0);
+    maneuver.consumption = -5.0;
+    let mut cubesat = leo().with_maneuver(maneuver);
+
+    // Before the maneuver
+    cubesat.update_orbit().unwrap();
+    update_maneuvers(&mut cubesat).unwrap();
+    let speed = cubesat.vel.unwrap().abs();
+    for _ in 0..10 {
+        cubesat.iterate();
+        cubesat.update_orbit().unwrap();
+        update_maneuvers(&mut cubesat).unwrap();
+    }
+
+    // At t = 100 s the speed increased by 10 m/s and propellant was used
+    assert!((cubesat.vel.unwrap().abs() - speed - 10.0).abs() < 0.01);
+    let used = 4.0 * (1.0 - (-10.0 / (220.0 * G0)).exp());
+    assert!((cubesat.propellant.unwrap() - (0.5 - used)).abs() < 1e-12);
+    assert!((cubesat.mass.unwrap() - (4.0 - used)).abs() < 1e-12);
+    assert_eq!(cubesat.get_power_consumption(), -5.0);
+
+    // Only once, and the thruster power is off again
+    cubesat.iterate();
+    cubesat.update_orbit().unwrap();
+    update_maneuvers(&mut cubesat).unwrap();
+    assert_eq!(cubesat.get_power_consumption(), 0.0);
+    assert_eq!(cubesat.history.events().len(), 1);
+}
+
+#[test]
+fn impulsive_refused() {
+    let mut maneuver = Maneuver::impulsive("Raise", 0.0, ManeuverFrame::Vnb, (100.0, 0.0, 0.0));
+    maneuver.isp = Some(220.0);
+    let mut cubesat = leo().with_propellant(0.01).with_maneuver(maneuver);
+    cubesat.update_orbit().unwrap();
+    let vel = cubesat.vel.unwrap();
+    update_maneuvers(&mut cubesat).unwrap();
+    assert_eq!(cubesat.vel.unwrap(), vel);
+    assert_eq!(cubesat.propellant, Some(0.01));
+    assert!(cubesat.history.events()[0].1.contains("refused"));
+}
+
+#[test]
+fn impulsive_at_event() {
+    let mut maneuver = Maneuver::impulsive("Apoapsis", 0.0, ManeuverFrame::Vnb, (5.0, 0.0, 0.0));
+    maneuver.event = Some(OrbitEvent::Apoapsis);
+    maneuver.isp = Some(220.0);
+    let mut cubesat = leo()
+        .with_orbit_parameters(vec![
+            ("semi-major axis", orbit::RADIUS_EARTH + 1_000_000.0),
+            ("inclination", 0.0),
+            ("argument of periapsis", 0.0),
+            ("longitude of ascending node", 0.0),
+            ("eccentricity", 0.05),
+        ])
+        .with_maneuver(maneuver);
+    let period = 2.0
+        * std::f64::consts::PI
+        * ((orbit::RADIUS_EARTH + 1_000_000.0).powi(3) / orbit::CONST_MU).sqrt();
+
+    cubesat.update_orbit().unwrap();
+    update_maneuvers(&mut cubesat).unwrap();
+    while !cubesat.maneuvers.as_ref().unwrap()[0].executed {
+        cubesat.iterate();
+        cubesat.update_orbit().unwrap();
+        update_maneuvers(&mut cubesat).unwrap();
+    }
+
+    // Half an orbit after periapsis
+    let now = cubesat.time.unwrap().now;
+    assert!((now - period / 2.0).abs() <= 10.0);
+}
+
+#[test]
+fn finite() {
+    let mut maneuver = Maneuver::finite(
+        "Burn",
+        0.0,
+        ManeuverFrame::Vnb,
+        (1.0, 0.0, 0.0),
+        0.1,
+        100.0,
+        220.0,
+    );
+    maneuver.consumption = -10.0;
+    let mut cubesat = leo().with_maneuver(maneuver);
+
+    cubesat.update_orbit().unwrap();
+    let speed = cubesat.vel.unwrap().abs();
+    update_maneuvers(&mut cubesat).unwrap();
+    assert_eq!(cubesat.get_power_consumption(), -10.0);
+    for _ in 0..10 {
+        cubesat.iterate();
+        cubesat.update_orbit().unwrap();
+        update_maneuvers(&mut cubesat).unwrap();
+    }
+
+    // dv ~ F * t / m = 2.5 m/s, propellant = F / (Isp * g0) * t
+    let maneuver = &cubesat.maneuvers.as_ref().unwrap()[0];
+    assert!(maneuver.executed);
+    assert!(!maneuver.firing);
+    assert!((cubesat.vel.unwrap().abs() - speed - 2.5).abs() < 0.01);
+    let used = 0.1 / (220.0 * G0) * 100.0;
+    assert!((cubesat.propellant.unwrap() - (0.5 - used)).abs() < 1e-12);
+    assert_eq!(cubesat.get_power_consumption(), 0.0);
+    assert_eq!(cubesat.history.events().len(), 2);
+}
+
+#[test]
+fn analytic_orbit_ignored() {
+    let maneuver = Maneuver::impulsive("Raise", 0.0, ManeuverFrame::Vnb, (10.0, 0.0, 0.0));
+    let mut cubesat = leo()
+        .with_orbit_type("circular cosine")
+        .with_maneuver(maneuver);
+    cubesat.update_orbit().unwrap();
+    let vel = cubesat.vel.unwrap();
+    update_maneuvers(&mut cubesat).unwrap();
+    assert_eq!(cubesat.vel.unwrap(), vel);
+    assert!(cubesat.history.events()[0].1.contains("ignored"));
+}
+
+#[test]
+fn propulsion_supplies_propellant() {
+    let maneuver = Maneuver::impulsive("Raise", 0.0, ManeuverFrame::Vnb, (10.0, 0.0, 0.0));
+    let mut cubesat = leo()
+        .with_propulsion(0.2, 0.3, 0.5, 0.1, 220.0)
+        .with_maneuver(maneuver);
+    cubesat.update_orbit().unwrap();
+    update_maneuvers(&mut cubesat).unwrap();
+
+    // Isp is taken from the thruster, the budget from the tank
+    let used = 4.0 * (1.0 - (-10.0 / (220.0 * G0)).exp());
+    let propulsion = cubesat.propulsion.as_ref().unwrap();
+    assert!((propulsion.propellant - (0.5 - used)).abs() < 1e-12);
+    assert!(propulsion.valve_open);
+    assert_eq!(cubesat.propellant, Some(0.5));
+    cubesat.save_history();
+    assert!(cubesat.history.column("delta-v remaining").unwrap()[0] > 0.0);
+}
+
+#[test]
+fn propulsion_warm_up() {
+    let maneuver = Maneuver::impulsive("Raise", 100.0, ManeuverFrame::Vnb, (1.0, 0.0, 0.0));
+    let mut cubesat = leo()
+        .with_propulsion(0.2, 0.3, 0.5, 0.1, 220.0)
+        .with_maneuver(maneuver);
+    cubesat.propulsion.as_mut().unwrap().warm_up = 300.0;
+    cubesat.propulsion.as_mut().unwrap().consumption_heater = -2.0;
+
+    // The heater starts at once and the burn waits for it
+    cubesat.update_orbit().unwrap();
+    update_maneuvers(&mut cubesat).unwrap();
+    assert_eq!(cubesat.get_power_consumption(), -2.0);
+    while !cubesat.maneuvers.as_ref().unwrap()[0].executed {
+        cubesat.iterate();
+        cubesat.update_orbit().unwrap();
+        update_maneuvers(&mut cubesat).unwrap();
+    }
+    assert_eq!(cubesat.time.unwrap().now, 300.0);
+
+    // Heater off after the burn
+    cubesat.iterate();
+    cubesat.update_orbit().unwrap();
+    update_maneuvers(&mut cubesat).unwrap();
+    assert_eq!(cubesat.get_power_consumption(), 0.0);
+}
+
+#[test]
+fn safe_mode_deferred() {
+    let maneuver = Maneuver::impulsive("Raise", 0.0, ManeuverFrame::Vnb, (10.0, 0.0, 0.0));
+    let mut cubesat = leo()
+        .with_propulsion(0.2, 0.3, 0.5, 0.1, 220.0)
+        .with_maneuver(maneuver);
+    cubesat.safe_mode = true;
+    cubesat.update_orbit().unwrap();
+    let vel = cubesat.vel.unwrap();
+    update_maneuvers(&mut cubesat).unwrap();
+    assert_eq!(cubesat.vel.unwrap(), vel);
+    assert_eq!(cubesat.propulsion.as_ref().unwrap().propellant, 0.5);
+    assert!(cubesat.history.events()[0]
+        .1
+        .contains("deferred, safe mode"));
+    assert!(!cubesat.maneuvers.as_ref().unwrap()[0].executed);
+
+    // Logged once while waiting
+    cubesat.iterate();
+    cubesat.update_orbit().unwrap();
+    update_maneuvers(&mut cubesat).unwrap();
+    assert_eq!(cubesat.history.events().len(), 1);
+
+    // Fires once safe mode ends
+    cubesat.safe_mode = false;
+    cubesat.iterate();
+    cubesat.update_orbit().unwrap();
+    update_maneuvers(&mut cubesat).unwrap();
+    assert!(cubesat.maneuvers.as_ref().unwrap()[0].executed);
+    assert!(cubesat.propulsion.as_ref().unwrap().propellant < 0.5);
+}
+
+#[test]
+fn safe_mode_stops_finite() {
+    // The rest of the burn fires after safe mode
+    let maneuver = Maneuver::finite(
+        "Push",
+        0.0,
+        ManeuverFrame::Vnb,
+        (1.0, 0.0, 0.0),
+        0.1,
+        100.0,
+        220.0,
+    );
+    let mut cubesat = leo().with_maneuver(maneuver);
+    for _ in 0..3 {
+        cubesat.update_orbit().unwrap();
+        update_maneuvers(&mut cubesat).unwrap();
+        cubesat.iterate();
+    }
+    cubesat.safe_mode = true;
+    cubesat.update_orbit().unwrap();
+    update_maneuvers(&mut cubesat).unwrap();
+    let m = &cubesat.maneuvers.as_ref().unwrap()[0];
+    assert!(!m.firing && !m.executed);
+    assert_eq!(m.duration, Some(70.0));
+    cubesat.iterate();
+
+    cubesat.safe_mode = false;
+    cubesat.update_orbit().unwrap();
+    update_maneuvers(&mut cubesat).unwrap();
+    assert!(cubesat.maneuvers.as_ref().unwrap()[0].firing);
+}
+
+#[test]
+fn impulsive_config_errors() {
+    // No Isp: the burn would be free
+    let maneuver = Maneuver::impulsive("Raise", 0.0, ManeuverFrame::Vnb, (10.0, 0.0, 0.0));
+    let mut cubesat = leo().with_maneuver(maneuver.clone());
+    cubesat.update_orbit().unwrap();
+    assert_eq!(
+        update_maneuvers(&mut cubesat),
+        Err(CubeSatError::missing("Isp for maneuver 'Raise'"))
+    );
+    assert!(!cubesat.maneuvers.as_ref().unwrap()[0].executed);
+
+    // No mass
+    let mut cubesat = leo().with_maneuver(maneuver);
+    cubesat.mass = None;
+    cubesat.maneuvers.as_mut().unwrap()[0].isp = Some(220.0);
+    cubesat.update_orbit().unwrap();
+    assert_eq!(
+        update_maneuvers(&mut cubesat),
+        Err(CubeSatError::missing("mass"))
+    );
+}
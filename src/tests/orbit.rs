@@ -1,6 +1,9 @@
 #[allow(unused_imports)]
 use crate::orbit::*;
 
+#[allow(unused_imports)]
+use crate::error::CubeSatError;
+
 #[test]
 fn five_hundred_kilometers() {
     let mut five_hundred = OrbitParameters::new();
@@ -25,7 +28,7 @@ fn orbit_circular_cosine() {
         .with_time(0.0, 1.0, 1.0);
 
     // cubesat.update_orbit();
-    super::orbit_circular_cosine(&mut cubesat);
+    super::orbit_circular_cosine(&mut cubesat).unwrap();
     let pos = cubesat.pos.unwrap();
     let vel = cubesat.vel.unwrap();
     let acc = cubesat.acc.unwrap();
@@ -57,7 +60,7 @@ fn orbit_circular_cosine() {
         .with_time(0.0, 1.0, 1.0);
 
     // cubesat.update_orbit();
-    super::orbit_circular_cosine(&mut cubesat);
+    super::orbit_circular_cosine(&mut cubesat).unwrap();
     let pos = cubesat.pos.unwrap();
     let vel = cubesat.vel.unwrap();
     let acc = cubesat.acc.unwrap();
@@ -93,7 +96,7 @@ fn orbit_parametric() {
         .with_time(0.0, 1.0, 1.0);
 
     // cubesat.update_orbit();
-    super::orbit_parametric(&mut cubesat);
+    super::orbit_parametric(&mut cubesat).unwrap();
     let pos = cubesat.pos.unwrap();
     let vel = cubesat.vel.unwrap();
     let acc = cubesat.acc.unwrap();
@@ -126,7 +129,7 @@ fn orbit_parametric() {
         .with_time(1.0, 1.0, 1.0);
 
     // cubesat.update_orbit();
-    super::orbit_parametric(&mut cubesat);
+    super::orbit_parametric(&mut cubesat).unwrap();
     let pos = cubesat.pos.unwrap();
     let vel = cubesat.vel.unwrap();
     let acc = cubesat.acc.unwrap();
@@ -143,3 +146,599 @@ fn orbit_parametric() {
     assert!(acc.y == 0.0);
     assert!(acc.z == 0.0);
 }
+
+#[test]
+fn atmospheric_density() {
+    // Sea level and band boundaries
+    assert_eq!(super::atmospheric_density(0.0), 1.225);
+    assert_eq!(super::atmospheric_density(500_000.0), 6.967e-13);
+
+    // Decreasing with altitude
+    let low = super::atmospheric_density(400_000.0);
+    let mid = super::atmospheric_density(425_000.0);
+    let high = super::atmospheric_density(450_000.0);
+    assert!(low > mid && mid > high);
+    assert!(super::atmospheric_density(2_000_000.0) < 3.019e-15);
+}
+
+#[test]
+fn state_from_parameters() {
+    // Same state as the parametric orbit
+    let mut cubesat = cubesat::CubeSat::new()
+        .with_orbit_type("parametric")
+        .with_orbit_parameters(vec![
+            ("semi-major axis", 7_000_000.0),
+            ("inclination", 20.0),
+            ("argument of periapsis", 25.0),
+            ("longitude of ascending node", 15.0),
+            ("eccentricity", 0.1),
+        ])
+        .with_position(0.0, 0.0, 0.0)
+        .with_velocity(0.0, 0.0, 0.0)
+        .with_time(0.0, 1.0, 1.0);
+    super::orbit_parametric(&mut cubesat).unwrap();
+    let (pos, vel) =
+        super::state_from_parameters(cubesat.orbit_parameters.as_ref().unwrap(), 0.0).unwrap();
+    // Parametric orbit uses a coarse bisection for E
+    assert!(pos.sub(&cubesat.pos.unwrap()).abs() < 1_000.0);
+    assert!(vel.sub(&cubesat.vel.unwrap()).abs() < 1.0);
+
+    // Circular orbit without eccentricity
+    let params = cubesat::CubeSat::new()
+        .with_orbit_parameters(vec![
+            ("semi-major axis", 7_000_000.0),
+            ("inclination", 0.0),
+            ("argument of periapsis", 0.0),
+            ("longitude of ascending node", 0.0),
+        ])
+        .orbit_parameters
+        .unwrap();
+    let (pos, vel) = super::state_from_parameters(&params, 1234.0).unwrap();
+    assert!((pos.abs() - 7_000_000.0).abs() < 1e-6);
+    assert!((vel.abs() - (CONST_MU / 7_000_000.0).sqrt()).abs() < 1e-9);
+}
+
+#[test]
+fn orbit_numerical() {
+    let semi = RADIUS_EARTH + 500_000.0;
+    let mut cubesat = cubesat::CubeSat::new()
+        .with_orbit_type("numerical")
+        .with_orbit_parameters(vec![
+            ("semi-major axis", semi),
+            ("inclination", 45.0),
+            ("argument of periapsis", 0.0),
+            ("longitude of ascending node", 0.0),
+        ])
+        .with_position(0.0, 0.0, 0.0)
+        .with_velocity(0.0, 0.0, 0.0)
+        .with_acceleration(0.0, 0.0, 0.0)
+        .with_time(0.0, 6000.0, 10.0);
+
+    // Initialized from the orbit parameters
+    cubesat.update_orbit().unwrap();
+    assert!((cubesat.pos.unwrap().abs() - semi).abs() < 1e-6);
+    assert!((cubesat.acc.unwrap().abs() - CONST_MU / semi.powi(2)).abs() < 1e-9);
+
+    // Radius is kept over one orbit
+    for _ in 0..600 {
+        cubesat.iterate();
+        cubesat.update_orbit().unwrap();
+    }
+    assert!((cubesat.pos.unwrap().abs() - semi).abs() < 1.0);
+}
+
+#[test]
+fn solar_radiation_pressure() {
+    let radius = RADIUS_EARTH + 500_000.0;
+    let cubesat = cubesat::CubeSat::new()
+        .with_sun(-1.0, 0.0, 0.0)
+        .with_rotation(0.0, 0.0, 0.0)
+        .with_mass(1.0)
+        .with_geometry(vec![((1.0, 0.0, 0.0), 0.01)])
+        .with_solar_radiation_pressure(SrpModel::Cannonball, 0.01, 1.5);
+    let srp = cubesat.solar_radiation_pressure.as_ref().unwrap();
+
+    // Cannonball: a = P * Cr * A / m along the sunlight
+    let sunlit = vector::Vector3::new(radius, 0.0, 0.0);
+    let acc = srp.acceleration(&cubesat, &sunlit).unwrap();
+    assert!((acc.x + SOLAR_PRESSURE * 1.5 * 0.01).abs() < 1e-15);
+    assert_eq!(acc.y, 0.0);
+
+    // None in eclipse
+    let eclipse = vector::Vector3::new(-radius, 0.0, 0.0);
+    assert_eq!(
+        srp.acceleration(&cubesat, &eclipse),
+        Ok(vector::Vector3::origin())
+    );
+
+    // Faces: single face towards the sun
+    let faces = SolarRadiationPressure {
+        model: SrpModel::Faces,
+        area: 0.0,
+        reflectivity: 0.0,
+    };
+    let acc = faces.acceleration(&cubesat, &sunlit).unwrap();
+    assert!((acc.x + SOLAR_PRESSURE * 0.01 * 1.3).abs() < 1e-15);
+}
+
+#[test]
+fn sun_and_moon_position() {
+    // Early January: sun close to perihelion, at ecliptic longitude ~280 deg
+    let sun = sun_position(time::J2000);
+    assert!(1.47e11 < sun.abs() && sun.abs() < 1.48e11);
+    assert!(sun.x > 0.0 && sun.y < 0.0 && sun.z < 0.0);
+
+    // Half a year later: sun on the other side
+    let sun_later = sun_position(time::J2000 + 182.6);
+    assert!(sun.unit().dot(&sun_later.unit()) < -0.99);
+
+    // Moon distance stays within perigee and apogee over a month
+    for day in 0..30 {
+        let moon = moon_position(time::J2000 + day as f64);
+        assert!(3.5e8 < moon.abs() && moon.abs() < 4.1e8);
+        // Latitude limited by the inclination of the lunar orbit (~5 deg) and the obliquity
+        assert!((moon.z / moon.abs()).abs() < (29.0_f64.to_radians()).sin());
+    }
+}
+
+#[test]
+fn third_body() {
+    // No acceleration at the center of the Earth
+    let origin = vector::Vector3::origin();
+    let both = ThirdBody {
+        sun: true,
+        moon: true,
+    };
+    assert!(both.acceleration(&origin, time::J2000).abs() < 1e-20);
+
+    // Tidal acceleration in LEO: sun ~5e-7 m/s^2, moon ~1e-6 m/s^2
+    let sun = sun_position(time::J2000);
+    let pos = sun.unit().scale(RADIUS_EARTH + 500_000.0);
+    let sun_only = ThirdBody {
+        sun: true,
+        moon: false,
+    };
+    let acc = sun_only.acceleration(&pos, time::J2000);
+    assert!(4e-7 < acc.abs() && acc.abs() < 7e-7);
+    assert!(acc.dot(&sun) > 0.0);
+
+    let moon = moon_position(time::J2000);
+    let pos = moon.unit().scale(RADIUS_EARTH + 500_000.0);
+    let moon_only = ThirdBody {
+        sun: false,
+        moon: true,
+    };
+    let acc = moon_only.acceleration(&pos, time::J2000);
+    assert!(8e-7 < acc.abs() && acc.abs() < 1.6e-6);
+
+    // Disabled
+    let none = ThirdBody {
+        sun: false,
+        moon: false,
+    };
+    assert_eq!(none.acceleration(&pos, time::J2000), origin);
+}
+
+#[test]
+fn gravity_field_file() {
+    let parameters = GravityFieldParameters {
+        path: "src/tests/gravity.txt".to_string(),
+        degree: 3,
+        order: 2,
+        gm: 3.986_004_415e14,
+        radius: 6_378_136.3,
+    };
+    let field = GravityField::from_file(&parameters).unwrap();
+    assert_eq!(field.degree, 3);
+    assert_eq!(field.order, 2);
+    assert_eq!(field.c[2][0], -0.484165371736e-3);
+    assert_eq!(field.s[2][2], -0.140016683654e-5);
+    assert_eq!(field.c[3][2], 0.904627768605e-6);
+    // Truncated
+    assert_eq!(field.c[3].len(), 4);
+    assert_eq!(field.c[3][3], 0.0);
+}
+
+#[test]
+fn gravity_field_j2() {
+    let parameters = GravityFieldParameters {
+        path: String::new(),
+        degree: 2,
+        order: 0,
+        gm: CONST_MU,
+        radius: RADIUS_EARTH,
+    };
+    let mut field = GravityField::new(&parameters);
+    let c20 = -0.484165371736e-3;
+    field.set_coefficient(2, 0, c20, 0.0);
+
+    // Analytic J2 acceleration
+    let j2 = -c20 * 5.0_f64.sqrt();
+    let pos = vector::Vector3::new(4_000_000.0, 3_000_000.0, 5_000_000.0);
+    let r = pos.abs();
+    let factor = -1.5 * j2 * CONST_MU * RADIUS_EARTH.powi(2) / r.powi(5);
+    let zr = 5.0 * (pos.z / r).powi(2);
+    let expected = vector::Vector3::new(
+        factor * pos.x * (1.0 - zr),
+        factor * pos.y * (1.0 - zr),
+        factor * pos.z * (3.0 - zr),
+    );
+
+    let acc = field.acceleration(&pos, time::J2000 + 0.3);
+    assert!(acc.sub(&expected).abs() < 1e-12 * expected.abs().max(1.0));
+}
+
+#[test]
+fn gravity_field_tesseral() {
+    let parameters = GravityFieldParameters {
+        path: String::new(),
+        degree: 3,
+        order: 3,
+        gm: CONST_MU,
+        radius: RADIUS_EARTH,
+    };
+    let mut field = GravityField::new(&parameters);
+    let (c22, s22, c31) = (0.243914352398e-5, -0.140016683654e-5, 0.202998882184e-5);
+    field.set_coefficient(2, 2, c22, s22);
+    field.set_coefficient(3, 1, c31, 0.0);
+    let julian_date = time::J2000 + 0.7;
+
+    // Potential in Earth-fixed coordinates
+    let potential = |p: &vector::Vector3| {
+        let p = p.rot_z(-earth_rotation_angle(julian_date));
+        let r = p.abs();
+        let (x, y, z) = (p.x, p.y, p.z);
+        // P22 = 3 * cos^2(lat), P31 = 3/2 * (5 * sin^2(lat) - 1) * cos(lat)
+        let n22 = (5.0_f64 / 12.0).sqrt();
+        let n31 = (2.0 * 7.0 * 2.0 / 24.0_f64).sqrt();
+        let u22 = n22 * 3.0 * (c22 * (x * x - y * y) + s22 * 2.0 * x * y) / r.powi(2);
+        let u31 = n31 * 1.5 * (5.0 * z * z / (r * r) - 1.0) * c31 * x / r;
+        CONST_MU / r * ((RADIUS_EARTH / r).powi(2) * u22 + (RADIUS_EARTH / r).powi(3) * u31)
+    };
+
+    // Gradient by central differences
+    let pos = vector::Vector3::new(4_000_000.0, -3_000_000.0, 5_000_000.0);
+    let h = 1.0;
+    let dx = vector::Vector3::new(h, 0.0, 0.0);
+    let dy = vector::Vector3::new(0.0, h, 0.0);
+    let dz = vector::Vector3::new(0.0, 0.0, h);
+    let expected = vector::Vector3::new(
+        (potential(&pos.add(&dx)) - potential(&pos.sub(&dx))) / (2.0 * h),
+        (potential(&pos.add(&dy)) - potential(&pos.sub(&dy))) / (2.0 * h),
+        (potential(&pos.add(&dz)) - potential(&pos.sub(&dz))) / (2.0 * h),
+    );
+
+    let acc = field.acceleration(&pos, julian_date);
+    assert!(acc.sub(&expected).abs() < 1e-6 * expected.abs());
+}
+
+#[test]
+fn gravity_field_shared() {
+    let toml = r#"
+        [gravity_field]
+        path = "src/tests/gravity.txt"
+        degree = 4
+        order = 4
+    "#;
+    let mut first: cubesat::CubeSat = toml::from_str(toml).unwrap();
+    let mut second: cubesat::CubeSat = toml::from_str(toml).unwrap();
+    let mut cache = Vec::new();
+    first.load_gravity_field(&mut cache).unwrap();
+    second.load_gravity_field(&mut cache).unwrap();
+    assert_eq!(cache.len(), 1);
+    assert!(std::sync::Arc::ptr_eq(
+        first.gravity_model.as_ref().unwrap(),
+        second.gravity_model.as_ref().unwrap()
+    ));
+
+    // Other constants for the same file are a separate field
+    let mut third: cubesat::CubeSat = toml::from_str(toml).unwrap();
+    third.gravity_field.as_mut().unwrap().radius = 6_378_137.0;
+    third.load_gravity_field(&mut cache).unwrap();
+    assert_eq!(cache.len(), 2);
+    assert_eq!(third.gravity_model.as_ref().unwrap().radius, 6_378_137.0);
+}
+
+#[allow(unused)]
+fn circular(radius: f64, inclination: f64) -> OrbitParameters {
+    let mut parameters = OrbitParameters::new();
+    parameters.set_semi_major_axis(radius);
+    parameters.set_inclination(inclination);
+    parameters.set_argument_of_periapsis(0.0);
+    parameters.set_longitude_of_ascending_node(0.0);
+    parameters
+}
+
+#[test]
+fn hohmann_transfer() {
+    // LEO to GEO: 3.89 km/s in 5.27 h
+    let transfer = hohmann(&circular(6_678_000.0, 0.0), &circular(42_164_000.0, 0.0)).unwrap();
+    assert_eq!(transfer.burns.len(), 2);
+    assert!((transfer.delta_v - 3_893.0).abs() < 10.0);
+    assert!((transfer.duration / 3600.0 - 5.27).abs() < 0.01);
+    assert_eq!(transfer.burns[1].time, transfer.duration);
+
+    // Lowering burns retrograde
+    let lowering = hohmann(&circular(42_164_000.0, 0.0), &circular(6_678_000.0, 0.0)).unwrap();
+    assert!(lowering.burns[0].delta_v.x < 0.0);
+    assert!((lowering.delta_v - transfer.delta_v).abs() < 1e-6);
+
+    // The target needs a radius
+    assert!(hohmann(&circular(6_678_000.0, 0.0), &OrbitParameters::new()).is_err());
+}
+
+#[test]
+fn bi_elliptic_transfer() {
+    // Cheaper than Hohmann for a radius ratio above 15.58 and a high apoapsis
+    let current = circular(7_000_000.0, 0.0);
+    let target = circular(20.0 * 7_000_000.0, 0.0);
+    let direct = hohmann(&current, &target).unwrap();
+    let transfer = bi_elliptic(&current, &target, 60.0 * 7_000_000.0).unwrap();
+    assert_eq!(transfer.burns.len(), 3);
+    assert!(transfer.delta_v < direct.delta_v);
+    assert!(transfer.duration > direct.duration);
+    assert!(transfer.burns[2].delta_v.x < 0.0);
+    assert!(bi_elliptic(&current, &target, 7_000_000.0).is_err());
+}
+
+#[test]
+fn plane_change_transfer() {
+    let v = (CONST_MU / 7_000_000.0).sqrt();
+    let check = |current: &OrbitParameters, target: &OrbitParameters, theta: f64| {
+        let transfer = plane_change(current, target, 100.0).unwrap();
+        let expected = 2.0 * v * (theta / 2.0).to_radians().sin();
+        assert!((transfer.delta_v - expected).abs() < 1e-6);
+
+        // The burn lies on the target plane and leaves the velocity in it at the same speed
+        let burn = &transfer.burns[0];
+        let (pos, vel) = crate::orbit::state_from_parameters(current, 100.0 + burn.time).unwrap();
+        let normal = orbit_normal(target).unwrap();
+        assert!(pos.unit().dot(&normal).abs() < 1e-9);
+        let dv = crate::maneuver::ManeuverFrame::Vnb.to_inertial(&burn.delta_v, &pos, &vel);
+        let v_new = vel.add(&dv);
+        assert!((v_new.abs() - v).abs() < 1e-6);
+        assert!(v_new.unit().dot(&normal).abs() < 1e-9);
+    };
+
+    // Inclination only
+    check(
+        &circular(7_000_000.0, 0.0),
+        &circular(7_000_000.0, 10.0),
+        10.0,
+    );
+
+    // Same inclination, the ascending node moves by 90 degrees: cos(theta) = cos^2(45)
+    let mut target = circular(7_000_000.0, 45.0);
+    target.set_longitude_of_ascending_node(90.0);
+    check(&circular(7_000_000.0, 45.0), &target, 60.0);
+
+    // Nothing to do in the same plane
+    let transfer = plane_change(
+        &circular(7_000_000.0, 45.0),
+        &circular(7_000_000.0, 45.0),
+        0.0,
+    );
+    assert!(transfer.unwrap().burns.is_empty());
+}
+
+#[test]
+fn phasing_transfer() {
+    let current = circular(7_000_000.0, 0.0);
+    let period = 2.0 * std::f64::consts::PI * (7_000_000.0_f64.powi(3) / CONST_MU).sqrt();
+
+    // Catching up with a target 30 degrees ahead in 2 revolutions needs a lower, faster orbit
+    let mut target = circular(7_000_000.0, 0.0);
+    target.set_argument_of_periapsis(30.0);
+    let transfer = phasing(&current, &target, 2, 0.0).unwrap();
+    assert!(transfer.burns[0].delta_v.x < 0.0);
+    assert_eq!(transfer.burns[1].delta_v.x, -transfer.burns[0].delta_v.x);
+    assert!((transfer.duration - (2.0 * period - period * 30.0 / 360.0)).abs() < 1e-6);
+
+    // Falling behind a target 30 degrees behind raises the orbit
+    target.set_argument_of_periapsis(-30.0);
+    let transfer = phasing(&current, &target, 2, 0.0).unwrap();
+    assert!(transfer.burns[0].delta_v.x > 0.0);
+    assert!(phasing(&current, &target, 0, 0.0).is_err());
+}
+
+#[test]
+fn transfer_schedule() {
+    let current = circular(RADIUS_EARTH + 500_000.0, 0.0);
+    let target = circular(RADIUS_EARTH + 800_000.0, 0.0);
+    let transfer = hohmann(&current, &target).unwrap();
+
+    // Same schedule from the API and from TOML
+    let maneuvers = transfer.to_maneuvers("Hohmann", 0.0);
+    assert_eq!(maneuvers.len(), 2);
+    assert_eq!(maneuvers[0].name, "Hohmann 1");
+    assert_eq!(maneuvers[1].time, transfer.duration);
+    #[derive(serde::Deserialize)]
+    struct Schedule {
+        maneuvers: Vec<crate::maneuver::Maneuver>,
+    }
+    let parsed: Schedule = toml::from_str(&schedule_toml(&maneuvers)).unwrap();
+    assert_eq!(parsed.maneuvers, maneuvers);
+
+    // Simulated transfer ends on the target orbit
+    let mut cubesat = cubesat::CubeSat::new()
+        .with_orbit_type("numerical")
+        .with_orbit_parameters(vec![
+            ("semi-major axis", RADIUS_EARTH + 500_000.0),
+            ("inclination", 0.0),
+            ("argument of periapsis", 0.0),
+            ("longitude of ascending node", 0.0),
+        ])
+        .with_position(0.0, 0.0, 0.0)
+        .with_velocity(0.0, 0.0, 0.0)
+        .with_acceleration(0.0, 0.0, 0.0)
+        .with_time(0.0, transfer.duration + 3000.0, 1.0)
+        .with_mass(4.0)
+        .with_propulsion(0.2, 0.3, 0.5, 0.1, 220.0)
+        .with_transfer(&transfer, "Hohmann", 0.0);
+    while cubesat.time.unwrap().now < cubesat.time.unwrap().end {
+        cubesat.update_orbit().unwrap();
+        crate::maneuver::update_maneuvers(&mut cubesat).unwrap();
+        if cubesat.time.unwrap().now > transfer.duration + 10.0 {
+            let r = cubesat.pos.unwrap().abs();
+            assert!((r - (RADIUS_EARTH + 800_000.0)).abs() < 2_000.0);
+        }
+        cubesat.iterate();
+    }
+}
+
+#[test]
+fn transfer_plan() {
+    // Planned from the TOML section into Isp-carrying maneuvers and a schedule
+    let plan: TransferPlan = toml::from_str(
+        "name = \"Raise\"\nkind = 0\nstart = 100.0\nisp = 220.0\n\
+         [target]\nsemi_major_axis = 7000000.0\n",
+    )
+    .unwrap();
+    let mut cubesat = cubesat::CubeSat::new()
+        .with_orbit_parameters(vec![
+            ("semi-major axis", 6_800_000.0),
+            ("inclination", 0.0),
+            ("argument of periapsis", 0.0),
+            ("longitude of ascending node", 0.0),
+        ])
+        .with_transfer_plan(plan);
+    cubesat.plan_transfers().unwrap();
+    let maneuvers = cubesat.maneuvers.as_ref().unwrap();
+    assert_eq!(maneuvers.len(), 2);
+    assert_eq!(maneuvers[0].time, 100.0);
+    assert!(maneuvers.iter().all(|m| m.isp == Some(220.0)));
+    assert_eq!(cubesat.history.summary()[0].0, "Raise delta-v");
+
+    // A bi-elliptic transfer needs its apoapsis
+    let plan: TransferPlan =
+        toml::from_str("kind = 1\n[target]\nsemi_major_axis = 7000000.0\n").unwrap();
+    let current = circular(6_800_000.0, 0.0);
+    assert!(matches!(
+        plan.plan(&current),
+        Err(CubeSatError::ConfigMissing(_))
+    ));
+}
+
+#[test]
+fn albedo_irradiance() {
+    let r = RADIUS_EARTH + 500_000.0;
+    let pos = vector::Vector3::new(r, 0.0, 0.0);
+    let nadir = vector::Vector3::new(-1.0, 0.0, 0.0);
+    let zenith = vector::Vector3::new(1.0, 0.0, 0.0);
+    let overhead = vector::Vector3::new(1.0, 0.0, 0.0);
+    let mut albedo = Albedo::new(0.3, 237.0);
+    albedo.resolution = 1.0;
+
+    // Infrared on a nadir plate: view factor (R / r)^2
+    let (reflected, emitted) = albedo.irradiance(&pos, &nadir, &overhead, 1361.0, 2451545.0);
+    let view = (RADIUS_EARTH / r).powi(2);
+    assert!((emitted / (237.0 * view) - 1.0).abs() < 0.01);
+    assert!(reflected > 0.5 * 0.3 * 1361.0 * view && reflected < 0.3 * 1361.0 * view);
+
+    // Nothing on a zenith plate, no reflection over the night side
+    assert_eq!(
+        albedo.irradiance(&pos, &zenith, &overhead, 1361.0, 2451545.0),
+        (0.0, 0.0)
+    );
+    let (reflected, emitted) =
+        albedo.irradiance(&pos, &nadir, &overhead.negative(), 1361.0, 2451545.0);
+    assert_eq!(reflected, 0.0);
+    assert!(emitted > 0.0);
+}
+
+#[test]
+fn albedo_grid() {
+    let grid = ReflectivityGrid::from_file("src/tests/albedo.txt").unwrap();
+    assert_eq!(grid.value(45.0, 0.0), 0.6);
+    assert_eq!(grid.value(-45.0, 0.0), 0.2);
+    assert_eq!(grid.value(89.0, 179.0), 0.6);
+
+    // Over the equator, the mean of both hemispheres
+    let pos = vector::Vector3::new(RADIUS_EARTH + 500_000.0, 0.0, 0.0);
+    let nadir = vector::Vector3::new(-1.0, 0.0, 0.0);
+    let sun = vector::Vector3::new(1.0, 0.0, 0.0);
+    let mut gridded = Albedo::new(0.0, 0.0);
+    gridded.path = Some("src/tests/albedo.txt".to_string());
+    gridded.load().unwrap();
+    let constant = Albedo::new(0.4, 0.0);
+    let (a, _) = gridded.irradiance(&pos, &nadir, &sun, 1361.0, 2451545.0);
+    let (b, _) = constant.irradiance(&pos, &nadir, &sun, 1361.0, 2451545.0);
+    assert!((a / b - 1.0).abs() < 0.02);
+}
+
+#[test]
+fn true_anomaly_and_ground_station() {
+    // Eccentric orbit: periapsis at 0, apoapsis at 180 degrees
+    let mut params = circular(7_000_000.0, 20.0);
+    params.eccentricity = Some(0.1);
+    params.argument_of_periapsis = Some(30.0);
+    params.longitude_of_ascending_node = Some(0.0);
+    let period = params.period().unwrap();
+    for (t, expected) in [(0.1, 0.0), (0.5 * period, 180.0)] {
+        let (pos, vel) = crate::orbit::state_from_parameters(&params, t).unwrap();
+        let anomaly = true_anomaly(&pos, &vel);
+        assert!((anomaly - expected).abs() < 0.1 || (anomaly - expected).abs() > 359.9);
+    }
+
+    // Circular equatorial orbit is measured from the x axis
+    let r = 7_000_000.0;
+    let v = (CONST_MU / r).sqrt();
+    let pos = vector::Vector3::new(0.0, r, 0.0);
+    let vel = vector::Vector3::new(-v, 0.0, 0.0);
+    assert!((true_anomaly(&pos, &vel) - 90.0).abs() < 1e-9);
+    assert!((true_anomaly(&pos, &vel.negative()) - 270.0).abs() < 1e-9);
+
+    // Station straight below the satellite sees it at zenith
+    let station = GroundStation::new("Station", 10.0, 20.0);
+    let julian_date = time::J2000;
+    let below = station.position(julian_date);
+    let above = below.scale(r / below.abs());
+    assert!((station.elevation(&above, julian_date) - 90.0).abs() < 1e-6);
+    assert!(station.in_contact(&above, julian_date));
+    assert!(!station.in_contact(&above.negative(), julian_date));
+    let (latitude, longitude) = subsatellite_point(&above, julian_date);
+    assert!((latitude - 10.0).abs() < 1e-9);
+    assert!((longitude - 20.0).abs() < 1e-9);
+}
+
+#[test]
+fn orbit_errors() {
+    // Missing and invalid elements
+    let mut params = OrbitParameters::new();
+    params.set_semi_major_axis(7_000_000.0);
+    assert_eq!(
+        crate::orbit::state_from_parameters(&params, 0.0),
+        Err(CubeSatError::missing("inclination"))
+    );
+    params.set_inclination(0.0);
+    params.set_argument_of_periapsis(0.0);
+    params.set_longitude_of_ascending_node(0.0);
+    params.set_eccentricity(1.2);
+    assert!(matches!(
+        crate::orbit::state_from_parameters(&params, 0.0),
+        Err(CubeSatError::InvalidParameter(_))
+    ));
+
+    // Parametric orbit needs an eccentricity
+    let mut cubesat = cubesat::CubeSat::new()
+        .with_orbit_type("parametric")
+        .with_orbit_parameters(vec![
+            ("semi-major axis", 7_000_000.0),
+            ("inclination", 0.0),
+            ("argument of periapsis", 0.0),
+            ("longitude of ascending node", 0.0),
+            ("eccentricity", 0.0),
+        ])
+        .with_position(0.0, 0.0, 0.0)
+        .with_velocity(0.0, 0.0, 0.0)
+        .with_time(0.0, 1.0, 1.0);
+    assert!(matches!(
+        cubesat.update_orbit(),
+        Err(CubeSatError::InvalidParameter(_))
+    ));
+    cubesat.orbit_type = None;
+    assert_eq!(
+        cubesat.update_orbit(),
+        Err(CubeSatError::missing("orbit type"))
+    );
+}
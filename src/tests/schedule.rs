@@ -0,0 +1,158 @@
+#[allow(unused_imports)]
+use crate::schedule::*;
+
+#[allow(unused_imports)]
+use crate::error::CubeSatError;
+
+#[allow(unused_imports)]
+use crate::vector;
+
+#[test]
+fn condition_band() {
+    let band = Condition::new(ConditionKind::Latitude, Some(-10.0), Some(10.0));
+    assert!(band.within(0.0));
+    assert!(!band.within(10.5));
+
+    // Open and wrapping bands
+    let low = Condition::new(ConditionKind::Battery, None, Some(30.0));
+    assert!(low.within(-5.0));
+    assert!(!low.within(30.1));
+    let wrap = Condition::new(ConditionKind::Anomaly, Some(350.0), Some(10.0));
+    assert!(wrap.within(355.0));
+    assert!(wrap.within(5.0));
+    assert!(!wrap.within(180.0));
+}
+
+#[test]
+fn trigger_level_and_pulse() {
+    let mut level = Trigger::new(Logic::And, Vec::new(), None);
+    assert!(level.update(0.0, true));
+    assert!(!level.update(1.0, false));
+
+    // Pulse starts on the rising edge only
+    let mut pulse = Trigger::new(Logic::And, Vec::new(), Some(2.0));
+    assert!(pulse.update(0.0, true));
+    assert!(pulse.update(1.0, true));
+    assert!(!pulse.update(2.0, true));
+    assert!(!pulse.update(3.0, false));
+    assert!(pulse.update(4.0, true));
+}
+
+#[test]
+fn evaluate_conditions() {
+    let r = orbit::RADIUS_EARTH + 500_000.0;
+    let v = (orbit::CONST_MU / r).sqrt();
+    let mut cubesat = cubesat::CubeSat::new()
+        .with_time(0.0, 100.0, 1.0)
+        .with_position(0.0, r, 0.0)
+        .with_velocity(0.0, 0.0, v)
+        .with_sun(-1.0, 0.0, 0.0)
+        .with_eps(0.0, 10.0);
+    cubesat.eps.as_mut().unwrap().charge = 2.0;
+    cubesat.ground_stations = Some(vec![orbit::GroundStation::new("Kiruna", 67.9, 21.1)]);
+
+    // Sunlit, over the equator, low battery, within the time window
+    let eclipse = Condition::new(ConditionKind::Eclipse, None, None);
+    let sunlit = Condition::new(ConditionKind::Sunlit, None, None);
+    let equator = Condition::new(ConditionKind::Latitude, Some(-5.0), Some(5.0));
+    let battery = Condition::new(ConditionKind::Battery, None, Some(30.0));
+    let window = Condition::new(ConditionKind::Time, Some(0.0), Some(10.0));
+    let contact = Condition::new(ConditionKind::Contact, None, None);
+    assert!(!eclipse.evaluate(&cubesat));
+    assert!(sunlit.evaluate(&cubesat));
+    assert!(equator.evaluate(&cubesat));
+    assert!(battery.evaluate(&cubesat));
+    assert!(window.evaluate(&cubesat));
+    assert!(!contact.evaluate(&cubesat));
+
+    // AND needs all, OR needs one
+    let all = Trigger::new(Logic::And, vec![sunlit.clone(), contact.clone()], None);
+    let any = Trigger::new(Logic::Or, vec![sunlit, contact], None);
+    assert!(!all.evaluate(&cubesat));
+    assert!(any.evaluate(&cubesat));
+
+    // Over the north pole the equator band fails
+    cubesat.pos = Some(vector::Vector3::new(0.0, 0.0, r));
+    assert!(!equator.evaluate(&cubesat));
+}
+
+#[test]
+fn timeline() {
+    // Inline commands are merged with the file and sorted by time
+    let inline = Command::new(5.0, "Component", CommandKind::Auto, Vec::new());
+    let mut timeline = Timeline::new(vec![inline.clone()]);
+    timeline.path = Some("src/tests/timeline.csv".to_string());
+    timeline.load().unwrap();
+    timeline.load().unwrap();
+    let times: Vec<f64> = timeline.commands.iter().map(|c| c.time).collect();
+    assert_eq!(times, vec![5.0, 10.0, 30.0, 40.0]);
+    assert_eq!(timeline.commands[0], inline);
+    assert_eq!(timeline.commands[3].command, CommandKind::Mode);
+    assert_eq!(timeline.commands[3].describe(), "mode IDLE");
+
+    // Commands are handed out once, within half a step
+    assert!(timeline.due(0.0, 3.0).is_empty());
+    assert_eq!(timeline.due(3.6, 3.0).len(), 1);
+    assert_eq!(timeline.due(30.0, 3.0).len(), 2);
+    assert_eq!(timeline.due(30.0, 3.0).len(), 0);
+    assert_eq!(timeline.due(100.0, 3.0).len(), 1);
+}
+
+#[test]
+fn safe_mode_policy() {
+    let mut policy = SafeModePolicy::new(20.0, 40.0, 100.0);
+
+    // Enters at the lower threshold
+    assert!(!policy.update(false, 25.0, 0.0));
+    assert!(policy.update(false, 20.0, 10.0));
+
+    // Stays in between the thresholds and until the dwell has passed
+    assert!(policy.update(true, 30.0, 20.0));
+    assert!(policy.update(true, 45.0, 100.0));
+    assert!(!policy.update(true, 45.0, 110.0));
+    assert!(!policy.update(false, 30.0, 120.0));
+
+    // Everything is shed by default, except what is forced on
+    policy.force_on = vec!["Beacon".to_string()];
+    assert!(policy.sheds("Camera"));
+    assert!(!policy.sheds("Beacon"));
+    assert!(policy.forces("Beacon"));
+    policy.shed = Some(vec!["Camera".to_string()]);
+    assert!(!policy.sheds("Radio"));
+}
+
+#[test]
+fn timeline_errors() {
+    // Unknown commands point at their line and column
+    let text = "time, component, command\n10.0, Camera, on\n20.0, Camera, reboot\n";
+    assert_eq!(
+        Timeline::from_csv("plan.csv", text),
+        Err(CubeSatError::Parse {
+            path: "plan.csv".to_string(),
+            line: 3,
+            column: 15,
+            message: "unknown command 'reboot'".to_string(),
+        })
+    );
+
+    // A mistyped time is an error, not a skipped line
+    let text = "# Plan\ntime, component, command\n\n3O.0, Camera, on\n";
+    assert_eq!(
+        Timeline::from_csv("plan.csv", text),
+        Err(CubeSatError::Parse {
+            path: "plan.csv".to_string(),
+            line: 4,
+            column: 1,
+            message: "invalid time '3O.0'".to_string(),
+        })
+    );
+    assert!(matches!(
+        Timeline::from_csv("plan.csv", "10.0, Camera\n"),
+        Err(CubeSatError::Parse { line: 1, .. })
+    ));
+
+    // Missing files are reported, not fatal
+    let mut timeline = Timeline::new(Vec::new());
+    timeline.path = Some("src/tests/missing.csv".to_string());
+    assert!(matches!(timeline.load(), Err(CubeSatError::Io { .. })));
+}
@@ -0,0 +1,181 @@
+#[allow(unused_imports)]
+use crate::thermal::*;
+
+#[allow(unused_imports)]
+use crate::component;
+
+#[allow(unused_imports)]
+use crate::error::CubeSatError;
+
+#[test]
+fn conduction() {
+    // Two blocks settle at the capacity-weighted mean temperature
+    let mut thermal = Thermal::new(vec![
+        Node::new("Hot", 100.0, 50.0),
+        Node::new("Cold", 300.0, 10.0),
+    ]);
+    thermal.conduction.push(Link::new("Hot", "Cold", 0.5));
+
+    // Large step is split into stable sub-steps
+    assert!(thermal.stable_step() < 1000.0);
+    for _ in 0..100 {
+        thermal.update(&[0.0, 0.0], 1000.0);
+    }
+    assert!((thermal.temperature("Hot") - 20.0).abs() < 1e-6);
+    assert!((thermal.temperature("Cold") - 20.0).abs() < 1e-6);
+
+    // Radiative exchange also conserves energy
+    let mut thermal = Thermal::new(vec![
+        Node::new("Hot", 100.0, 50.0),
+        Node::new("Cold", 100.0, 10.0),
+    ]);
+    thermal.radiation.push(Link::new("Hot", "Cold", 0.01));
+    thermal.update(&[0.0, 0.0], 60.0);
+    let hot = thermal.temperature("Hot");
+    assert!(hot < 50.0);
+    assert!((hot + thermal.temperature("Cold") - 60.0).abs() < 1e-9);
+}
+
+#[test]
+fn radiative_equilibrium() {
+    // Insulated plate facing the sun
+    let (absorptivity, emissivity) = (0.9, 0.8);
+    let mut thermal = Thermal::new(vec![Node::face(
+        "Plate",
+        50.0,
+        (1.0, 0.0, 0.0),
+        0.01,
+        absorptivity,
+        emissivity,
+    )]);
+    let absorbed = absorptivity * 1361.0 * 0.01;
+    for _ in 0..1000 {
+        thermal.update(&[absorbed], 60.0);
+    }
+    let expected = equilibrium_temperature(absorptivity, emissivity, 1361.0);
+    assert!((thermal.temperature("Plate") - expected).abs() < 0.01);
+    assert!(expected > 125.0 && expected < 140.0);
+}
+
+#[test]
+fn update_thermal_sun_and_eclipse() {
+    // Sun face and battery coupled to it, the component heats the battery
+    let mut face = Node::face("+X", 50.0, (1.0, 0.0, 0.0), 0.01, 0.9, 0.8);
+    face.solar_panels.push("Solar panel".to_string());
+    let mut battery = Node::new("Battery", 100.0, 20.0);
+    battery.dissipation.push("Component".to_string());
+    let mut thermal = Thermal::new(vec![face, battery]);
+    thermal.conduction.push(Link::new("+X", "Battery", 0.1));
+    thermal.battery = Some("Battery".to_string());
+
+    let mut cubesat = cubesat::CubeSat::new()
+        .with_time(0.0, 100.0, 10.0)
+        .with_position(7_000_000.0, 0.0, 0.0)
+        .with_rotation(0.0, 0.0, 0.0)
+        .with_sun(-1.0, 0.0, 0.0)
+        .with_solar_panels(vec![(1.0, 0.0, 0.0)], 1.0)
+        .with_battery(1.0, 1.0)
+        .with_component("Component", -1.0, None, None, None)
+        .with_thermal(thermal);
+
+    // Sunlit face absorbs, battery dissipates the component power
+    let heat = external_heat(&cubesat);
+    let flux = cubesat.solar_flux();
+    assert!((heat[0] - 0.9 * flux * 0.01).abs() < 1e-9);
+    assert_eq!(heat[1], 1.0);
+
+    cubesat.update_thermal();
+    let face = cubesat.thermal.as_ref().unwrap().temperature("+X");
+    let battery = cubesat.thermal.as_ref().unwrap().temperature("Battery");
+    assert!(face > 20.0);
+    assert!(battery > 20.0);
+
+    // Temperatures reach the battery and solar cell models
+    assert_eq!(cubesat.battery.as_ref().unwrap().temperature, battery);
+    assert_eq!(cubesat.solar_panels.as_ref().unwrap()[0].temperature, face);
+    cubesat.save_history();
+    assert_eq!(cubesat.history.column("+X temperature").unwrap()[0], face);
+
+    // In eclipse the face only radiates
+    cubesat.pos = Some(vector::Vector3::new(-7_000_000.0, 0.0, 0.0));
+    assert!(cubesat.in_eclipse());
+    assert_eq!(external_heat(&cubesat)[0], 0.0);
+    cubesat.update_thermal();
+    assert!(cubesat.thermal.as_ref().unwrap().temperature("+X") < face);
+}
+
+#[test]
+fn heater_in_eclipse() {
+    // Cold battery in eclipse, heated through its own node
+    let mut battery = Node::new("Battery", 100.0, -1.0);
+    battery.emissivity = 0.8;
+    battery.area = 0.005;
+    let mut cubesat = cubesat::CubeSat::new()
+        .with_time(0.0, 100.0, 10.0)
+        .with_position(-7_000_000.0, 0.0, 0.0)
+        .with_rotation(0.0, 0.0, 0.0)
+        .with_sun(-1.0, 0.0, 0.0)
+        .with_thermal(Thermal::new(vec![battery]))
+        .with_heater("Battery heater", "Battery", -2.0, 0.0, 5.0);
+
+    // Thermostat switches on and the consumption includes the heater
+    cubesat.update_thermal();
+    assert_eq!(cubesat.get_power_consumption(), -2.0);
+    assert_eq!(external_heat(&cubesat), vec![2.0]);
+    for _ in 0..100 {
+        cubesat.update_thermal();
+    }
+    let temperature = cubesat.thermal.as_ref().unwrap().temperature("Battery");
+    assert!(temperature > -1.0 && temperature < 6.0);
+
+    // Duty cycle between the heater power and the radiated power
+    let duty_cycle = cubesat.heaters.as_ref().unwrap()[0].duty_cycle();
+    assert!(duty_cycle > 0.0 && duty_cycle < 1.0);
+    cubesat.summarize();
+    let summary = cubesat.history.summary();
+    assert!(summary
+        .iter()
+        .any(|(q, v)| q == "Battery heater duty cycle" && v.ends_with('%')));
+}
+
+#[test]
+fn validate_nodes() {
+    let mut thermal = Thermal::new(vec![
+        Node::new("Bus", 100.0, 20.0),
+        Node::new("Panel", 50.0, 20.0),
+    ]);
+    thermal.conduction.push(Link::new("Bus", "Panel", 0.5));
+    thermal.battery = Some("Bus".to_string());
+    assert_eq!(thermal.validate(), Ok(()));
+
+    // A misspelled link end is reported instead of panicking later
+    thermal.radiation.push(Link::new("Bus", "Pannel", 0.01));
+    assert_eq!(
+        thermal.validate(),
+        Err(CubeSatError::InvalidParameter(
+            "unknown thermal node 'Pannel'".to_string()
+        ))
+    );
+    thermal.radiation.clear();
+
+    // Capacities must be positive and links finite
+    thermal.nodes[1].capacity = 0.0;
+    assert!(matches!(
+        thermal.validate(),
+        Err(CubeSatError::InvalidParameter(_))
+    ));
+    thermal.nodes[1].capacity = 50.0;
+    thermal.conduction[0].value = f64::NAN;
+    assert!(matches!(
+        thermal.validate(),
+        Err(CubeSatError::InvalidParameter(_))
+    ));
+    thermal.conduction[0].value = 0.5;
+
+    // Names must be unique
+    thermal.nodes.push(Node::new("Bus", 10.0, 20.0));
+    assert!(matches!(
+        thermal.validate(),
+        Err(CubeSatError::InvalidParameter(_))
+    ));
+}
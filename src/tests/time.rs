@@ -18,3 +18,13 @@ fn hundred_steps() {
         time.next();
     }
 }
+
+#[test]
+fn julian_date() {
+    let mut time = Time::new(0.0, 100.0, 1.0);
+    assert_eq!(time.julian_date(0.0), J2000);
+    assert_eq!(time.julian_date(DAY), J2000 + 1.0);
+
+    time.epoch = J2000 + CENTURY;
+    assert_eq!(time.julian_date(0.0), J2000 + CENTURY);
+}
@@ -275,3 +275,59 @@ fn rot_xyz() {
         pos_unit_x
     );
 }
+
+#[test]
+fn add_sub_scale() {
+    let a = Vector3::new(1.0, 2.0, 3.0);
+    let b = Vector3::new(3.0, 2.0, 1.0);
+    assert_eq!(a.add(&b), Vector3::new(4.0, 4.0, 4.0));
+    assert_eq!(a.sub(&b), Vector3::new(-2.0, 0.0, 2.0));
+    assert_eq!(a.scale(2.0), Vector3::new(2.0, 4.0, 6.0));
+}
+
+#[test]
+fn cross() {
+    let unit_x = Vector3::new(1.0, 0.0, 0.0);
+    let unit_y = Vector3::new(0.0, 1.0, 0.0);
+    let unit_z = Vector3::new(0.0, 0.0, 1.0);
+    assert_eq!(unit_x.cross(&unit_y), unit_z);
+    assert_eq!(unit_y.cross(&unit_z), unit_x);
+    assert_eq!(unit_z.cross(&unit_x), unit_y);
+    assert_eq!(unit_y.cross(&unit_x), unit_z.negative());
+}
+
+#[test]
+fn unit() {
+    let origin = Vector3::origin();
+    let vector = Vector3::new(3.0, 0.0, 4.0);
+    assert_eq!(origin.unit(), origin);
+    assert!(vector.unit().sub(&Vector3::new(0.6, 0.0, 0.8)).abs() < 1e-12);
+    assert!((vector.unit().abs() - 1.0).abs() < 1e-12);
+}
+
+#[test]
+fn to_body_to_inertial() {
+    let rotation = Vector3::new(30.0, 45.0, 60.0);
+    let vector = Vector3::new(1.0, 2.0, 3.0);
+    let back = vector.to_body(&rotation).to_inertial(&rotation);
+    assert!(back.sub(&vector).abs() < 1e-12);
+
+    let rotation = Vector3::new(0.0, 0.0, 90.0);
+    let body = Vector3::new(1.0, 0.0, 0.0).to_body(&rotation);
+    assert!(body.sub(&Vector3::new(0.0, -1.0, 0.0)).abs() < 1e-12);
+}
+
+#[test]
+fn rot_axis() {
+    let x = Vector3::new(1.0, 0.0, 0.0);
+    let z = Vector3::new(0.0, 0.0, 2.0);
+    let close = |a: Vector3, b: Vector3| a.sub(&b).abs() < 1e-12;
+    assert!(close(
+        x.rot_axis(&z, std::f64::consts::FRAC_PI_2),
+        Vector3::new(0.0, 1.0, 0.0)
+    ));
+    assert!(close(x.rot_axis(&x, 1.0), x));
+    // Same as the fixed-axis rotation
+    let v = Vector3::new(0.3, -0.4, 0.5);
+    assert!(close(v.rot_axis(&z, 0.7), v.rot_z(0.7)));
+}
@@ -0,0 +1,332 @@
+#[path = "./tests/thermal.rs"]
+mod tests;
+
+use crate::cubesat;
+use crate::error::CubeSatError;
+use crate::vector;
+
+use serde::Deserialize;
+
+pub static STEFAN_BOLTZMANN: f64 = 5.670_374e-8; // [W/(m^2*K^4)]
+pub static KELVIN: f64 = 273.15; // [K] at 0 °C
+pub static SPACE_TEMPERATURE: f64 = 2.7; // [K]
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Node {
+    pub name: String,
+    pub capacity: f64, // [J/K]
+    #[serde(default = "Node::default_temperature")]
+    pub temperature: f64, // [°C]
+
+    // External surface, faces only
+    pub normal: Option<vector::Vector3>, // Body frame
+    #[serde(default = "Node::default_zero")]
+    pub area: f64, // [m^2]
+    #[serde(default = "Node::default_zero")]
+    pub absorptivity: f64, // Solar
+    #[serde(default = "Node::default_zero")]
+    pub emissivity: f64, // Infrared
+
+    // Heat sources and temperature sinks by name
+    #[serde(default = "Node::default_names")]
+    pub dissipation: Vec<String>, // Consumers whose power ends up as heat here
+    #[serde(default = "Node::default_names")]
+    pub solar_panels: Vec<String>, // Panels at this temperature
+}
+
+impl Node {
+    #[allow(unused)]
+    pub fn new(name: &str, capacity: f64, temperature: f64) -> Self {
+        Node {
+            name: name.to_string(),
+            capacity,
+            temperature,
+            normal: None,
+            area: 0.0,
+            absorptivity: 0.0,
+            emissivity: 0.0,
+            dissipation: Vec::new(),
+            solar_panels: Vec::new(),
+        }
+    }
+
+    #[allow(unused)]
+    pub fn face(
+        name: &str,
+        capacity: f64,
+        normal: (f64, f64, f64),
+        area: f64,
+        absorptivity: f64,
+        emissivity: f64,
+    ) -> Self {
+        let mut node = Node::new(name, capacity, Node::default_temperature());
+        node.normal = Some(vector::Vector3::new(normal.0, normal.1, normal.2));
+        node.area = area;
+        node.absorptivity = absorptivity;
+        node.emissivity = emissivity;
+        node
+    }
+
+    pub fn kelvin(&self) -> f64 {
+        self.temperature + KELVIN
+    }
+
+    // Default values for deserialization
+    fn default_temperature() -> f64 {
+        20.0
+    }
+    fn default_zero() -> f64 {
+        0.0
+    }
+    fn default_names() -> Vec<String> {
+        Vec::new()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Link {
+    pub nodes: (String, String),
+    pub value: f64, // [W/K] conductance, or [m^2] radiative exchange factor
+}
+
+impl Link {
+    #[allow(unused)]
+    pub fn new(a: &str, b: &str, value: f64) -> Self {
+        Link {
+            nodes: (a.to_string(), b.to_string()),
+            value,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Thermal {
+    pub nodes: Vec<Node>,
+    #[serde(default = "Thermal::default_links")]
+    pub conduction: Vec<Link>,
+    #[serde(default = "Thermal::default_links")]
+    pub radiation: Vec<Link>,
+    pub battery: Option<String>, // Node of the battery
+}
+
+impl Thermal {
+    #[allow(unused)]
+    pub fn new(nodes: Vec<Node>) -> Self {
+        Thermal {
+            nodes,
+            conduction: Vec::new(),
+            radiation: Vec::new(),
+            battery: None,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), CubeSatError> {
+        // Every name used by links and the battery is a node, and only once
+        for (i, node) in self.nodes.iter().enumerate() {
+            if self.nodes[..i].iter().any(|n| n.name == node.name) {
+                return Err(CubeSatError::InvalidParameter(format!(
+                    "duplicate thermal node '{}'",
+                    node.name
+                )));
+            }
+            // A node without capacity needs infinitely many sub-steps
+            if !node.capacity.is_finite() || node.capacity <= 0.0 {
+                return Err(CubeSatError::InvalidParameter(format!(
+                    "thermal node '{}' capacity {} J/K is not positive",
+                    node.name, node.capacity
+                )));
+            }
+        }
+        let links = self.conduction.iter().chain(self.radiation.iter());
+        if let Some(link) = links
+            .clone()
+            .find(|l| !l.value.is_finite() || l.value < 0.0)
+        {
+            return Err(CubeSatError::InvalidParameter(format!(
+                "thermal link '{}'-'{}' value {} is not finite and non-negative",
+                link.nodes.0, link.nodes.1, link.value
+            )));
+        }
+        let mut names: Vec<&String> = links.flat_map(|l| [&l.nodes.0, &l.nodes.1]).collect();
+        names.extend(self.battery.iter());
+        match names.into_iter().find(|n| !self.contains(n)) {
+            Some(name) => Err(CubeSatError::InvalidParameter(format!(
+                "unknown thermal node '{name}'"
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.nodes.iter().any(|n| n.name == name)
+    }
+
+    pub fn index(&self, name: &str) -> usize {
+        self.nodes
+            .iter()
+            .position(|n| n.name == name)
+            .unwrap_or_else(|| panic!("Unknown thermal node '{name}'!"))
+    }
+
+    pub fn temperature(&self, name: &str) -> f64 {
+        self.nodes[self.index(name)].temperature
+    }
+
+    pub fn heat_flows(&self, external: &[f64]) -> Vec<f64> {
+        // Net heat into every node [W]: external loads, links and emission to space
+        let mut flows = external.to_vec();
+        for link in &self.conduction {
+            let (a, b) = (self.index(&link.nodes.0), self.index(&link.nodes.1));
+            let q = link.value * (self.nodes[b].temperature - self.nodes[a].temperature);
+            flows[a] += q;
+            flows[b] -= q;
+        }
+        for link in &self.radiation {
+            let (a, b) = (self.index(&link.nodes.0), self.index(&link.nodes.1));
+            let q = STEFAN_BOLTZMANN
+                * link.value
+                * (self.nodes[b].kelvin().powi(4) - self.nodes[a].kelvin().powi(4));
+            flows[a] += q;
+            flows[b] -= q;
+        }
+        for (i, node) in self.nodes.iter().enumerate() {
+            flows[i] -= STEFAN_BOLTZMANN
+                * node.emissivity
+                * node.area
+                * (node.kelvin().powi(4) - SPACE_TEMPERATURE.powi(4));
+        }
+        flows
+    }
+
+    pub fn stable_step(&self) -> f64 {
+        // Explicit Euler is stable below C / (sum of linearised conductances)
+        let mut conductance = vec![0.0; self.nodes.len()];
+        for link in &self.conduction {
+            conductance[self.index(&link.nodes.0)] += link.value;
+            conductance[self.index(&link.nodes.1)] += link.value;
+        }
+        for link in &self.radiation {
+            let (a, b) = (self.index(&link.nodes.0), self.index(&link.nodes.1));
+            let t = self.nodes[a].kelvin().max(self.nodes[b].kelvin());
+            let g = 4.0 * STEFAN_BOLTZMANN * link.value * t.powi(3);
+            conductance[a] += g;
+            conductance[b] += g;
+        }
+        self.nodes
+            .iter()
+            .zip(conductance)
+            .map(|(n, g)| {
+                let g = g + 4.0 * STEFAN_BOLTZMANN * n.emissivity * n.area * n.kelvin().powi(3);
+                match g > 0.0 {
+                    true => 0.5 * n.capacity / g,
+                    false => f64::INFINITY,
+                }
+            })
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    pub fn update(&mut self, external: &[f64], timestep: f64) {
+        // Explicit Euler, split into stable sub-steps
+        let steps = (timestep / self.stable_step()).ceil().max(1.0) as usize;
+        let dt = timestep / steps as f64;
+        for _ in 0..steps {
+            let flows = self.heat_flows(external);
+            for (node, q) in self.nodes.iter_mut().zip(flows) {
+                node.temperature += q * dt / node.capacity;
+            }
+        }
+    }
+
+    // Default values for deserialization
+    fn default_links() -> Vec<Link> {
+        Vec::new()
+    }
+}
+
+pub fn external_heat(cubesat: &cubesat::CubeSat) -> Vec<f64> {
+    // Guards
+    let thermal = cubesat.thermal.as_ref().expect("No thermal model is set!");
+    let pos = &cubesat.pos.expect("No position vector is set!");
+    let rot = &cubesat.rot.expect("No rotation is set!");
+    let sun = &cubesat.sun.expect("No sun is set!");
+    let time = cubesat.time.as_ref().expect("No time is set!");
+    let eclipse = cubesat.in_eclipse();
+    let flux = cubesat.solar_flux();
+    let julian_date = time.julian_date(time.now);
+    let consumers = cubesat.get_power_consumers();
+
+    thermal
+        .nodes
+        .iter()
+        .map(|node| {
+            // Dissipation of the consumers on this node
+            let mut heat: f64 = consumers
+                .iter()
+                .filter(|(name, _)| node.dissipation.contains(name))
+                .map(|(_, c)| -c)
+                .sum();
+
+            // Powered heaters dissipate on the node they sense
+            if let Some(heaters) = &cubesat.heaters {
+                heat -= heaters
+                    .iter()
+                    .filter(|h| h.node == node.name)
+                    .filter(|h| consumers.iter().any(|(name, _)| *name == h.name))
+                    .map(|h| h.power_consumption())
+                    .sum::<f64>();
+            }
+
+            // Absorbed sunlight, albedo and Earth infrared on faces
+            if let Some(normal) = &node.normal {
+                let normal = normal.to_inertial(rot);
+                if !eclipse {
+                    let cos = normal.dot(&sun.negative()) / (normal.abs() * sun.abs());
+                    heat += node.absorptivity * flux * node.area * cos.max(0.0);
+                }
+                if let Some(albedo) = &cubesat.albedo {
+                    let (reflected, emitted) =
+                        albedo.irradiance(pos, &normal, &sun.negative(), flux, julian_date);
+                    heat += node.area * (node.absorptivity * reflected + node.emissivity * emitted);
+                }
+            }
+            heat
+        })
+        .collect()
+}
+
+pub fn update_thermal(cubesat: &mut cubesat::CubeSat) {
+    // Guards
+    let step = cubesat.time.as_ref().expect("No time is set!").step;
+    let external = external_heat(cubesat);
+    let thermal = cubesat.thermal.as_mut().expect("No thermal model is set!");
+
+    thermal.update(&external, step);
+
+    // Temperatures drive the battery and solar cell models
+    if let (Some(name), Some(battery)) = (&thermal.battery, cubesat.battery.as_mut()) {
+        battery.temperature = thermal.temperature(name);
+    }
+    if let Some(panels) = cubesat.solar_panels.as_mut() {
+        for node in &thermal.nodes {
+            for panel in panels
+                .iter_mut()
+                .filter(|p| node.solar_panels.contains(&p.name))
+            {
+                panel.temperature = node.temperature;
+            }
+        }
+    }
+
+    // Thermostats switch on the new temperatures
+    if let Some(heaters) = cubesat.heaters.as_mut() {
+        for heater in heaters {
+            heater.update(thermal.temperature(&heater.node), step);
+        }
+    }
+}
+
+#[allow(unused)]
+pub fn equilibrium_temperature(absorptivity: f64, emissivity: f64, flux: f64) -> f64 {
+    // Flat plate facing the sun, insulated back side [°C]
+    (absorptivity * flux / (emissivity * STEFAN_BOLTZMANN)).powf(0.25) - KELVIN
+}
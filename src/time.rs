@@ -7,6 +7,9 @@ pub static SECOND: f64 = 1.0;
 pub static MINUTE: f64 = 60.0 * SECOND;
 pub static HOUR: f64 = 60.0 * MINUTE;
 pub static DAY: f64 = 24.0 * HOUR;
+pub static YEAR: f64 = 365.25 * DAY;
+pub static J2000: f64 = 2_451_545.0; // [JD]
+pub static CENTURY: f64 = 36_525.0; // [days]
 
 #[derive(Debug, PartialEq, Copy, Clone, Deserialize)]
 pub struct Time {
@@ -14,6 +17,8 @@ pub struct Time {
     pub step: f64,
     pub start: f64,
     pub end: f64,
+    #[serde(default = "Time::default_epoch")]
+    pub epoch: f64, // [JD] at time 0
 }
 
 impl Time {
@@ -24,10 +29,20 @@ impl Time {
             step: time_step,
             start: start_time,
             end: end_time, // 1 Day
+            epoch: Time::default_epoch(),
         }
     }
 
+    pub fn julian_date(&self, time: f64) -> f64 {
+        self.epoch + time / DAY
+    }
+
     pub fn next(&mut self) {
         self.now += self.step;
     }
+
+    // Default values for deserialization
+    fn default_epoch() -> f64 {
+        J2000
+    }
 }
@@ -35,13 +35,7 @@ impl Vector3 {
     pub fn angle_to(&self, other: &Vector3) -> f64 {
         let term1 = self.dot(other);
         let term2 = self.abs() * other.abs();
-        let mut frac = term1 / term2;
-        if frac < -1.0 {
-            frac = -1.0;
-        }
-        if frac > 1.0 {
-            frac = 1.0;
-        }
+        let frac = (term1 / term2).clamp(-1.0, 1.0);
         frac.acos() // Angle
     }
 
@@ -50,7 +44,7 @@ impl Vector3 {
         let mut y = radians.cos() * self.y - radians.sin() * self.z;
         let mut z = radians.sin() * self.y + radians.cos() * self.z;
         // Round very-near-zero to zero
-        let epsilon = std::f64::EPSILON;
+        let epsilon = f64::EPSILON;
         if x.abs() < epsilon {
             x = 0.0;
         }
@@ -68,7 +62,7 @@ impl Vector3 {
         let mut y = self.y;
         let mut z = -radians.sin() * self.x + radians.cos() * self.z;
         // Round very-near-zero to zero
-        let epsilon = std::f64::EPSILON;
+        let epsilon = f64::EPSILON;
         if x.abs() < epsilon {
             x = 0.0;
         }
@@ -86,7 +80,7 @@ impl Vector3 {
         let mut y = radians.sin() * self.x + radians.cos() * self.y;
         let mut z = self.z;
         // Round very-near-zero to zero
-        let epsilon = std::f64::EPSILON;
+        let epsilon = f64::EPSILON;
         if x.abs() < epsilon {
             x = 0.0;
         }
@@ -106,4 +100,68 @@ impl Vector3 {
             z: -self.z,
         }
     }
+
+    pub fn add(&self, other: &Vector3) -> Self {
+        Vector3 {
+            x: self.x + other.x,
+            y: self.y + other.y,
+            z: self.z + other.z,
+        }
+    }
+
+    pub fn sub(&self, other: &Vector3) -> Self {
+        Vector3 {
+            x: self.x - other.x,
+            y: self.y - other.y,
+            z: self.z - other.z,
+        }
+    }
+
+    pub fn scale(&self, factor: f64) -> Self {
+        Vector3 {
+            x: self.x * factor,
+            y: self.y * factor,
+            z: self.z * factor,
+        }
+    }
+
+    pub fn cross(&self, other: &Vector3) -> Self {
+        Vector3 {
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x,
+        }
+    }
+
+    pub fn unit(&self) -> Self {
+        let abs = self.abs();
+        if abs == 0.0 {
+            return *self;
+        }
+        self.scale(1.0 / abs)
+    }
+
+    // Body frame -> inertial frame, rotation in degrees (same order as SolarPanel)
+    pub fn to_inertial(self, rotation: &Vector3) -> Self {
+        let ang_to_rad = std::f64::consts::PI / 180.0;
+        self.rot_x(rotation.x * ang_to_rad)
+            .rot_y(rotation.y * ang_to_rad)
+            .rot_z(rotation.z * ang_to_rad)
+    }
+
+    // Inertial frame -> body frame, rotation in degrees
+    pub fn to_body(self, rotation: &Vector3) -> Self {
+        let ang_to_rad = std::f64::consts::PI / 180.0;
+        self.rot_z(-rotation.z * ang_to_rad)
+            .rot_y(-rotation.y * ang_to_rad)
+            .rot_x(-rotation.x * ang_to_rad)
+    }
+
+    // Rodrigues' rotation about an arbitrary axis
+    pub fn rot_axis(self, axis: &Vector3, radians: f64) -> Self {
+        let k = axis.unit();
+        self.scale(radians.cos())
+            .add(&k.cross(&self).scale(radians.sin()))
+            .add(&k.scale(k.dot(&self) * (1.0 - radians.cos())))
+    }
 }